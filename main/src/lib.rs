@@ -4,7 +4,22 @@ use cosmwasm_std::{
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use sha2::Sha256;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("File transfer already exists")]
+    DuplicateTransfer {},
+
+    #[error("Fee percentage must be between 0 and 10000 (100.00%)")]
+    FeeOutOfRange {},
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
@@ -57,7 +72,7 @@ pub fn instantiate(
     _env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     let state = State {
         file_transfers: vec![],
         admin: info.sender.clone(),
@@ -73,7 +88,7 @@ pub fn execute(
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::RecordTransfer { file_hash, recipient } => 
             record_transfer(deps, env, info, file_hash, recipient),
@@ -90,14 +105,14 @@ fn record_transfer(
     info: MessageInfo,
     file_hash: String,
     recipient: String,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     let mut state: State = from_json(&deps.storage.get(b"state")
         .ok_or(StdError::not_found("State"))?)?;
 
     let recipient = deps.api.addr_validate(&recipient)?;
 
     if state.file_transfers.iter().any(|t| t.file_hash == file_hash && t.recipient == recipient) {
-        return Err(StdError::generic_err("File transfer already exists"));
+        return Err(ContractError::DuplicateTransfer {});
     }
 
     let transfer_amount = info.funds.iter()
@@ -128,12 +143,12 @@ fn withdraw_fees(
     _env: Env,
     info: MessageInfo,
     amount: Uint128,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     let state: State = from_json(&deps.storage.get(b"state")
         .ok_or(StdError::not_found("State"))?)?;
 
     if info.sender != state.admin {
-        return Err(StdError::generic_err("Unauthorized"));
+        return Err(ContractError::Unauthorized {});
     }
 
     let bank_msg = BankMsg::Send {
@@ -154,16 +169,16 @@ fn set_fee_percentage(
     deps: DepsMut,
     info: MessageInfo,
     percentage: Uint128,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     let mut state: State = from_json(&deps.storage.get(b"state")
         .ok_or(StdError::not_found("State"))?)?;
 
     if info.sender != state.admin {
-        return Err(StdError::generic_err("Unauthorized"));
+        return Err(ContractError::Unauthorized {});
     }
 
     if percentage > Uint128::new(10000) {
-        return Err(StdError::generic_err("Fee percentage must be between 0 and 10000 (100.00%)"));
+        return Err(ContractError::FeeOutOfRange {});
     }
 
     state.fee_percentage = percentage;
@@ -202,4 +217,70 @@ fn query_fee_percentage(deps: Deps) -> StdResult<Uint128> {
     let state: State = from_json(&deps.storage.get(b"state")
         .ok_or(StdError::not_found("State"))?)?;
     Ok(state.fee_percentage)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage};
+    use cosmwasm_std::{coins, OwnedDeps};
+
+    type TestDeps = OwnedDeps<MockStorage, MockApi, MockQuerier>;
+
+    fn setup() -> TestDeps {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg { fee_percentage: Uint128::new(100) };
+        instantiate(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+        deps
+    }
+
+    fn exec(deps: &mut TestDeps, sender: &str, msg: ExecuteMsg) -> Result<Response, ContractError> {
+        execute(deps.as_mut(), mock_env(), mock_info(sender, &coins(10000, "usei")), msg)
+    }
+
+    fn record_msg(file_hash: &str) -> ExecuteMsg {
+        ExecuteMsg::RecordTransfer {
+            file_hash: file_hash.to_string(),
+            recipient: "recipient".to_string(),
+        }
+    }
+
+    #[test]
+    fn duplicate_transfer_is_a_typed_error() {
+        let mut deps = setup();
+        exec(&mut deps, "sender", record_msg("file")).unwrap();
+        let err = exec(&mut deps, "sender", record_msg("file")).unwrap_err();
+        assert!(matches!(err, ContractError::DuplicateTransfer {}));
+    }
+
+    #[test]
+    fn non_admin_is_unauthorized() {
+        let mut deps = setup();
+        let withdraw = ExecuteMsg::WithdrawFees { amount: Uint128::new(1) };
+        let err = exec(&mut deps, "stranger", withdraw).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let set = ExecuteMsg::SetFeePercentage { percentage: Uint128::new(50) };
+        let err = exec(&mut deps, "stranger", set).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+    }
+
+    #[test]
+    fn fee_above_100_percent_is_out_of_range() {
+        let mut deps = setup();
+        let set = ExecuteMsg::SetFeePercentage { percentage: Uint128::new(10001) };
+        let err = exec(&mut deps, "admin", set).unwrap_err();
+        assert!(matches!(err, ContractError::FeeOutOfRange {}));
+
+        let set = ExecuteMsg::SetFeePercentage { percentage: Uint128::new(10000) };
+        exec(&mut deps, "admin", set).unwrap();
+        assert_eq!(query_fee_percentage(deps.as_ref()).unwrap(), Uint128::new(10000));
+    }
+
+    #[test]
+    fn storage_errors_surface_as_std() {
+        let mut deps = mock_dependencies();
+        let err = execute(deps.as_mut(), mock_env(), mock_info("sender", &[]), record_msg("file")).unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::NotFound { .. })));
+    }
+}
@@ -1,4 +1,4 @@
-/**
+/*!
  Developer 🏗️: @virjilakrum Baturalp Güvenç
  @title ZK File Transfer Smart Contract for SEI Blockchain
  @dev This contract enables secure file transfers using Zero-Knowledge proofs on the SEI blockchain
@@ -26,27 +26,36 @@
 
  use cosmwasm_std::{
     entry_point, to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
-    StdError, Uint128, CosmosMsg, BankMsg, QueryRequest, BankQuery, BalanceResponse, Addr,
+    StdError, Uint128, CosmosMsg, BankMsg, QueryRequest, BankQuery, BalanceResponse, Addr, Event, Order, Storage, Coin, Decimal,
+    Reply, SubMsg, SubMsgResult,
 };
+use cw_storage_plus::{Bound, Item, Map};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use bellman::{Circuit, ConstraintSystem, SynthesisError};
 use bls12_381::{Bls12, Scalar};
 use rand::rngs::OsRng;
+use sha2::{Digest, Sha256};
+use compact_storage::{CompactItem, CompactMap};
 
-// ZK Proof implementation
-mod zk_proof {
+// ZK Proof implementation; public so off-chain provers can build proofs against
+// the exact circuit the contract verifies
+pub mod zk_proof {
     use super::*;
 
     pub struct FileTransferCircuit {
         pub file_hash: Option<[u8; 32]>,
         pub recipient: Option<[u8; 32]>,
         pub secret: Option<[u8; 32]>,
+        pub metadata: Option<[u8; 32]>,
+        pub metadata_commitment: Option<[u8; 32]>,
+        pub block_height: Option<u64>,
+        pub epoch: Option<u64>,
     }
 
-    impl Circuit<Bls12> for FileTransferCircuit {
-        fn synthesize<CS: ConstraintSystem<Bls12>>(
+    impl Circuit<Scalar> for FileTransferCircuit {
+        fn synthesize<CS: ConstraintSystem<Scalar>>(
             self,
             cs: &mut CS
         ) -> Result<(), SynthesisError> {
@@ -81,6 +90,98 @@ mod zk_proof {
                 |lc| lc + file_hash + recipient,
             );
 
+            // A zero file hash has no inverse, so it can't satisfy this constraint
+            let file_hash_inv = cs.alloc(
+                || "file hash inverse",
+                || {
+                    let file_hash = self.file_hash.map(|h| Scalar::from_bytes(&h).unwrap())
+                        .ok_or(SynthesisError::AssignmentMissing)?;
+                    Option::from(file_hash.invert()).ok_or(SynthesisError::DivisionByZero)
+                }
+            )?;
+
+            cs.enforce(
+                || "file hash nonzero constraint",
+                |lc| lc + file_hash,
+                |lc| lc + file_hash_inv,
+                |lc| lc + CS::one(),
+            );
+
+            // Public commitment to private file metadata (e.g. a size range),
+            // binding it to the secret without revealing it
+            let metadata_commitment = cs.alloc_input(
+                || "metadata commitment",
+                || {
+                    self.metadata_commitment.map(|m| Scalar::from_bytes(&m).unwrap())
+                        .ok_or(SynthesisError::AssignmentMissing)
+                }
+            )?;
+
+            let metadata = cs.alloc(
+                || "metadata",
+                || {
+                    self.metadata.map(|m| Scalar::from_bytes(&m).unwrap())
+                        .ok_or(SynthesisError::AssignmentMissing)
+                }
+            )?;
+
+            cs.enforce(
+                || "metadata commitment constraint",
+                |lc| lc + metadata,
+                |lc| lc + secret,
+                |lc| lc + metadata_commitment,
+            );
+
+            // Block height the proof was generated at, bounding its replay window.
+            // Squaring it ties the input into the constraint system.
+            let block_height = cs.alloc_input(
+                || "block height",
+                || {
+                    self.block_height.map(Scalar::from)
+                        .ok_or(SynthesisError::AssignmentMissing)
+                }
+            )?;
+
+            let block_height_sq = cs.alloc(
+                || "block height squared",
+                || {
+                    self.block_height.map(|h| Scalar::from(h) * Scalar::from(h))
+                        .ok_or(SynthesisError::AssignmentMissing)
+                }
+            )?;
+
+            cs.enforce(
+                || "block height constraint",
+                |lc| lc + block_height,
+                |lc| lc + block_height,
+                |lc| lc + block_height_sq,
+            );
+
+            // Key epoch the proof was generated under; bumping the contract's epoch
+            // invalidates every proof from earlier ones. Squared like the block height.
+            let epoch = cs.alloc_input(
+                || "epoch",
+                || {
+                    self.epoch.map(Scalar::from)
+                        .ok_or(SynthesisError::AssignmentMissing)
+                }
+            )?;
+
+            let epoch_sq = cs.alloc(
+                || "epoch squared",
+                || {
+                    self.epoch.map(|e| Scalar::from(e) * Scalar::from(e))
+                        .ok_or(SynthesisError::AssignmentMissing)
+                }
+            )?;
+
+            cs.enforce(
+                || "epoch constraint",
+                |lc| lc + epoch,
+                |lc| lc + epoch,
+                |lc| lc + epoch_sq,
+            );
+
             Ok(())
         }
     }
@@ -88,27 +189,38 @@ mod zk_proof {
     pub struct Proof(pub Vec<u8>);
 
     impl Proof {
-        pub fn new(file_hash: [u8; 32], recipient: [u8; 32], secret: [u8; 32]) -> Self {
-            use bellman::groth16::{
-                create_random_proof, generate_random_parameters,
-                prepare_verifying_key, verify_proof,
-            };
+        pub fn new(
+            file_hash: [u8; 32],
+            recipient: [u8; 32],
+            secret: [u8; 32],
+            metadata: [u8; 32],
+            metadata_commitment: [u8; 32],
+            block_height: u64,
+            epoch: u64,
+        ) -> Self {
+            use bellman::groth16::{create_random_proof, generate_random_parameters};
 
             let params = {
                 let c = FileTransferCircuit {
                     file_hash: Some(file_hash),
                     recipient: Some(recipient),
                     secret: Some(secret),
+                    metadata: Some(metadata),
+                    metadata_commitment: Some(metadata_commitment),
+                    block_height: Some(block_height),
+                    epoch: Some(epoch),
                 };
                 generate_random_parameters::<Bls12, _, _>(c, &mut OsRng).unwrap()
             };
 
-            let pvk = prepare_verifying_key(&params.vk);
-
             let c = FileTransferCircuit {
                 file_hash: Some(file_hash),
                 recipient: Some(recipient),
                 secret: Some(secret),
+                metadata: Some(metadata),
+                metadata_commitment: Some(metadata_commitment),
+                block_height: Some(block_height),
+                epoch: Some(epoch),
             };
 
             let proof = create_random_proof(c, &params, &mut OsRng).unwrap();
@@ -119,30 +231,233 @@ mod zk_proof {
             Proof(proof_bytes)
         }
 
-        pub fn verify(&self, file_hash: &[u8], recipient: &[u8]) -> bool {
+        pub fn verify(
+            &self,
+            file_hash: &[u8],
+            recipient: &[u8],
+            metadata_commitment: &[u8; 32],
+            block_height: u64,
+            epoch: u64,
+            verifying_key: Option<&[u8]>,
+        ) -> bool {
             use bellman::groth16::{prepare_verifying_key, verify_proof, Proof};
 
-            let params = {
-                let c = FileTransferCircuit {
-                    file_hash: None,
-                    recipient: None,
-                    secret: None,
-                };
-                bellman::groth16::generate_random_parameters::<Bls12, _, _>(c, &mut OsRng).unwrap()
+            // Prefer the verifying key committed on-chain over freshly generated parameters
+            let vk = match verifying_key {
+                Some(bytes) => match read_verifying_key(bytes) {
+                    Some(vk) => vk,
+                    None => return false,
+                },
+                None => {
+                    let c = FileTransferCircuit {
+                        file_hash: None,
+                        recipient: None,
+                        secret: None,
+                        metadata: None,
+                        metadata_commitment: None,
+                        block_height: None,
+                        epoch: None,
+                    };
+                    bellman::groth16::generate_random_parameters::<Bls12, _, _>(c, &mut OsRng).unwrap().vk
+                }
             };
 
-            let pvk = prepare_verifying_key(&params.vk);
+            let pvk = prepare_verifying_key(&vk);
 
-            let proof = Proof::read(&mut &self.0[..]).unwrap();
+            let proof = match Proof::read(&mut &self.0[..]) {
+                Ok(proof) => proof,
+                Err(_) => return false,
+            };
 
-            let inputs = [
-                Scalar::from_bytes(&file_hash[..32].try_into().unwrap()).unwrap(),
-                Scalar::from_bytes(&recipient[..32].try_into().unwrap()).unwrap(),
-            ];
+            let inputs = match public_inputs(file_hash, recipient, metadata_commitment, block_height, epoch) {
+                Some(inputs) => inputs,
+                None => return false,
+            };
 
             verify_proof(&pvk, &proof, &inputs).is_ok()
         }
     }
+
+    // Parse a serialized Groth16 verifying key for this circuit
+    pub fn read_verifying_key(bytes: &[u8]) -> Option<bellman::groth16::VerifyingKey<Bls12>> {
+        bellman::groth16::VerifyingKey::read(bytes).ok()
+    }
+
+    // Field elements the circuit's public inputs are derived as: the first 32 bytes
+    // of the file hash and recipient inputs and the commitment as little-endian
+    // scalars, then the block height and epoch; None if any of them isn't a canonical scalar
+    pub fn public_inputs(
+        file_hash: &[u8],
+        recipient: &[u8],
+        metadata_commitment: &[u8; 32],
+        block_height: u64,
+        epoch: u64,
+    ) -> Option<[Scalar; 5]> {
+        let scalar = |bytes: &[u8]| -> Option<Scalar> {
+            Option::from(Scalar::from_bytes(bytes.get(..32)?.try_into().ok()?))
+        };
+        Some([
+            scalar(file_hash)?,
+            scalar(recipient)?,
+            scalar(metadata_commitment)?,
+            Scalar::from(block_height),
+            Scalar::from(epoch),
+        ])
+    }
+}
+
+// Typed storage for the config and transfers, mirroring the Item/Map calls used on them.
+// Values are written as JSON by default, byte-compatible with cw-storage-plus, or as
+// MessagePack with the `msgpack` feature, which keeps the growing State and per-transfer
+// entries smaller. Reads accept either format, so toggling the feature between builds
+// never strands values already on disk.
+mod compact_storage {
+    use super::*;
+    use serde::de::DeserializeOwned;
+    use std::marker::PhantomData;
+
+    pub fn encode<T: Serialize>(data: &T) -> StdResult<Vec<u8>> {
+        if cfg!(feature = "msgpack") {
+            to_msgpack(data)
+        } else {
+            cosmwasm_std::to_json_vec(data)
+        }
+    }
+
+    // Human-readable mode, since cosmwasm-std types such as Binary refuse to serialize
+    // in any other; fields are keyed by name so added Option fields stay decodable
+    pub fn to_msgpack<T: Serialize>(data: &T) -> StdResult<Vec<u8>> {
+        let mut bytes = vec![];
+        let mut serializer = rmp_serde::Serializer::new(&mut bytes)
+            .with_struct_map()
+            .with_human_readable();
+        data.serialize(&mut serializer)
+            .map_err(|e| StdError::serialize_err(std::any::type_name::<T>(), e))?;
+        Ok(bytes)
+    }
+
+    // The first byte is the format marker: a JSON object opens with '{', which MessagePack
+    // encodes as the integer 123 and never as the start of a map
+    pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> StdResult<T> {
+        match bytes.first() {
+            Some(b'{') => cosmwasm_std::from_json(bytes),
+            _ => {
+                let mut deserializer = rmp_serde::Deserializer::from_read_ref(bytes).with_human_readable();
+                T::deserialize(&mut deserializer).map_err(|e| StdError::parse_err(std::any::type_name::<T>(), e))
+            }
+        }
+    }
+
+    pub struct CompactItem<T> {
+        key: &'static [u8],
+        data: PhantomData<T>,
+    }
+
+    impl<T: Serialize + DeserializeOwned> CompactItem<T> {
+        pub const fn new(key: &'static str) -> Self {
+            CompactItem {
+                key: key.as_bytes(),
+                data: PhantomData,
+            }
+        }
+
+        pub fn load(&self, storage: &dyn Storage) -> StdResult<T> {
+            self.may_load(storage)?
+                .ok_or_else(|| StdError::not_found(std::any::type_name::<T>()))
+        }
+
+        pub fn may_load(&self, storage: &dyn Storage) -> StdResult<Option<T>> {
+            storage.get(self.key).map(|bytes| decode(&bytes)).transpose()
+        }
+
+        pub fn save(&self, storage: &mut dyn Storage, data: &T) -> StdResult<()> {
+            storage.set(self.key, &encode(data)?);
+            Ok(())
+        }
+    }
+
+    // Keys follow the cw-storage-plus layout: length-prefixed namespace, then the big-endian id
+    pub struct CompactMap<T> {
+        namespace: &'static [u8],
+        data: PhantomData<T>,
+    }
+
+    impl<T: Serialize + DeserializeOwned> CompactMap<T> {
+        pub const fn new(namespace: &'static str) -> Self {
+            CompactMap {
+                namespace: namespace.as_bytes(),
+                data: PhantomData,
+            }
+        }
+
+        fn prefix(&self) -> Vec<u8> {
+            let mut prefix = (self.namespace.len() as u16).to_be_bytes().to_vec();
+            prefix.extend_from_slice(self.namespace);
+            prefix
+        }
+
+        fn key(&self, id: u64) -> Vec<u8> {
+            let mut key = self.prefix();
+            key.extend_from_slice(&id.to_be_bytes());
+            key
+        }
+
+        pub fn load(&self, storage: &dyn Storage, id: u64) -> StdResult<T> {
+            self.may_load(storage, id)?
+                .ok_or_else(|| StdError::not_found(std::any::type_name::<T>()))
+        }
+
+        pub fn may_load(&self, storage: &dyn Storage, id: u64) -> StdResult<Option<T>> {
+            storage.get(&self.key(id)).map(|bytes| decode(&bytes)).transpose()
+        }
+
+        pub fn save(&self, storage: &mut dyn Storage, id: u64, data: &T) -> StdResult<()> {
+            storage.set(&self.key(id), &encode(data)?);
+            Ok(())
+        }
+
+        pub fn remove(&self, storage: &mut dyn Storage, id: u64) {
+            storage.remove(&self.key(id));
+        }
+
+        pub fn range<'a>(
+            &self,
+            storage: &'a dyn Storage,
+            min: Option<Bound<'a, u64>>,
+            max: Option<Bound<'a, u64>>,
+            order: Order,
+        ) -> Box<dyn Iterator<Item = StdResult<(u64, T)>> + 'a> {
+            let prefix = self.prefix();
+            // Ids are fixed-width, so appending a zero byte yields the next possible key
+            let start = match min {
+                Some(Bound::Inclusive((id, _))) => self.key(id),
+                Some(Bound::Exclusive((id, _))) => [self.key(id), vec![0]].concat(),
+                Some(Bound::InclusiveRaw(raw)) => [prefix.clone(), raw].concat(),
+                Some(Bound::ExclusiveRaw(raw)) => [prefix.clone(), raw, vec![0]].concat(),
+                None => prefix.clone(),
+            };
+            let end = match max {
+                Some(Bound::Inclusive((id, _))) => [self.key(id), vec![0]].concat(),
+                Some(Bound::Exclusive((id, _))) => self.key(id),
+                Some(Bound::InclusiveRaw(raw)) => [prefix.clone(), raw, vec![0]].concat(),
+                Some(Bound::ExclusiveRaw(raw)) => [prefix.clone(), raw].concat(),
+                None => {
+                    let mut end = prefix.clone();
+                    if let Some(last) = end.last_mut() {
+                        *last += 1;
+                    }
+                    end
+                }
+            };
+            let prefix_len = prefix.len();
+            Box::new(storage.range(Some(&start), Some(&end), order).map(move |(key, value)| {
+                let id: [u8; 8] = key[prefix_len..]
+                    .try_into()
+                    .map_err(|_| StdError::generic_err("Invalid transfer key"))?;
+                Ok((u64::from_be_bytes(id), decode(&value)?))
+            }))
+        }
+    }
 }
 
 #[derive(Error, Debug)]
@@ -161,216 +476,4407 @@ pub enum ContractError {
 
     #[error("Insufficient funds")]
     InsufficientFunds {},
+
+    #[error("Event namespace must be between 1 and {max} characters")]
+    InvalidEventNamespace { max: usize },
+
+    #[error("Withdrawal destination is not whitelisted")]
+    DestinationNotWhitelisted {},
+
+    #[error("File transfer not found")]
+    TransferNotFound {},
+
+    #[error("File transfer already revoked")]
+    AlreadyRevoked {},
+
+    #[error("Transfer cannot move from {from:?} to {to:?}")]
+    InvalidStatusTransition { from: TransferStatus, to: TransferStatus },
+
+    #[error("Fee splits must sum to at most 10000 basis points")]
+    InvalidFeeSplits {},
+
+    #[error("Recipient could not be resolved to an address")]
+    UnresolvableRecipient {},
+
+    #[error("TTL exceeds the maximum of {max} seconds")]
+    TtlTooLong { max: u64 },
+
+    #[error("Metadata commitment must be 32 bytes")]
+    InvalidMetadataCommitment {},
+
+    #[error("Unexpected funds in denom {denom}")]
+    UnexpectedFunds { denom: String },
+
+    #[error("Fee increases must be announced with ProposeFeePercentage")]
+    FeeIncreaseRequiresNotice {},
+
+    #[error("At least one accepted denom is required")]
+    NoAcceptedDenoms {},
+
+    #[error("Proof was generated outside the allowed block window")]
+    StaleProof {},
+
+    #[error("Recipient has not opted in to receiving transfers")]
+    RecipientNotOptedIn {},
+
+    #[error("Approval threshold must be between 1 and the number of admins")]
+    InvalidThreshold {},
+
+    #[error("Pending operation not found")]
+    OperationNotFound {},
+
+    #[error("Operation already approved by this admin")]
+    AlreadyApproved {},
+
+    #[error("Admin has been active within the inactivity period")]
+    AdminStillActive {},
+
+    #[error("Duplicate (file_hash, recipient) at batch index {index}")]
+    DuplicateInBatch { index: usize },
+
+    #[error("Batch item amounts must add up to the attached funds")]
+    BatchFundsMismatch {},
+
+    #[error("Wrapped key exceeds {max} bytes")]
+    WrappedKeyTooLong { max: usize },
+
+    #[error("Amount must be greater than zero")]
+    ZeroAmount {},
+
+    #[error("File size exceeds the configured maximum")]
+    FileTooLarge {},
+
+    #[error("Recipient set must not be empty")]
+    EmptyRecipientSet {},
+
+    #[error("Invalid metadata signature")]
+    InvalidSignature {},
+
+    #[error("Denom is not a fee denom")]
+    UnsupportedDenom {},
+
+    #[error("No escrowed shares are reclaimable")]
+    NothingToReclaim {},
+
+    #[error("Recording is paused")]
+    Paused {},
+
+    #[error("Unknown coupon code")]
+    InvalidCoupon {},
+
+    #[error("Coupon code has already been used")]
+    CouponAlreadyUsed {},
+
+    #[error("Too soon since the last proof verification")]
+    TooSoon {},
+
+    #[error("Only live, unacknowledged transfers with an expiry can be extended")]
+    TransferNotExtendable {},
+
+    #[error("Feature {feature} is disabled for this deployment")]
+    FeatureDisabled { feature: String },
+
+    #[error("Fee recipient cannot be the contract itself")]
+    InvalidFeeRecipient {},
+
+    #[error("Too many recipients for a single record")]
+    TooManyRecipients {},
+
+    #[error("Transfer amount is below the {min} minimum for {denom}")]
+    BelowMinimumAmount { denom: String, min: Uint128 },
+
+    #[error("Current value does not match the expected value")]
+    Conflict {},
+
+    #[error("Invalid denom")]
+    InvalidDenom {},
+
+    #[error("Batch item {index} records a file that is already being recorded")]
+    FileLocked { index: usize },
+
+    #[error("A minimum fee of {amount} {denom} must be attached")]
+    MinFeeNotMet { denom: String, amount: Uint128 },
+
+    #[error("File hash is blocked")]
+    FileHashBlocked {},
+
+    #[error("Memo exceeds {max} bytes")]
+    MemoTooLong { max: usize },
+
+    #[error("Fees paid within the window would exceed the spending cap of {cap}")]
+    SpendCapExceeded { cap: Uint128 },
+
+    #[error("Recipient must be an address with the {expected} prefix")]
+    InvalidRecipientPrefix { expected: String },
 }
 
 // Contract state
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
-    file_transfers: Vec<FileTransfer>,
     admin: String,
     fee_percentage: Uint128,
+    event_namespace: String,
+    fee_recipient: String,
+    withdraw_whitelist: Vec<String>,
+    allow_rerecord: bool,
+    fee_splits: Vec<(Addr, u16)>,
+    name_service: Option<Addr>,
+    max_ttl_seconds: Option<u64>,
+    free_transfer_quota: u32,
+    fee_increase_delay: u64,
+    pending_fee: Option<PendingFee>,
+    verbose_events: bool,
+    accepted_denoms: Vec<String>,
+    proof_freshness_window: Option<u64>,
+    opt_in_required: bool,
+    admins: Vec<String>,
+    admin_threshold: u32,
+    recovery_admin: Option<String>,
+    admin_inactivity_period: u64,
+    last_admin_action: u64,
+    price_oracle: Option<Addr>,
+    minimal_events: bool,
+    max_file_size: Option<u64>,
+    burn_percentage: Uint128,
+    query_scan_limit: u32,
+    fee_ramp: Option<FeeRamp>,
+    verification_policy: VerificationPolicy,
+    paused: bool,
+    auto_pause_threshold: u32,
+    auto_pause_window: u64,
+    min_verify_interval: u64,
+    verify_interval_per_sender: bool,
+    features: Features,
+    fallback_recipient: Option<Addr>,
+    max_recipients: u32,
+    verifier_contract: Option<Addr>,
+    fee_mode: FeeMode,
+    min_fee: Option<Coin>,
+    verifying_key_hash: Option<Binary>,
+    query_limits: QueryLimits,
+    current_epoch: u64,
+    spend_cap: Option<SpendCap>,
+    auto_prune: bool,
+    auto_prune_limit: u32,
+    required_prefix: Option<String>,
 }
 
+// A fee increase announced ahead of time; the old rate applies until effective_at
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct FileTransfer {
-    file_hash: String,
-    sender: String,
-    recipient: String,
-    timestamp: u64,
-    transfer_fee: Uint128,
+pub struct PendingFee {
+    percentage: Uint128,
+    effective_at: u64,
 }
 
-// Messages that can be sent to the contract
+// Senders and payment denoms whose records skip proof verification; empty by default
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct VerificationPolicy {
+    trusted_senders: Vec<Addr>,
+    trusted_denoms: Vec<String>,
+}
+
+// A linear move from `from` to `target` between the `start` and `end` timestamps
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
-pub enum ExecuteMsg {
-    RecordTransfer {
-        file_hash: String,
-        recipient: String,
-        zk_proof: Vec<u8>,
-    },
-    WithdrawFees {
-        amount: Uint128,
-    },
-    SetFeePercentage {
-        percentage: Uint128,
-    },
+pub struct FeeRamp {
+    from: Uint128,
+    target: Uint128,
+    start: u64,
+    end: u64,
 }
 
-// Query messages
+const DEFAULT_EVENT_NAMESPACE: &str = "zk_file_transfer";
+const MAX_EVENT_NAMESPACE_LEN: usize = 64;
+const MAX_RECENT_TRANSFERS: u32 = 50;
+const MAX_FEE_LEADERBOARD: usize = 50;
+// Decimal exponent fee totals are normalized to, and the one assumed for unconfigured denoms
+const NORMALIZED_EXPONENT: u32 = 18;
+const DEFAULT_DENOM_EXPONENT: u32 = 6;
+const MAX_BATCH_QUERY_IDS: usize = 100;
+const MAX_WRAPPED_KEY_LEN: usize = 512;
+const MAX_MEMO_LEN: usize = 256;
+const DEFAULT_PAGE_LIMIT: u32 = 30;
+const MAX_PAGE_LIMIT: u32 = 100;
+const DEFAULT_QUERY_SCAN_LIMIT: u32 = 1000;
+const DEFAULT_MAX_RECIPIENTS: u32 = 20;
+// How long past its deadline an unclaimed escrow must sit before it can be swept
+const ESCROW_ABANDON_PERIOD: u64 = 90 * 24 * 60 * 60;
+const MAX_TRANSFER_HISTORY: usize = 20;
+// Expired transfers each record may prune when auto-prune is on, by default and at most
+const DEFAULT_AUTO_PRUNE_LIMIT: u32 = 10;
+const MAX_AUTO_PRUNE_LIMIT: u32 = 50;
+
+// Rough gas model for record_transfer, surfaced by EstimateRecordGas; revisit these
+// whenever the record path gains storage writes or verification work. Per transfer
+// covers the indexes, event log entry, fee leaderboard, spend window and treasury
+// balance query, plus auto-pruning at MAX_AUTO_PRUNE_LIMIT
+const GAS_RECORD_BASE: u64 = 100_000;
+const GAS_RECORD_PER_TRANSFER: u64 = 250_000 + MAX_AUTO_PRUNE_LIMIT as u64 * GAS_PRUNE_PER_TRANSFER;
+const GAS_PRUNE_PER_TRANSFER: u64 = 15_000;
+const GAS_PROOF_VERIFY: u64 = 2_000_000;
+
+// Most fees (in the common normalized unit) one sender may pay within a rolling window
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub struct SpendCap {
+    pub amount: Uint128,
+    pub window_seconds: u64,
+}
+
+// Result count used when a query's caller gives no limit, and the most it may ask for
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub struct QueryLimit {
+    pub default: u32,
+    pub max: u32,
+}
+
+impl QueryLimit {
+    fn clamp(&self, requested: Option<u32>) -> usize {
+        requested.unwrap_or(self.default).min(self.max) as usize
+    }
+}
+
+// Operator-tunable result caps per query family
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct QueryLimits {
+    pub transfers: QueryLimit,
+    pub recent_transfers: QueryLimit,
+    pub event_log: QueryLimit,
+    pub updated_since: QueryLimit,
+    pub export_snapshot: QueryLimit,
+}
+
+impl Default for QueryLimits {
+    fn default() -> Self {
+        let page = QueryLimit {
+            default: DEFAULT_PAGE_LIMIT,
+            max: MAX_PAGE_LIMIT,
+        };
+        QueryLimits {
+            transfers: page,
+            recent_transfers: QueryLimit {
+                default: MAX_RECENT_TRANSFERS,
+                max: MAX_RECENT_TRANSFERS,
+            },
+            event_log: page,
+            updated_since: page,
+            export_snapshot: page,
+        }
+    }
+}
+
+// How the fee relates to attached funds: taken out of them, or added on top of the
+// net amount so that attached = net + fee
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub enum QueryMsg {
-    GetFileTransfers {},
-    VerifyTransfer { file_hash: String, recipient: String },
-    GetContractBalance {},
-    GetFeePercentage {},
+pub enum FeeMode {
+    FromAmount,
+    OnTop,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct InstantiateMsg {
-    pub fee_percentage: Uint128,
+// Lifecycle of a transfer; Expired is derived from expires_at when read
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferStatus {
+    Recorded,
+    Acknowledged,
+    Revoked,
+    Expired,
 }
 
-// Contract instantiation
-#[entry_point]
-pub fn instantiate(
-    deps: DepsMut,
-    _env: Env,
-    info: MessageInfo,
-    msg: InstantiateMsg,
-) -> Result<Response, ContractError> {
-    let state = State {
-        file_transfers: vec![],
-        admin: info.sender.to_string(),
-        fee_percentage: msg.fee_percentage,
-    };
-    deps.storage.set(b"state", &to_json_binary(&state)?);
-    Ok(Response::default())
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FileTransfer {
+    file_hash: String,
+    sender: String,
+    recipient: String,
+    timestamp: u64,
+    transfer_fee: Uint128,
+    status: TransferStatus,
+    revoked_at: Option<u64>,
+    expires_at: Option<u64>,
+    metadata_commitment: Option<Binary>,
+    proof_hash: Binary,
+    proved_height: u64,
+    wrapped_key: Option<Binary>,
+    file_size: Option<u64>,
+    recipient_set_hash: Option<Binary>,
+    delivery_signature: Option<Binary>,
+    update_seq: u64,
+    visible_at: Option<u64>,
+    memo: Option<String>,
+    epoch: u64,
 }
 
-// Contract execution
-#[entry_point]
-pub fn execute(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    msg: ExecuteMsg,
-) -> Result<Response, ContractError> {
-    match msg {
-        ExecuteMsg::RecordTransfer {
-            file_hash,
-            recipient,
-            zk_proof,
-        } => record_transfer(deps, env, info, file_hash, recipient, zk_proof),
-        ExecuteMsg::WithdrawFees { amount } => withdraw_fees(deps, env, info, amount),
-        ExecuteMsg::SetFeePercentage { percentage } => set_fee_percentage(deps, info, percentage),
-    }
+// One entry of a BatchRecordTransfer; `amount` is its share of the attached funds
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BatchTransferItem {
+    pub file_hash: String,
+    pub recipient: String,
+    pub zk_proof: Vec<u8>,
+    pub ttl_seconds: Option<u64>,
+    pub metadata_commitment: Option<Binary>,
+    pub proved_height: Option<u64>,
+    pub wrapped_key: Option<Binary>,
+    pub file_size: Option<u64>,
+    pub metadata_signature: Option<Binary>,
+    pub coupon: Option<String>,
+    pub visible_at: Option<u64>,
+    pub memo: Option<String>,
+    pub amount: Uint128,
 }
 
-// Record file transfer function
-fn record_transfer(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
+// Caller-supplied fields shared by RecordTransfer and RecordTransferFor;
+// `recipient_set_hash` is set when the proof binds a multi-recipient set
+struct TransferParams {
+    recipient_set_hash: Option<[u8; 32]>,
     file_hash: String,
     recipient: String,
     zk_proof: Vec<u8>,
-) -> Result<Response, ContractError> {
-    let mut state: State = deps.storage.get(b"state").and_then(|data| Ok(cosmwasm_std::from_json(data)?)).unwrap();
+    ttl_seconds: Option<u64>,
+    metadata_commitment: Option<Binary>,
+    proved_height: Option<u64>,
+    wrapped_key: Option<Binary>,
+    file_size: Option<u64>,
+    metadata_signature: Option<Binary>,
+    coupon: Option<String>,
+    visible_at: Option<u64>,
+    memo: Option<String>,
+}
 
-    // Check if transfer already exists
-    if state.file_transfers.iter().any(|t| t.file_hash == file_hash && t.recipient == recipient) {
-        return Err(ContractError::DuplicateTransfer {});
-    }
+// Caller-supplied fields of RecordMultiRecipient
+struct MultiRecipientParams {
+    file_hash: String,
+    recipients: Vec<String>,
+    zk_proof: Vec<u8>,
+    proved_height: Option<u64>,
+    escrow_deadline: Option<u64>,
+}
 
-    // Verify ZK proof
-    let proof = zk_proof::Proof(zk_proof);
-    if !proof.verify(file_hash.as_bytes(), recipient.as_bytes()) {
-        return Err(ContractError::InvalidProof {});
-    }
+// Fields of UpdateConfig; None leaves the setting unchanged
+struct ConfigUpdate {
+    fee_percentage: Option<Uint128>,
+    paused: Option<bool>,
+    accepted_denoms: Option<Vec<String>>,
+    allow_rerecord: Option<bool>,
+    free_transfer_quota: Option<u32>,
+    fee_increase_delay: Option<u64>,
+    verbose_events: Option<bool>,
+    minimal_events: Option<bool>,
+    burn_percentage: Option<Uint128>,
+    query_scan_limit: Option<u32>,
+}
 
-    // Calculate transfer fee
-    let transfer_amount = info.funds.iter().find(|c| c.denom == "usei").map(|c| c.amount).unwrap_or_default();
-    let transfer_fee = transfer_amount * state.fee_percentage / Uint128::new(10000); // fee_percentage is in basis points
+// A recipient's net share of a multi-recipient transfer, released on acknowledgement
+// or reclaimable by the sender once the deadline has passed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Escrow {
+    transfer_id: u64,
+    sender: Addr,
+    denom: String,
+    amount: Uint128,
+    deadline: u64,
+}
 
-    let transfer = FileTransfer {
-        file_hash: file_hash.clone(),
-        sender: info.sender.to_string(),
-        recipient: recipient.clone(),
-        timestamp: env.block.time.seconds(),
-        transfer_fee,
-    };
-    state.file_transfers.push(transfer);
-    deps.storage.set(b"state", &to_json_binary(&state)?);
+// Contract configuration and transfers, keyed by a sequential transfer id
+const CONFIG: CompactItem<State> = CompactItem::new("config");
+const TRANSFERS: CompactMap<FileTransfer> = CompactMap::new("transfers");
+const NEXT_TRANSFER_ID: Item<u64> = Item::new("next_transfer_id");
 
-    Ok(Response::new()
-        .add_attribute("action", "record_transfer")
-        .add_attribute("file_hash", file_hash)
-        .add_attribute("recipient", recipient)
-        .add_attribute("transfer_fee", transfer_fee.to_string()))
-}
+// (file_hash, recipient) -> id of the latest transfer recorded under that key
+const TRANSFER_KEYS: Map<(&str, &str), u64> = Map::new("transfer_keys");
 
-// Withdraw fees function (admin only)
-fn withdraw_fees(
+// Pre-Item/Map layout: the whole state, transfers included, as one JSON blob
+const LEGACY_STATE_KEY: &[u8] = b"state";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+struct LegacyState {
+    file_transfers: Vec<LegacyFileTransfer>,
+    admin: String,
+    fee_percentage: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+struct LegacyFileTransfer {
+    file_hash: String,
+    sender: String,
+    recipient: String,
+    timestamp: u64,
+    transfer_fee: Uint128,
+}
+
+// Authz-style grants: (granter, grantee) -> whether grantee may record on granter's behalf
+const DELEGATIONS: Map<(&Addr, &Addr), bool> = Map::new("delegations");
+
+// Running total of fees paid per sender, normalized to NORMALIZED_EXPONENT decimals
+const FEES_PAID: Map<&Addr, Uint128> = Map::new("fees_paid");
+
+// Fee-exempt transfers used per sender against the free quota
+const FREE_TRANSFERS_USED: Map<&Addr, u32> = Map::new("free_transfers_used");
+
+// Negotiated fees per (sender, file_hash), replacing the computed fee for those records
+const FEE_OVERRIDES: Map<(&Addr, &str), Uint128> = Map::new("fee_overrides");
+
+// Fees retained by the contract (after stakeholder splits) per denom
+const ACCRUED_FEES: Map<&str, Uint128> = Map::new("accrued_fees");
+
+// Hashes of proofs already verified, bound to the public inputs they were verified against
+const VERIFIED_PROOFS: Map<&[u8], ()> = Map::new("verified_proofs");
+
+// Number of proofs that passed the pairing check over the contract's life
+const PROOFS_VERIFIED: Item<u64> = Item::new("proofs_verified");
+
+// Number of single or multi-recipient records turned away for an invalid proof
+const PROOFS_REJECTED: Item<u64> = Item::new("proofs_rejected");
+
+// Serialized Groth16 verifying key proofs are checked against, once committed by the admin
+const VERIFYING_KEY: Item<Binary> = Item::new("verifying_key");
+
+// Gross value attached to recorded transfers per denom
+const VOLUME: Map<&str, Uint128> = Map::new("volume");
+
+// One-time fee waiver codes; the flag is set once a code has been redeemed
+const COUPONS: Map<&str, bool> = Map::new("coupons");
+
+// Every denom a transfer has ever been paid in, even if no longer accepted
+const SEEN_DENOMS: Map<&str, ()> = Map::new("seen_denoms");
+
+// Number of distinct recipients a file_hash has been recorded for
+const FILE_HASH_COUNTS: Map<&str, u32> = Map::new("file_hash_counts");
+
+// Escrowed shares keyed by (file_hash, recipient), and the total held per denom
+const ESCROWS: Map<(&str, &str), Escrow> = Map::new("escrows");
+const ESCROWED: Map<&str, Uint128> = Map::new("escrowed");
+
+// Append-only log of significant actions for indexers without tx access
+const EVENT_LOG: Map<u64, EventRecord> = Map::new("event_log");
+const NEXT_EVENT_SEQ: Item<u64> = Item::new("next_event_seq");
+
+// Fees generated per recipient, and the top MAX_FEE_LEADERBOARD of them kept sorted on write
+const FEES_BY_RECIPIENT: Map<&str, Uint128> = Map::new("fees_by_recipient");
+const FEE_LEADERBOARD: Item<Vec<RecipientFees>> = Item::new("fee_leaderboard");
+
+// Timestamps of records inside the auto-pause window, oldest first
+const RECENT_RECORD_TIMES: Item<Vec<u64>> = Item::new("recent_record_times");
+
+// Normalized fees each sender paid inside the spend-cap window as (timestamp, fee), oldest first
+const SENDER_SPEND: Map<&Addr, Vec<(u64, Uint128)>> = Map::new("sender_spend");
+
+// Height of the last record that ran proof verification, globally and per sender
+const LAST_VERIFY_HEIGHT: Item<u64> = Item::new("last_verify_height");
+const SENDER_LAST_VERIFY_HEIGHT: Map<&Addr, u64> = Map::new("sender_last_verify_height");
+
+// Latest update sequence of each transfer, pointing at its id; sequences start at 1
+const TRANSFER_UPDATES: Map<u64, u64> = Map::new("transfer_updates");
+const NEXT_UPDATE_SEQ: Item<u64> = Item::new("next_update_seq");
+
+// Transfers with an expiry, keyed by (expires_at, id) so expired ones are found in order
+const TRANSFER_EXPIRIES: Map<(u64, u64), ()> = Map::new("transfer_expiries");
+
+// Amendments made to each transfer after it was recorded, oldest first
+const TRANSFER_HISTORY: Map<u64, Vec<Amendment>> = Map::new("transfer_history");
+
+// File hashes the admin has blocked from ever being recorded
+const BLOCKED_FILE_HASHES: Map<&str, ()> = Map::new("blocked_file_hashes");
+
+// Files with a batch record in progress; always cleared before the batch returns
+const FILE_LOCKS: Map<&str, ()> = Map::new("file_locks");
+
+// Escrow refunds awaiting their submessage reply, keyed by reply id
+const REFUND_REPLIES: Map<u64, Coin> = Map::new("refund_replies");
+const NEXT_REPLY_ID: Item<u64> = Item::new("next_reply_id");
+
+// Transfer ids per recipient, for newest-first lookups
+const RECIPIENT_TRANSFERS: Map<(&str, u64), ()> = Map::new("recipient_transfers");
+
+// Fee rate overrides per denom; unlisted denoms use the default fee_percentage
+const DENOM_FEE_PERCENTAGES: Map<&str, Uint128> = Map::new("denom_fee_percentages");
+
+// Decimal exponent per denom, used to add up fees paid in different denoms
+const DENOM_EXPONENTS: Map<&str, u32> = Map::new("denom_exponents");
+
+// Accrued fee level per denom at which records send the fees to the fee recipient
+const AUTO_WITHDRAW_THRESHOLDS: Map<&str, Uint128> = Map::new("auto_withdraw_thresholds");
+
+// Smallest amount a record may attach per denom; unset denoms have no floor
+const MIN_TRANSFER_AMOUNTS: Map<&str, Uint128> = Map::new("min_transfer_amounts");
+
+// Recipients who have opted in to appearing in transfers
+const OPTED_IN: Map<&Addr, bool> = Map::new("opted_in");
+
+// secp256k1 public keys accounts sign with: senders over metadata commitments,
+// recipients over delivery confirmations
+const ACCOUNT_PUBKEYS: Map<&Addr, Binary> = Map::new("sender_pubkeys");
+
+// Sensitive operations awaiting m-of-n admin approval
+const PENDING_OPERATIONS: Map<u64, PendingOperation> = Map::new("pending_operations");
+const NEXT_OPERATION_ID: Item<u64> = Item::new("next_operation_id");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminOperation {
+    WithdrawFees {
+        amount: Uint128,
+        to: Option<String>,
+        denom: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOperation {
+    operation: AdminOperation,
+    approvals: Vec<String>,
+}
+
+// Messages that can be sent to the contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    RecordTransfer {
+        file_hash: String,
+        recipient: String,
+        zk_proof: Vec<u8>,
+        ttl_seconds: Option<u64>,
+        metadata_commitment: Option<Binary>,
+        proved_height: Option<u64>,
+        wrapped_key: Option<Binary>,
+        file_size: Option<u64>,
+        metadata_signature: Option<Binary>,
+        coupon: Option<String>,
+        visible_at: Option<u64>,
+        memo: Option<String>,
+    },
+    WithdrawFees {
+        amount: Uint128,
+        to: Option<String>,
+        denom: Option<String>,
+    },
+    SetFeePercentage {
+        percentage: Uint128,
+    },
+    SetFeePercentageCas {
+        expected: Uint128,
+        new: Uint128,
+    },
+    SetFeeMode {
+        mode: FeeMode,
+    },
+    SetMinFee {
+        min_fee: Option<Coin>,
+    },
+    SetAutoWithdrawThreshold {
+        denom: String,
+        threshold: Uint128,
+    },
+    BlockFileHash {
+        file_hash: String,
+    },
+    UnblockFileHash {
+        file_hash: String,
+    },
+    Grant {
+        grantee: String,
+    },
+    Revoke {
+        grantee: String,
+    },
+    RecordTransferFor {
+        on_behalf_of: String,
+        file_hash: String,
+        recipient: String,
+        zk_proof: Vec<u8>,
+        ttl_seconds: Option<u64>,
+        metadata_commitment: Option<Binary>,
+        proved_height: Option<u64>,
+        wrapped_key: Option<Binary>,
+        file_size: Option<u64>,
+        metadata_signature: Option<Binary>,
+        coupon: Option<String>,
+        visible_at: Option<u64>,
+        memo: Option<String>,
+    },
+    AddWithdrawDestination {
+        address: String,
+    },
+    RemoveWithdrawDestination {
+        address: String,
+    },
+    RevokeTransfer {
+        file_hash: String,
+        recipient: String,
+    },
+    SetAllowRerecord {
+        allow: bool,
+    },
+    SetFeeSplits {
+        splits: Vec<(String, u16)>,
+    },
+    SetNameService {
+        address: Option<String>,
+    },
+    SetMaxTtl {
+        max_ttl_seconds: Option<u64>,
+    },
+    SetFreeTransferQuota {
+        quota: u32,
+    },
+    ProposeFeePercentage {
+        percentage: Uint128,
+    },
+    SetFeeIncreaseDelay {
+        seconds: u64,
+    },
+    SetVerboseEvents {
+        enabled: bool,
+    },
+    SetAcceptedDenoms {
+        denoms: Vec<String>,
+    },
+    SetProofFreshnessWindow {
+        blocks: Option<u64>,
+    },
+    OptIn {},
+    OptOut {},
+    SetOptInRequired {
+        required: bool,
+    },
+    SetAdmins {
+        admins: Vec<String>,
+        threshold: u32,
+    },
+    ApproveOperation {
+        id: u64,
+    },
+    SetRecoveryAdmin {
+        address: Option<String>,
+        inactivity_seconds: u64,
+    },
+    ClaimAdmin {},
+    SetDenomFeePercentage {
+        denom: String,
+        percentage: Option<Uint128>,
+    },
+    BatchRecordTransfer {
+        transfers: Vec<BatchTransferItem>,
+    },
+    RecordMultiRecipient {
+        file_hash: String,
+        recipients: Vec<String>,
+        zk_proof: Vec<u8>,
+        proved_height: Option<u64>,
+        escrow_deadline: Option<u64>,
+    },
+    SetPriceOracle {
+        address: Option<String>,
+    },
+    SetVerifierContract {
+        address: Option<String>,
+    },
+    SetVerifyingKey {
+        verifying_key: Binary,
+    },
+    BumpEpoch {},
+    SetMinimalEvents {
+        enabled: bool,
+    },
+    SetMaxFileSize {
+        max_file_size: Option<u64>,
+    },
+    SetBurnPercentage {
+        percentage: Uint128,
+    },
+    SetQueryScanLimit {
+        limit: u32,
+    },
+    SetQueryLimits {
+        limits: QueryLimits,
+    },
+    SetSpendCap {
+        cap: Option<SpendCap>,
+    },
+    SetAutoPrune {
+        enabled: bool,
+        limit: u32,
+    },
+    SetRequiredPrefix {
+        prefix: Option<String>,
+    },
+    SetMaxRecipients {
+        max: u32,
+    },
+    ScheduleFeeRamp {
+        target: Uint128,
+        start: u64,
+        end: u64,
+    },
+    AcknowledgeTransfer {
+        file_hash: String,
+    },
+    ReclaimEscrow {
+        file_hash: String,
+    },
+    SweepAbandonedEscrow {
+        limit: u32,
+    },
+    SetVerificationPolicy {
+        trusted_senders: Vec<String>,
+        trusted_denoms: Vec<String>,
+    },
+    SetPaused {
+        paused: bool,
+    },
+    SetAutoPause {
+        threshold: u32,
+        window_seconds: u64,
+    },
+    IssueCoupons {
+        codes: Vec<String>,
+    },
+    ConfirmDelivery {
+        file_hash: String,
+        signature: Binary,
+    },
+    SetMinVerifyInterval {
+        blocks: u64,
+        per_sender: bool,
+    },
+    SetDenomExponent {
+        denom: String,
+        exponent: Option<u32>,
+    },
+    SetMinTransferAmount {
+        denom: String,
+        amount: Option<Uint128>,
+    },
+    SetFeeOverride {
+        sender: String,
+        file_hash: String,
+        fee: Option<Uint128>,
+    },
+    ExtendTransfer {
+        file_hash: String,
+        recipient: String,
+        additional_seconds: u64,
+    },
+    SetFallbackRecipient {
+        address: Option<String>,
+    },
+    SetFeeRecipient {
+        address: String,
+    },
+    UpdateConfig {
+        fee_percentage: Option<Uint128>,
+        paused: Option<bool>,
+        accepted_denoms: Option<Vec<String>>,
+        allow_rerecord: Option<bool>,
+        free_transfer_quota: Option<u32>,
+        fee_increase_delay: Option<u64>,
+        verbose_events: Option<bool>,
+        minimal_events: Option<bool>,
+        burn_percentage: Option<Uint128>,
+        query_scan_limit: Option<u32>,
+    },
+    RegisterPubkey {
+        pubkey: Binary,
+    },
+}
+
+// Query messages
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    GetFileTransfers { page: Option<PageRequest> },
+    VerifyTransfer { file_hash: String, recipient: String },
+    VerifyTransferDetailed { file_hash: String, recipient: String },
+    GetContractBalance {},
+    GetFeePercentage {},
+    GetFeesPaidBy { sender: String },
+    QuoteFee { amount: Uint128, denom: Option<String> },
+    GetRevokedTransfers { page: Option<PageRequest> },
+    ReverifyTransfer { file_hash: String, recipient: String, zk_proof: Binary },
+    GetRecentTransfers { limit: u32 },
+    GetTreasury {},
+    GetTransfersByIds { ids: Vec<u64> },
+    GetVolume {},
+    QuoteFeeInDisplay { amount: Uint128, display_denom: String },
+    FileExists { file_hash: String },
+    GetFeeSchedule {},
+    GetTransfersByTimeRange { start: u64, end: u64, page: Option<PageRequest> },
+    GetLatestForRecipient { recipient: String },
+    GetTransfersByStatus { status: TransferStatus, limit: Option<u32> },
+    EstimateRecordGas {},
+    GetEventLog { start_after: Option<u64>, limit: Option<u32> },
+    TopRecipientsByFees { limit: u32 },
+    GetSeenDenoms {},
+    GetDeliveryProof { file_hash: String, recipient: String },
+    GetEffectiveFeeRate { amount: Option<Uint128>, denom: Option<String> },
+    GetUpdatedSince { seq: u64, limit: Option<u32> },
+    GetProofStats {},
+    GetRoles { address: String },
+    GetTransferHistory { file_hash: String, recipient: String },
+    ExportSnapshot { start_after: Option<u64>, limit: Option<u32> },
+    GetPublicInputs { file_hash: String, recipient: String },
+    GetDashboard { recent_limit: u32 },
+    GetVerifyingKeyHash {},
+    GetParameters {},
+    ComputeKey { file_hash: String, recipient: String },
+    GetContractBalances {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    pub fee_percentage: Uint128,
+    pub event_namespace: Option<String>,
+    pub fee_recipient: Option<String>,
+    pub allow_rerecord: Option<bool>,
+    pub name_service: Option<String>,
+    pub max_ttl_seconds: Option<u64>,
+    pub fee_increase_delay: Option<u64>,
+    pub accepted_denoms: Option<Vec<String>>,
+    pub initial_transfers: Option<Vec<FileTransfer>>,
+    pub features: Option<Features>,
+}
+
+// Optional behaviours a deployment opts into at instantiate; fixed afterwards
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Features {
+    pub pausable: bool,
+    pub escrow: bool,
+    pub allowlist: bool,
+    pub opt_in: bool,
+}
+
+impl Default for Features {
+    fn default() -> Self {
+        Features {
+            pausable: true,
+            escrow: true,
+            allowlist: true,
+            opt_in: false,
+        }
+    }
+}
+
+// Cursor pagination for list queries; `key` is an opaque token taken from a previous `next_key`
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct PageRequest {
+    pub key: Option<Binary>,
+    pub limit: Option<u32>,
+    pub reverse: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PageResponse<T> {
+    pub items: Vec<T>,
+    pub next_key: Option<Binary>,
+    pub truncated: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TreasuryEntry {
+    pub denom: String,
+    pub accrued: Uint128,
+    pub contract_balance: Uint128,
+}
+
+// Gas estimate components; a record of n transfers costs roughly
+// base + n * (per_transfer + proof_verify), less proof_verify for cached proofs
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GasEstimate {
+    pub base: u64,
+    pub per_transfer: u64,
+    pub proof_verify: u64,
+    pub single_record: u64,
+}
+
+// One entry of the event log, carrying the same attributes as the emitted event
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EventRecord {
+    pub seq: u64,
+    pub action: String,
+    pub height: u64,
+    pub timestamp: u64,
+    pub attributes: Vec<(String, String)>,
+}
+
+// A change to one field of a recorded transfer
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Amendment {
+    pub field: String,
+    pub old_value: String,
+    pub new_value: String,
+    pub timestamp: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RecipientFees {
+    pub recipient: String,
+    pub fees: Uint128,
+}
+
+// Rate in force at the current block for a denom, with the fee it implies for an optional
+// amount; `min_fee` is the configured floor, which raises `fee` when in the same denom
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EffectiveFeeRate {
+    pub denom: String,
+    pub percentage: Uint128,
+    pub fee: Option<Uint128>,
+    pub min_fee: Option<Coin>,
+}
+
+// Lifetime proof verification counters; rejections inside a batch revert the
+// batch, so neither they nor the batch's verified items are counted
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProofStats {
+    pub proofs_verified: u64,
+    pub proofs_rejected: u64,
+}
+
+// One page of a state export; `config` is set on the first page only, and the
+// pages' transfers concatenated form its `initial_transfers` for a fresh instance
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Snapshot {
+    pub config: Option<InstantiateMsg>,
+    pub transfers: Vec<FileTransfer>,
+    pub last_id: Option<u64>,
+}
+
+// A transfer's proof public inputs as hex-encoded little-endian field elements, in
+// circuit order
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PublicInputs {
+    pub file_hash: String,
+    pub recipient: String,
+    pub metadata_commitment: String,
+    pub block_height: String,
+    pub epoch: String,
+}
+
+// Everything a dashboard shows on load, gathered in one response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Dashboard {
+    pub config: State,
+    pub fee_schedule: FeeSchedule,
+    pub treasury: Vec<TreasuryEntry>,
+    pub volume: Vec<Coin>,
+    pub transfer_count: u64,
+    pub proof_stats: ProofStats,
+    pub recent_transfers: Vec<FileTransfer>,
+}
+
+// An admin-tunable parameter with its current and compile-time default values as
+// JSON; `default` is None when instantiate must supply the value
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Parameter {
+    pub name: String,
+    pub current: String,
+    pub default: Option<String>,
+}
+
+// Which privileged roles an address holds, for UIs deciding what to show
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Roles {
+    pub is_admin: bool,
+    pub is_fee_recipient: bool,
+    pub is_allowlisted: bool,
+    pub is_opted_in: bool,
+}
+
+// Every input to fee calculation; rates are in basis points
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeeSchedule {
+    pub base_percentage: Uint128,
+    pub effective_percentage: Uint128,
+    pub pending_fee: Option<PendingFee>,
+    pub fee_ramp: Option<FeeRamp>,
+    pub denom_overrides: Vec<(String, Uint128)>,
+    pub free_transfer_quota: u32,
+    pub burn_percentage: Uint128,
+    pub fee_splits: Vec<(Addr, u16)>,
+    pub rounding: String,
+    pub fee_mode: FeeMode,
+    pub min_fee: Option<Coin>,
+}
+
+// Query interface expected from the name-service contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NameServiceQueryMsg {
+    ResolveRecord { name: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ResolveRecordResponse {
+    pub address: Option<String>,
+}
+
+// Query interface expected from the price-oracle contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceOracleQueryMsg {
+    Price { base_denom: String, quote_denom: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceResponse {
+    pub rate: Decimal,
+}
+
+// Query interface expected from an external proof-verifier contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VerifierQueryMsg {
+    VerifyProof {
+        proof: Binary,
+        file_hash: String,
+        recipient: Binary,
+        metadata_commitment: Binary,
+        proved_height: u64,
+        epoch: u64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VerifyProofResponse {
+    pub valid: bool,
+}
+
+// Envelope for every query response so clients can detect schema changes.
+// Bump SCHEMA_VERSION whenever a response type gains or changes fields.
+pub const SCHEMA_VERSION: u16 = 25;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Versioned<T> {
+    pub schema_version: u16,
+    pub data: T,
+}
+
+fn versioned<T>(data: T) -> Versioned<T> {
+    Versioned {
+        schema_version: SCHEMA_VERSION,
+        data,
+    }
+}
+
+// Contract instantiation
+#[entry_point]
+pub fn instantiate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    let event_namespace = msg
+        .event_namespace
+        .unwrap_or_else(|| DEFAULT_EVENT_NAMESPACE.to_string());
+    if event_namespace.is_empty() || event_namespace.len() > MAX_EVENT_NAMESPACE_LEN {
+        return Err(ContractError::InvalidEventNamespace { max: MAX_EVENT_NAMESPACE_LEN });
+    }
+
+    let fee_recipient = match msg.fee_recipient {
+        Some(addr) => deps.api.addr_validate(&addr)?.to_string(),
+        None => info.sender.to_string(),
+    };
+    if fee_recipient == env.contract.address.as_str() {
+        return Err(ContractError::InvalidFeeRecipient {});
+    }
+
+    let accepted_denoms = msg
+        .accepted_denoms
+        .unwrap_or_else(|| vec!["usei".to_string()]);
+    validate_accepted_denoms(&accepted_denoms)?;
+
+    let features = msg.features.unwrap_or_default();
+
+    let name_service = msg
+        .name_service
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+
+    let state = State {
+        event_namespace,
+        fee_recipient,
+        allow_rerecord: msg.allow_rerecord.unwrap_or(false),
+        name_service,
+        max_ttl_seconds: msg.max_ttl_seconds,
+        fee_increase_delay: msg.fee_increase_delay.unwrap_or(0),
+        accepted_denoms,
+        opt_in_required: features.opt_in,
+        features,
+        ..default_state(info.sender.to_string(), msg.fee_percentage, env.block.time.seconds())
+    };
+    CONFIG.save(deps.storage, &state)?;
+
+    // Transfers carried over from another deployment are stored as-is, without reverification
+    let initial_transfers = msg.initial_transfers.unwrap_or_default();
+    let preloaded = initial_transfers.len() as u64;
+    for (id, mut transfer) in initial_transfers.into_iter().enumerate() {
+        deps.api.addr_validate(&transfer.sender)?;
+        deps.api.addr_validate(&transfer.recipient)?;
+        if TRANSFER_KEYS.has(deps.storage, (&transfer.file_hash, &transfer.recipient)) {
+            return Err(ContractError::DuplicateTransfer {});
+        }
+        let id = id as u64;
+        TRANSFER_KEYS.save(deps.storage, (&transfer.file_hash, &transfer.recipient), &id)?;
+        increment_file_hash_count(deps.storage, &transfer.file_hash)?;
+        RECIPIENT_TRANSFERS.save(deps.storage, (&transfer.recipient, id), &())?;
+        index_expiry(deps.storage, id, &transfer)?;
+        save_transfer(deps.storage, id, &mut transfer)?;
+    }
+    NEXT_TRANSFER_ID.save(deps.storage, &preloaded)?;
+
+    Ok(Response::new().add_attribute("preloaded_transfers", preloaded.to_string()))
+}
+
+// Config a fresh deployment starts from; instantiate overrides what its message sets
+fn default_state(admin: String, fee_percentage: Uint128, now: u64) -> State {
+    State {
+        fee_recipient: admin.clone(),
+        admin,
+        fee_percentage,
+        event_namespace: DEFAULT_EVENT_NAMESPACE.to_string(),
+        withdraw_whitelist: vec![],
+        allow_rerecord: false,
+        fee_splits: vec![],
+        name_service: None,
+        max_ttl_seconds: None,
+        free_transfer_quota: 0,
+        fee_increase_delay: 0,
+        pending_fee: None,
+        verbose_events: false,
+        accepted_denoms: vec!["usei".to_string()],
+        proof_freshness_window: None,
+        opt_in_required: Features::default().opt_in,
+        admins: vec![],
+        admin_threshold: 1,
+        recovery_admin: None,
+        admin_inactivity_period: 0,
+        last_admin_action: now,
+        price_oracle: None,
+        minimal_events: false,
+        max_file_size: None,
+        burn_percentage: Uint128::zero(),
+        query_scan_limit: DEFAULT_QUERY_SCAN_LIMIT,
+        fee_ramp: None,
+        verification_policy: VerificationPolicy::default(),
+        paused: false,
+        auto_pause_threshold: 0,
+        auto_pause_window: 0,
+        min_verify_interval: 0,
+        verify_interval_per_sender: false,
+        features: Features::default(),
+        fallback_recipient: None,
+        max_recipients: DEFAULT_MAX_RECIPIENTS,
+        verifier_contract: None,
+        fee_mode: FeeMode::FromAmount,
+        min_fee: None,
+        verifying_key_hash: None,
+        query_limits: QueryLimits::default(),
+        current_epoch: 0,
+        spend_cap: None,
+        auto_prune: false,
+        auto_prune_limit: DEFAULT_AUTO_PRUNE_LIMIT,
+        required_prefix: None,
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
+// Contract migration: split a legacy single-blob state into the config Item and transfers Map
+#[entry_point]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    // Transfers recorded before the expiry index existed are added to it here
+    let legacy = match deps.storage.get(LEGACY_STATE_KEY) {
+        Some(legacy) => legacy,
+        None => {
+            let transfers: Vec<(u64, FileTransfer)> = TRANSFERS
+                .range(deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<_>>()?;
+            for (id, transfer) in &transfers {
+                index_expiry(deps.storage, *id, transfer)?;
+            }
+            return Ok(Response::new().add_attribute("action", "migrate"));
+        }
+    };
+
+    // The legacy blob holds only the admin, the fee and bare transfers; everything
+    // added since starts from its instantiate default
+    let LegacyState {
+        file_transfers,
+        admin,
+        fee_percentage,
+    } = cosmwasm_std::from_json(&legacy)?;
+    let state = default_state(admin, fee_percentage, env.block.time.seconds());
+
+    let migrated = file_transfers.len() as u64;
+    let mut collected = Uint128::zero();
+    for (id, legacy) in file_transfers.into_iter().enumerate() {
+        let id = id as u64;
+        collected += legacy.transfer_fee;
+        let sender = Addr::unchecked(&legacy.sender);
+        let normalized_fee = normalize_amount(deps.storage, "usei", legacy.transfer_fee)?;
+        FEES_PAID.update(deps.storage, &sender, |paid| -> StdResult<_> {
+            Ok(paid.unwrap_or_default() + normalized_fee)
+        })?;
+        let mut transfer = FileTransfer {
+            file_hash: legacy.file_hash,
+            sender: legacy.sender,
+            recipient: legacy.recipient,
+            timestamp: legacy.timestamp,
+            transfer_fee: legacy.transfer_fee,
+            status: TransferStatus::Recorded,
+            revoked_at: None,
+            expires_at: None,
+            metadata_commitment: None,
+            proof_hash: Binary::default(),
+            proved_height: 0,
+            wrapped_key: None,
+            file_size: None,
+            recipient_set_hash: None,
+            delivery_signature: None,
+            update_seq: 0,
+            visible_at: None,
+            memo: None,
+            epoch: 0,
+        };
+        if !TRANSFER_KEYS.has(deps.storage, (&transfer.file_hash, &transfer.recipient)) {
+            increment_file_hash_count(deps.storage, &transfer.file_hash)?;
+        }
+        TRANSFER_KEYS.save(deps.storage, (&transfer.file_hash, &transfer.recipient), &id)?;
+        RECIPIENT_TRANSFERS.save(deps.storage, (&transfer.recipient, id), &())?;
+        save_transfer(deps.storage, id, &mut transfer)?;
+    }
+    NEXT_TRANSFER_ID.save(deps.storage, &migrated)?;
+    // The first release charged fees in usei and let the admin withdraw any amount, so
+    // only what is still held is carried over as withdrawable
+    let balance = deps.querier.query_balance(&env.contract.address, "usei")?.amount;
+    ACCRUED_FEES.save(deps.storage, "usei", &collected.min(balance))?;
+    CONFIG.save(deps.storage, &state)?;
+    deps.storage.remove(LEGACY_STATE_KEY);
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("migrated_transfers", migrated.to_string()))
+}
+
+// Submessage replies: a failed escrow refund is sent to the fallback recipient instead
+#[entry_point]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let refund = REFUND_REPLIES
+        .may_load(deps.storage, msg.id)?
+        .ok_or_else(|| StdError::not_found("RefundReply"))?;
+    REFUND_REPLIES.remove(deps.storage, msg.id);
+
+    let error = match msg.result {
+        SubMsgResult::Ok(_) => return Ok(Response::new()),
+        SubMsgResult::Err(error) => error,
+    };
+    let state = CONFIG.load(deps.storage)?;
+    let fallback = state
+        .fallback_recipient
+        .clone()
+        .ok_or_else(|| StdError::generic_err(format!("Refund failed: {}", error)))?;
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: fallback.to_string(),
+            amount: vec![refund.clone()],
+        })
+        .add_attribute("action", "refund_fallback")
+        .add_event(
+            Event::new(event_type(&state, "refund_fallback"))
+                .add_attribute("to", fallback)
+                .add_attribute("amount", refund.to_string())
+                .add_attribute("error", error),
+        ))
+}
+
+// Contract execution
+#[entry_point]
+pub fn execute(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    record_admin_activity(deps.storage, &env, &info)?;
+
+    match msg {
+        ExecuteMsg::RecordTransfer {
+            file_hash,
+            recipient,
+            zk_proof,
+            ttl_seconds,
+            metadata_commitment,
+            proved_height,
+            wrapped_key,
+            file_size,
+            metadata_signature,
+            coupon,
+            visible_at,
+            memo,
+        } => {
+            let sender = info.sender.clone();
+            let params = TransferParams {
+                recipient_set_hash: None,
+                file_hash,
+                recipient,
+                zk_proof,
+                ttl_seconds,
+                metadata_commitment,
+                proved_height,
+                wrapped_key,
+                file_size,
+                metadata_signature,
+                coupon,
+                visible_at,
+                memo,
+            };
+            let result = record_transfer(deps.branch(), env, info.clone(), sender, params);
+            settle_rejected_proof(deps, &info, result)
+        }
+        ExecuteMsg::WithdrawFees { amount, to, denom } => withdraw_fees(deps, env, info, amount, to, denom),
+        ExecuteMsg::SetFeePercentage { percentage } => set_fee_percentage(deps, env, info, percentage),
+        ExecuteMsg::SetFeePercentageCas { expected, new } => {
+            set_fee_percentage_cas(deps, env, info, expected, new)
+        }
+        ExecuteMsg::SetFeeMode { mode } => set_fee_mode(deps, info, mode),
+        ExecuteMsg::SetMinFee { min_fee } => set_min_fee(deps, info, min_fee),
+        ExecuteMsg::SetAutoWithdrawThreshold { denom, threshold } => {
+            set_auto_withdraw_threshold(deps, info, denom, threshold)
+        }
+        ExecuteMsg::BlockFileHash { file_hash } => block_file_hash(deps, env, info, file_hash),
+        ExecuteMsg::UnblockFileHash { file_hash } => unblock_file_hash(deps, env, info, file_hash),
+        ExecuteMsg::Grant { grantee } => grant(deps, info, grantee),
+        ExecuteMsg::Revoke { grantee } => revoke(deps, info, grantee),
+        ExecuteMsg::RecordTransferFor {
+            on_behalf_of,
+            file_hash,
+            recipient,
+            zk_proof,
+            ttl_seconds,
+            metadata_commitment,
+            proved_height,
+            wrapped_key,
+            file_size,
+            metadata_signature,
+            coupon,
+            visible_at,
+            memo,
+        } => {
+            let params = TransferParams {
+                recipient_set_hash: None,
+                file_hash,
+                recipient,
+                zk_proof,
+                ttl_seconds,
+                metadata_commitment,
+                proved_height,
+                wrapped_key,
+                file_size,
+                metadata_signature,
+                coupon,
+                visible_at,
+                memo,
+            };
+            let result = record_transfer_for(deps.branch(), env, info.clone(), on_behalf_of, params);
+            settle_rejected_proof(deps, &info, result)
+        }
+        ExecuteMsg::AddWithdrawDestination { address } => add_withdraw_destination(deps, info, address),
+        ExecuteMsg::RemoveWithdrawDestination { address } => {
+            remove_withdraw_destination(deps, info, address)
+        }
+        ExecuteMsg::RevokeTransfer { file_hash, recipient } => {
+            revoke_transfer(deps, env, info, file_hash, recipient)
+        }
+        ExecuteMsg::SetAllowRerecord { allow } => set_allow_rerecord(deps, info, allow),
+        ExecuteMsg::SetFeeSplits { splits } => set_fee_splits(deps, info, splits),
+        ExecuteMsg::SetNameService { address } => set_name_service(deps, info, address),
+        ExecuteMsg::SetMaxTtl { max_ttl_seconds } => set_max_ttl(deps, info, max_ttl_seconds),
+        ExecuteMsg::SetFreeTransferQuota { quota } => set_free_transfer_quota(deps, info, quota),
+        ExecuteMsg::ProposeFeePercentage { percentage } => {
+            propose_fee_percentage(deps, env, info, percentage)
+        }
+        ExecuteMsg::SetFeeIncreaseDelay { seconds } => set_fee_increase_delay(deps, info, seconds),
+        ExecuteMsg::SetVerboseEvents { enabled } => set_verbose_events(deps, info, enabled),
+        ExecuteMsg::SetAcceptedDenoms { denoms } => set_accepted_denoms(deps, info, denoms),
+        ExecuteMsg::SetProofFreshnessWindow { blocks } => set_proof_freshness_window(deps, info, blocks),
+        ExecuteMsg::OptIn {} => opt_in(deps, info),
+        ExecuteMsg::OptOut {} => opt_out(deps, info),
+        ExecuteMsg::SetOptInRequired { required } => set_opt_in_required(deps, info, required),
+        ExecuteMsg::SetAdmins { admins, threshold } => set_admins(deps, info, admins, threshold),
+        ExecuteMsg::ApproveOperation { id } => approve_operation(deps, env, info, id),
+        ExecuteMsg::SetRecoveryAdmin {
+            address,
+            inactivity_seconds,
+        } => set_recovery_admin(deps, info, address, inactivity_seconds),
+        ExecuteMsg::ClaimAdmin {} => claim_admin(deps, env, info),
+        ExecuteMsg::SetDenomFeePercentage { denom, percentage } => {
+            set_denom_fee_percentage(deps, info, denom, percentage)
+        }
+        ExecuteMsg::BatchRecordTransfer { transfers } => batch_record_transfer(deps, env, info, transfers),
+        ExecuteMsg::RecordMultiRecipient {
+            file_hash,
+            recipients,
+            zk_proof,
+            proved_height,
+            escrow_deadline,
+        } => {
+            let params = MultiRecipientParams {
+                file_hash,
+                recipients,
+                zk_proof,
+                proved_height,
+                escrow_deadline,
+            };
+            let result = record_multi_recipient(deps.branch(), env, info.clone(), params);
+            settle_rejected_proof(deps, &info, result)
+        }
+        ExecuteMsg::SetPriceOracle { address } => set_price_oracle(deps, info, address),
+        ExecuteMsg::SetVerifierContract { address } => set_verifier_contract(deps, info, address),
+        ExecuteMsg::SetVerifyingKey { verifying_key } => set_verifying_key(deps, info, verifying_key),
+        ExecuteMsg::BumpEpoch {} => bump_epoch(deps, env, info),
+        ExecuteMsg::SetMinimalEvents { enabled } => set_minimal_events(deps, info, enabled),
+        ExecuteMsg::SetMaxFileSize { max_file_size } => set_max_file_size(deps, info, max_file_size),
+        ExecuteMsg::SetBurnPercentage { percentage } => set_burn_percentage(deps, info, percentage),
+        ExecuteMsg::SetQueryScanLimit { limit } => set_query_scan_limit(deps, info, limit),
+        ExecuteMsg::SetQueryLimits { limits } => set_query_limits(deps, info, limits),
+        ExecuteMsg::SetSpendCap { cap } => set_spend_cap(deps, info, cap),
+        ExecuteMsg::SetAutoPrune { enabled, limit } => set_auto_prune(deps, info, enabled, limit),
+        ExecuteMsg::SetRequiredPrefix { prefix } => set_required_prefix(deps, info, prefix),
+        ExecuteMsg::SetMaxRecipients { max } => set_max_recipients(deps, info, max),
+        ExecuteMsg::ScheduleFeeRamp { target, start, end } => {
+            schedule_fee_ramp(deps, env, info, target, start, end)
+        }
+        ExecuteMsg::AcknowledgeTransfer { file_hash } => acknowledge_transfer(deps, env, info, file_hash),
+        ExecuteMsg::ReclaimEscrow { file_hash } => reclaim_escrow(deps, env, info, file_hash),
+        ExecuteMsg::SweepAbandonedEscrow { limit } => sweep_abandoned_escrow(deps, env, info, limit),
+        ExecuteMsg::SetVerificationPolicy {
+            trusted_senders,
+            trusted_denoms,
+        } => set_verification_policy(deps, info, trusted_senders, trusted_denoms),
+        ExecuteMsg::SetPaused { paused } => set_paused(deps, info, paused),
+        ExecuteMsg::SetAutoPause {
+            threshold,
+            window_seconds,
+        } => set_auto_pause(deps, info, threshold, window_seconds),
+        ExecuteMsg::IssueCoupons { codes } => issue_coupons(deps, info, codes),
+        ExecuteMsg::ConfirmDelivery { file_hash, signature } => confirm_delivery(deps, info, file_hash, signature),
+        ExecuteMsg::SetMinVerifyInterval { blocks, per_sender } => {
+            set_min_verify_interval(deps, info, blocks, per_sender)
+        }
+        ExecuteMsg::SetDenomExponent { denom, exponent } => set_denom_exponent(deps, info, denom, exponent),
+        ExecuteMsg::SetMinTransferAmount { denom, amount } => set_min_transfer_amount(deps, info, denom, amount),
+        ExecuteMsg::SetFeeOverride { sender, file_hash, fee } => {
+            set_fee_override(deps, info, sender, file_hash, fee)
+        }
+        ExecuteMsg::ExtendTransfer {
+            file_hash,
+            recipient,
+            additional_seconds,
+        } => extend_transfer(deps, env, info, file_hash, recipient, additional_seconds),
+        ExecuteMsg::SetFallbackRecipient { address } => set_fallback_recipient(deps, info, address),
+        ExecuteMsg::SetFeeRecipient { address } => set_fee_recipient(deps, env, info, address),
+        ExecuteMsg::UpdateConfig {
+            fee_percentage,
+            paused,
+            accepted_denoms,
+            allow_rerecord,
+            free_transfer_quota,
+            fee_increase_delay,
+            verbose_events,
+            minimal_events,
+            burn_percentage,
+            query_scan_limit,
+        } => {
+            let update = ConfigUpdate {
+                fee_percentage,
+                paused,
+                accepted_denoms,
+                allow_rerecord,
+                free_transfer_quota,
+                fee_increase_delay,
+                verbose_events,
+                minimal_events,
+                burn_percentage,
+                query_scan_limit,
+            };
+            update_config(deps, env, info, update)
+        }
+        ExecuteMsg::RegisterPubkey { pubkey } => register_pubkey(deps, info, pubkey),
+    }
+}
+
+// Record file transfer function
+fn record_transfer(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    params: TransferParams,
+) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    let (info, floor_attached) = split_fee_floor(info, &state);
+    let mut floor_left = floor_attached;
+    let response = record_floored_transfer(deps.branch(), env, info, sender, params, &mut floor_left)?;
+    settle_fee_floor(deps.storage, &state, floor_attached, floor_left)?;
+    Ok(response)
+}
+
+// Record one transfer, drawing a min_fee floor in another denom from `floor_left`,
+// the fee-denom coins the caller set aside with split_fee_floor
+fn record_floored_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    params: TransferParams,
+    floor_left: &mut Uint128,
+) -> Result<Response, ContractError> {
+    let TransferParams {
+        recipient_set_hash,
+        file_hash,
+        recipient,
+        zk_proof,
+        ttl_seconds,
+        metadata_commitment,
+        proved_height,
+        wrapped_key,
+        file_size,
+        metadata_signature,
+        coupon,
+        visible_at,
+        memo,
+    } = params;
+    let state = CONFIG.load(deps.storage)?;
+    if state.paused {
+        return Err(ContractError::Paused {});
+    }
+    if BLOCKED_FILE_HASHES.has(deps.storage, &file_hash) {
+        return Err(ContractError::FileHashBlocked {});
+    }
+    let recipient = resolve_recipient(deps.as_ref(), &state, recipient)?;
+    if let Some(prefix) = &state.required_prefix {
+        if deps.api.addr_validate(&recipient).is_err() || bech32_prefix(&recipient) != Some(prefix.as_str()) {
+            return Err(ContractError::InvalidRecipientPrefix { expected: prefix.clone() });
+        }
+    }
+
+    if state.opt_in_required {
+        let opted_in = match deps.api.addr_validate(&recipient) {
+            Ok(addr) => OPTED_IN.may_load(deps.storage, &addr)?.unwrap_or(false),
+            Err(_) => false,
+        };
+        if !opted_in {
+            return Err(ContractError::RecipientNotOptedIn {});
+        }
+    }
+
+    // Opaque to the contract; only the size is bounded
+    if wrapped_key.as_ref().is_some_and(|key| key.len() > MAX_WRAPPED_KEY_LEN) {
+        return Err(ContractError::WrappedKeyTooLong { max: MAX_WRAPPED_KEY_LEN });
+    }
+    // Free-form note for humans, never interpreted
+    if memo.as_ref().is_some_and(|memo| memo.len() > MAX_MEMO_LEN) {
+        return Err(ContractError::MemoTooLong { max: MAX_MEMO_LEN });
+    }
+
+    if let (Some(size), Some(max)) = (file_size, state.max_file_size) {
+        if size > max {
+            return Err(ContractError::FileTooLarge {});
+        }
+    }
+
+    if let (Some(ttl), Some(max)) = (ttl_seconds, state.max_ttl_seconds) {
+        if ttl > max {
+            return Err(ContractError::TtlTooLong { max });
+        }
+    }
+    // Without a max TTL, the expiry must still fit in a timestamp
+    let now = env.block.time.seconds();
+    let expires_at = ttl_seconds
+        .map(|ttl| {
+            now.checked_add(ttl)
+                .ok_or(ContractError::TtlTooLong { max: u64::MAX - now })
+        })
+        .transpose()?;
+
+    // Records without metadata prove against a zero commitment
+    let commitment: [u8; 32] = match &metadata_commitment {
+        Some(c) => c
+            .as_slice()
+            .try_into()
+            .map_err(|_| ContractError::InvalidMetadataCommitment {})?,
+        None => [0u8; 32],
+    };
+
+    // A signed commitment must verify against the sender's registered key
+    if let Some(signature) = &metadata_signature {
+        if metadata_commitment.is_none() {
+            return Err(ContractError::InvalidSignature {});
+        }
+        let pubkey = ACCOUNT_PUBKEYS
+            .may_load(deps.storage, &sender)?
+            .ok_or(ContractError::InvalidSignature {})?;
+        let message_hash = Sha256::digest(commitment);
+        let valid = deps
+            .api
+            .secp256k1_verify(&message_hash, signature, &pubkey)
+            .unwrap_or(false);
+        if !valid {
+            return Err(ContractError::InvalidSignature {});
+        }
+    }
+
+    // Proofs commit to the height they were generated at; reject ones outside the window
+    let proved_height = proved_height.unwrap_or_default();
+    if let Some(window) = state.proof_freshness_window {
+        if env.block.height.abs_diff(proved_height) > window {
+            return Err(ContractError::StaleProof {});
+        }
+    }
+
+    // Verify ZK proof, skipping the pairing check for a proof already verified
+    // against these exact public inputs or a sender/denom the admin trusts
+    let denom = payment_denom(&info, &state)?;
+    let trusted = state.verification_policy.trusted_senders.contains(&sender)
+        || state.verification_policy.trusted_denoms.contains(&denom);
+    let proof_bytes = zk_proof.len();
+    let recipient_input: &[u8] = match &recipient_set_hash {
+        Some(set_hash) => set_hash,
+        None => recipient.as_bytes(),
+    };
+    let inputs = ProofInputs {
+        file_hash: &file_hash,
+        recipient: recipient_input,
+        metadata_commitment: commitment,
+        proved_height,
+        epoch: state.current_epoch,
+    };
+    let proof_hash = proof_hash(&zk_proof, &inputs);
+    let cache_key = proof_cache_key(&proof_hash, state.verifying_key_hash.as_ref());
+    let cache_hit = VERIFIED_PROOFS.has(deps.storage, &cache_key);
+    if !trusted && !cache_hit {
+        throttle_verification(deps.storage, &state, &sender, env.block.height)?;
+        if !check_proof(deps.as_ref(), &state, &zk_proof, &inputs)? {
+            return Err(ContractError::InvalidProof {});
+        }
+        note_verification(deps.storage, &state, &sender, env.block.height)?;
+        VERIFIED_PROOFS.save(deps.storage, &cache_key, &())?;
+        let verified = PROOFS_VERIFIED.may_load(deps.storage)?.unwrap_or_default();
+        PROOFS_VERIFIED.save(deps.storage, &(verified + 1))?;
+    }
+
+    // Bounded by auto_prune_limit, and run after the proof check so a rejected proof
+    // prunes nothing, before the duplicate check so a key it frees is counted as new,
+    // and before this record so it can't prune itself
+    let pruned = if state.auto_prune {
+        prune_expired(deps.storage, env.block.time.seconds(), state.auto_prune_limit)?
+    } else {
+        0
+    };
+
+    // Check if transfer already exists; revoked keys are reusable only when allowed
+    let existing_id = TRANSFER_KEYS.may_load(deps.storage, (&file_hash, &recipient))?;
+    if let Some(id) = existing_id {
+        let existing = TRANSFERS.load(deps.storage, id)?;
+        if existing.status != TransferStatus::Revoked || !state.allow_rerecord {
+            return Err(ContractError::DuplicateTransfer {});
+        }
+    }
+
+    // Calculate transfer fee in whichever accepted denom was attached
+    let transfer_amount = extract_funds(&info, &denom)?;
+    if let Some(min) = MIN_TRANSFER_AMOUNTS.may_load(deps.storage, &denom)? {
+        if transfer_amount < min {
+            return Err(ContractError::BelowMinimumAmount { denom, min });
+        }
+    }
+    // A coupon waives the fee once; otherwise the first free_transfer_quota records
+    // per sender are fee-exempt
+    let free_used = FREE_TRANSFERS_USED.may_load(deps.storage, &sender)?.unwrap_or_default();
+    let transfer_fee = if let Some(code) = &coupon {
+        match COUPONS.may_load(deps.storage, code)? {
+            Some(false) => COUPONS.save(deps.storage, code, &true)?,
+            Some(true) => return Err(ContractError::CouponAlreadyUsed {}),
+            None => return Err(ContractError::InvalidCoupon {}),
+        }
+        Uint128::zero()
+    } else if let Some(fee) = FEE_OVERRIDES.may_load(deps.storage, (&sender, &file_hash))? {
+        fee.min(transfer_amount)
+    } else if free_used < state.free_transfer_quota {
+        FREE_TRANSFERS_USED.save(deps.storage, &sender, &(free_used + 1))?;
+        Uint128::zero()
+    } else {
+        let fee = compute_fee(deps.storage, &state, env.block.time.seconds(), &denom, transfer_amount)?;
+        match &state.min_fee {
+            Some(floor) if floor.denom == denom => {
+                if transfer_amount < floor.amount {
+                    return Err(ContractError::MinFeeNotMet {
+                        denom: floor.denom.clone(),
+                        amount: floor.amount,
+                    });
+                }
+                fee.max(floor.amount)
+            }
+            Some(floor) => {
+                if *floor_left < floor.amount {
+                    return Err(ContractError::MinFeeNotMet {
+                        denom: floor.denom.clone(),
+                        amount: floor.amount,
+                    });
+                }
+                *floor_left -= floor.amount;
+                ACCRUED_FEES.update(deps.storage, &floor.denom, |accrued| -> StdResult<_> {
+                    Ok(accrued.unwrap_or_default() + floor.amount)
+                })?;
+                fee
+            }
+            None => fee,
+        }
+    };
+    let mut transfer = FileTransfer {
+        file_hash: file_hash.clone(),
+        sender: sender.to_string(),
+        recipient: recipient.clone(),
+        timestamp: env.block.time.seconds(),
+        transfer_fee,
+        status: TransferStatus::Recorded,
+        revoked_at: None,
+        expires_at,
+        metadata_commitment,
+        proof_hash: Binary::from(proof_hash),
+        proved_height,
+        wrapped_key,
+        file_size,
+        recipient_set_hash: recipient_set_hash.map(|set_hash| Binary::from(set_hash.to_vec())),
+        delivery_signature: None,
+        update_seq: 0,
+        visible_at,
+        memo,
+        epoch: state.current_epoch,
+    };
+    let id = NEXT_TRANSFER_ID.may_load(deps.storage)?.unwrap_or_default();
+    save_transfer(deps.storage, id, &mut transfer)?;
+    TRANSFER_KEYS.save(deps.storage, (&file_hash, &recipient), &id)?;
+    RECIPIENT_TRANSFERS.save(deps.storage, (&recipient, id), &())?;
+    index_expiry(deps.storage, id, &transfer)?;
+    NEXT_TRANSFER_ID.save(deps.storage, &(id + 1))?;
+    if existing_id.is_none() {
+        increment_file_hash_count(deps.storage, &file_hash)?;
+    }
+
+    // Cross-denom fee totals are kept in a common unit
+    let normalized_fee = normalize_amount(deps.storage, &denom, transfer_fee)?;
+    track_sender_spend(deps.storage, &state, &sender, env.block.time.seconds(), normalized_fee)?;
+    FEES_PAID.update(deps.storage, &sender, |paid| -> StdResult<_> {
+        Ok(paid.unwrap_or_default() + normalized_fee)
+    })?;
+    if !normalized_fee.is_zero() {
+        record_recipient_fees(deps.storage, &recipient, normalized_fee)?;
+    }
+    VOLUME.update(deps.storage, &denom, |volume| -> StdResult<_> {
+        Ok(volume.unwrap_or_default() + transfer_amount)
+    })?;
+    SEEN_DENOMS.save(deps.storage, &denom, &())?;
+
+    // Stream each stakeholder's share; any remainder stays accrued in the contract
+    let shares: Vec<(&Addr, Uint128)> = state
+        .fee_splits
+        .iter()
+        .map(|(addr, bps)| (addr, transfer_fee.multiply_ratio(*bps as u128, 10000u128)))
+        .filter(|(_, share)| !share.is_zero())
+        .collect();
+    let distributed: Uint128 = shares.iter().map(|(_, share)| *share).sum();
+    let mut split_msgs: Vec<BankMsg> = shares
+        .into_iter()
+        .map(|(addr, share)| BankMsg::Send {
+            to_address: addr.to_string(),
+            amount: vec![cosmwasm_std::Coin {
+                denom: denom.clone(),
+                amount: share,
+            }],
+        })
+        .collect();
+    // Burn share of the fee, capped by whatever the splits left over
+    let burned = (transfer_fee * state.burn_percentage / Uint128::new(10000)).min(transfer_fee - distributed);
+    if !burned.is_zero() {
+        split_msgs.push(BankMsg::Burn {
+            amount: vec![Coin {
+                denom: denom.clone(),
+                amount: burned,
+            }],
+        });
+    }
+    let accrued = ACCRUED_FEES.update(deps.storage, &denom, |accrued| -> StdResult<_> {
+        Ok(accrued.unwrap_or_default() + transfer_fee - distributed - burned)
+    })?;
+
+    // Once accrued fees reach the denom's threshold, send them to the fee recipient,
+    // never more than is held outside escrow and this record's own payouts
+    let threshold = AUTO_WITHDRAW_THRESHOLDS.may_load(deps.storage, &denom)?.unwrap_or_default();
+    let mut auto_withdrawn = Uint128::zero();
+    if !threshold.is_zero() && accrued >= threshold {
+        let balance = deps.querier.query_balance(&env.contract.address, &denom)?.amount;
+        let escrowed = ESCROWED.may_load(deps.storage, &denom)?.unwrap_or_default();
+        auto_withdrawn = accrued.min(balance.saturating_sub(escrowed + distributed + burned));
+        if !auto_withdrawn.is_zero() {
+            ACCRUED_FEES.save(deps.storage, &denom, &(accrued - auto_withdrawn))?;
+            split_msgs.push(BankMsg::Send {
+                to_address: state.fee_recipient.clone(),
+                amount: vec![Coin {
+                    denom: denom.clone(),
+                    amount: auto_withdrawn,
+                }],
+            });
+        }
+    }
+
+    let mut event = Event::new(event_type(&state, "record_transfer"))
+        .add_attribute("transfer_id", id.to_string())
+        .add_attribute(
+            "proof_cache",
+            match (trusted, cache_hit) {
+                (true, _) => "trusted",
+                (false, true) => "hit",
+                (false, false) => "miss",
+            },
+        );
+    // Privacy mode: only the opaque id is emitted
+    if !state.minimal_events {
+        event = event
+            .add_attribute("file_hash", file_hash)
+            .add_attribute("sender", sender)
+            .add_attribute("recipient", recipient)
+            .add_attribute("transfer_fee", transfer_fee.to_string())
+            .add_attribute("burned", burned.to_string())
+            .add_attribute("denom", denom);
+    }
+    // Extra attributes for gas profiling
+    if state.verbose_events && !state.minimal_events {
+        event = event
+            .add_attribute("proof_bytes", proof_bytes.to_string())
+            .add_attribute("transfer_count_after", (id + 1).to_string());
+    }
+    if !auto_withdrawn.is_zero() {
+        event = event.add_attribute("auto_withdrawn", auto_withdrawn.to_string());
+    }
+    if pruned > 0 {
+        event = event.add_attribute("pruned", pruned.to_string());
+    }
+    if track_record_rate(deps.storage, &state, env.block.time.seconds())? {
+        event = event.add_attribute("alert", "auto_paused");
+    }
+    append_event_log(deps.storage, &env, "record_transfer", &event)?;
+
+    Ok(Response::new()
+        .add_messages(split_msgs)
+        .add_attribute("action", "record_transfer")
+        .add_event(event))
+}
+
+// An invalid proof turns the record away without reverting, so the rejection is
+// counted; the attached funds go back to the caller. Nothing is written before the
+// proof check, so no partial record, prune or throttle height is kept
+fn settle_rejected_proof(
+    deps: DepsMut,
+    info: &MessageInfo,
+    result: Result<Response, ContractError>,
+) -> Result<Response, ContractError> {
+    if !matches!(result, Err(ContractError::InvalidProof {})) {
+        return result;
+    }
+    let state = CONFIG.load(deps.storage)?;
+    let rejected = PROOFS_REJECTED.may_load(deps.storage)?.unwrap_or_default() + 1;
+    PROOFS_REJECTED.save(deps.storage, &rejected)?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "reject_proof")
+        .add_event(
+            Event::new(event_type(&state, "reject_proof"))
+                .add_attribute("sender", info.sender.to_string())
+                .add_attribute("proofs_rejected", rejected.to_string()),
+        );
+    if !info.funds.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: info.funds.clone(),
+        });
+    }
+    Ok(response)
+}
+
+// Record several transfers in one call, splitting the attached funds per item;
+// an invalid proof in any item aborts the whole batch. Batches are not settled like
+// single records: earlier items are already written, so the batch must revert, which
+// also rolls back the verified counts of those items
+fn batch_record_transfer(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    transfers: Vec<BatchTransferItem>,
+) -> Result<Response, ContractError> {
+    // Reject intra-batch conflicts before recording anything
+    for (index, item) in transfers.iter().enumerate() {
+        if transfers[..index]
+            .iter()
+            .any(|prev| prev.file_hash == item.file_hash && prev.recipient == item.recipient)
+        {
+            return Err(ContractError::DuplicateInBatch { index });
+        }
+    }
+
+    let state = CONFIG.load(deps.storage)?;
+    let (info, floor_attached) = split_fee_floor(info, &state);
+    let mut floor_left = floor_attached;
+    let denom = payment_denom(&info, &state)?;
+    let attached = extract_funds(&info, &denom)?;
+    let total: Uint128 = transfers.iter().map(|item| item.amount).sum();
+    if total != attached {
+        return Err(ContractError::BatchFundsMismatch {});
+    }
+
+    let count = transfers.len();
+    let mut response = Response::new().add_attribute("action", "batch_record_transfer");
+    let mut locked = vec![];
+    for (index, item) in transfers.into_iter().enumerate() {
+        // Each file stays locked for the rest of the batch so records of it can't
+        // interleave with its per-file count updates
+        if FILE_LOCKS.has(deps.storage, &item.file_hash) {
+            return Err(ContractError::FileLocked { index });
+        }
+        FILE_LOCKS.save(deps.storage, &item.file_hash, &())?;
+        locked.push(item.file_hash.clone());
+
+        let item_info = MessageInfo {
+            sender: info.sender.clone(),
+            funds: if item.amount.is_zero() {
+                vec![]
+            } else {
+                vec![Coin {
+                    denom: denom.clone(),
+                    amount: item.amount,
+                }]
+            },
+        };
+        let params = TransferParams {
+            recipient_set_hash: None,
+            file_hash: item.file_hash,
+            recipient: item.recipient,
+            zk_proof: item.zk_proof,
+            ttl_seconds: item.ttl_seconds,
+            metadata_commitment: item.metadata_commitment,
+            proved_height: item.proved_height,
+            wrapped_key: item.wrapped_key,
+            file_size: item.file_size,
+            metadata_signature: item.metadata_signature,
+            coupon: item.coupon,
+            visible_at: item.visible_at,
+            memo: item.memo,
+        };
+        let recorded = record_floored_transfer(
+            deps.branch(),
+            env.clone(),
+            item_info,
+            info.sender.clone(),
+            params,
+            &mut floor_left,
+        )?;
+        response = response
+            .add_submessages(recorded.messages)
+            .add_events(recorded.events);
+    }
+    for file_hash in locked {
+        FILE_LOCKS.remove(deps.storage, &file_hash);
+    }
+    settle_fee_floor(deps.storage, &state, floor_attached, floor_left)?;
+
+    Ok(response.add_attribute("count", count.to_string()))
+}
+
+// Record one transfer per recipient from a single proof whose recipient input
+// commits to the sorted recipient set; attached funds are split evenly and, with
+// an escrow deadline, each share net of fees is held until acknowledged
+fn record_multi_recipient(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    params: MultiRecipientParams,
+) -> Result<Response, ContractError> {
+    let MultiRecipientParams {
+        file_hash,
+        recipients,
+        zk_proof,
+        proved_height,
+        escrow_deadline,
+    } = params;
+    if recipients.is_empty() {
+        return Err(ContractError::EmptyRecipientSet {});
+    }
+    let mut sorted = recipients.clone();
+    sorted.sort();
+    if let Some(index) = sorted.windows(2).position(|pair| pair[0] == pair[1]) {
+        return Err(ContractError::DuplicateInBatch { index: index + 1 });
+    }
+    let set_hash = recipient_set_hash(&sorted);
+    if escrow_deadline.is_some_and(|deadline| deadline <= env.block.time.seconds()) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Escrow deadline must be in the future",
+        )));
+    }
+
+    let state = CONFIG.load(deps.storage)?;
+    if recipients.len() > state.max_recipients as usize {
+        return Err(ContractError::TooManyRecipients {});
+    }
+    if escrow_deadline.is_some() && !state.features.escrow {
+        return Err(ContractError::FeatureDisabled { feature: "escrow".to_string() });
+    }
+    let (info, floor_attached) = split_fee_floor(info, &state);
+    let mut floor_left = floor_attached;
+    let denom = payment_denom(&info, &state)?;
+    let attached = extract_funds(&info, &denom)?;
+    let count = recipients.len();
+    let share = attached.multiply_ratio(1u128, count as u128);
+    let remainder = attached - share * Uint128::from(count as u128);
+
+    let mut response = Response::new()
+        .add_attribute("action", "record_multi_recipient")
+        .add_attribute("recipient_set_hash", Binary::from(set_hash.to_vec()).to_base64());
+    for (index, recipient) in recipients.into_iter().enumerate() {
+        // Any indivisible remainder is charged with the first recipient
+        let amount = if index == 0 { share + remainder } else { share };
+        let item_info = MessageInfo {
+            sender: info.sender.clone(),
+            funds: if amount.is_zero() {
+                vec![]
+            } else {
+                vec![Coin {
+                    denom: denom.clone(),
+                    amount,
+                }]
+            },
+        };
+        let params = TransferParams {
+            recipient_set_hash: Some(set_hash),
+            file_hash: file_hash.clone(),
+            recipient,
+            zk_proof: zk_proof.clone(),
+            ttl_seconds: None,
+            metadata_commitment: None,
+            proved_height,
+            wrapped_key: None,
+            file_size: None,
+            metadata_signature: None,
+            coupon: None,
+            visible_at: None,
+            memo: None,
+        };
+        let recorded = record_floored_transfer(
+            deps.branch(),
+            env.clone(),
+            item_info,
+            info.sender.clone(),
+            params,
+            &mut floor_left,
+        )?;
+        response = response
+            .add_submessages(recorded.messages)
+            .add_events(recorded.events);
+
+        if let Some(deadline) = escrow_deadline {
+            let id = NEXT_TRANSFER_ID.load(deps.storage)? - 1;
+            let transfer = TRANSFERS.load(deps.storage, id)?;
+            let held = amount - transfer.transfer_fee;
+            if !held.is_zero() {
+                let escrow = Escrow {
+                    transfer_id: id,
+                    sender: info.sender.clone(),
+                    denom: denom.clone(),
+                    amount: held,
+                    deadline,
+                };
+                ESCROWS.save(deps.storage, (&file_hash, &transfer.recipient), &escrow)?;
+                ESCROWED.update(deps.storage, &denom, |total| -> StdResult<_> {
+                    Ok(total.unwrap_or_default() + held)
+                })?;
+            }
+        }
+    }
+    settle_fee_floor(deps.storage, &state, floor_attached, floor_left)?;
+
+    Ok(response.add_attribute("count", count.to_string()))
+}
+
+// Record a transfer on behalf of a granter (grantee only)
+fn record_transfer_for(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    on_behalf_of: String,
+    params: TransferParams,
+) -> Result<Response, ContractError> {
+    let granter = deps.api.addr_validate(&on_behalf_of)?;
+    let granted = DELEGATIONS
+        .may_load(deps.storage, (&granter, &info.sender))?
+        .unwrap_or(false);
+    if !granted {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    record_transfer(deps, env, info, granter, params)
+}
+
+// Allow grantee to record transfers on the caller's behalf
+fn grant(deps: DepsMut, info: MessageInfo, grantee: String) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    let grantee = deps.api.addr_validate(&grantee)?;
+    DELEGATIONS.save(deps.storage, (&info.sender, &grantee), &true)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "grant")
+        .add_event(
+            Event::new(event_type(&state, "grant"))
+                .add_attribute("granter", info.sender)
+                .add_attribute("grantee", grantee),
+        ))
+}
+
+// Remove a previously issued grant
+fn revoke(deps: DepsMut, info: MessageInfo, grantee: String) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    let grantee = deps.api.addr_validate(&grantee)?;
+    DELEGATIONS.remove(deps.storage, (&info.sender, &grantee));
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke")
+        .add_event(
+            Event::new(event_type(&state, "revoke"))
+                .add_attribute("granter", info.sender)
+                .add_attribute("grantee", grantee),
+        ))
+}
+
+// Withdraw fees function (admin only)
+fn withdraw_fees(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+    to: Option<String>,
+    denom: Option<String>,
+) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+
+    if amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+    let denom = denom.unwrap_or_else(|| "usei".to_string());
+    if !state.accepted_denoms.contains(&denom) {
+        return Err(ContractError::UnsupportedDenom {});
+    }
+
+    // With an m-of-n admin set, withdrawals are queued until enough admins approve
+    if state.admin_threshold > 1 {
+        let proposer = info.sender.to_string();
+        if !state.admins.contains(&proposer) {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        let id = NEXT_OPERATION_ID.may_load(deps.storage)?.unwrap_or_default();
+        PENDING_OPERATIONS.save(
+            deps.storage,
+            id,
+            &PendingOperation {
+                operation: AdminOperation::WithdrawFees { amount, to, denom },
+                approvals: vec![proposer],
+            },
+        )?;
+        NEXT_OPERATION_ID.save(deps.storage, &(id + 1))?;
+
+        return Ok(Response::new()
+            .add_attribute("action", "propose_operation")
+            .add_event(
+                Event::new(event_type(&state, "propose_operation"))
+                    .add_attribute("operation_id", id.to_string())
+                    .add_attribute("operation", "withdraw_fees")
+                    .add_attribute("approvals", "1"),
+            ));
+    }
+
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    execute_withdrawal(deps, env, &state, amount, to, denom)
+}
+
+// Send accrued fees to the fee recipient or a whitelisted destination (authorization checked by caller)
+fn execute_withdrawal(
+    deps: DepsMut,
+    env: Env,
+    state: &State,
+    amount: Uint128,
+    to: Option<String>,
+    denom: String,
+) -> Result<Response, ContractError> {
+    // Only fee denoms may leave the contract; accepted denoms can change while a proposal is pending
+    if !state.accepted_denoms.contains(&denom) {
+        return Err(ContractError::UnsupportedDenom {});
+    }
+
+    // Destinations other than the fee recipient must be pre-approved
+    let to_address = match to {
+        Some(to) => {
+            let to = deps.api.addr_validate(&to)?.to_string();
+            let allowed = state.features.allowlist && state.withdraw_whitelist.contains(&to);
+            if to != state.fee_recipient && !allowed {
+                return Err(ContractError::DestinationNotWhitelisted {});
+            }
+            to
+        }
+        None => state.fee_recipient.clone(),
+    };
+
+    // Escrowed shares belong to senders and recipients, not the treasury. Only fees
+    // both accrued and actually held can leave; holding less than has accrued means
+    // the accounting has drifted from the bank balance
+    let balance = deps.querier.query_balance(&env.contract.address, &denom)?.amount;
+    let escrowed = ESCROWED.may_load(deps.storage, &denom)?.unwrap_or_default();
+    let available = balance.saturating_sub(escrowed);
+    let accrued = ACCRUED_FEES.may_load(deps.storage, &denom)?.unwrap_or_default();
+    if amount > accrued.min(available) {
+        return Err(ContractError::InsufficientFunds {});
+    }
+    let drift = accrued.saturating_sub(available);
+
+    ACCRUED_FEES.save(deps.storage, &denom, &(accrued - amount))?;
+
+    let bank_msg = BankMsg::Send {
+        to_address: to_address.clone(),
+        amount: vec![cosmwasm_std::Coin {
+            denom: denom.clone(),
+            amount,
+        }],
+    };
+
+    let event = Event::new(event_type(state, "withdraw_fees"))
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("denom", denom)
+        .add_attribute("to", to_address);
+    append_event_log(deps.storage, &env, "withdraw_fees", &event)?;
+
+    let mut response = Response::new()
+        .add_message(CosmosMsg::Bank(bank_msg))
+        .add_attribute("action", "withdraw_fees")
+        .add_event(event);
+    if !drift.is_zero() {
+        response = response.add_attribute("warning", format!("accounting_drift:{}", drift));
+    }
+    Ok(response)
+}
+
+// Add an address to the withdrawal whitelist (admin only)
+fn add_withdraw_destination(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !state.features.allowlist {
+        return Err(ContractError::FeatureDisabled { feature: "allowlist".to_string() });
+    }
+
+    let address = deps.api.addr_validate(&address)?.to_string();
+    if !state.withdraw_whitelist.contains(&address) {
+        state.withdraw_whitelist.push(address.clone());
+    }
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_withdraw_destination")
+        .add_event(
+            Event::new(event_type(&state, "add_withdraw_destination"))
+                .add_attribute("address", address),
+        ))
+}
+
+// Remove an address from the withdrawal whitelist (admin only)
+fn remove_withdraw_destination(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let address = deps.api.addr_validate(&address)?.to_string();
+    state.withdraw_whitelist.retain(|a| a != &address);
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_withdraw_destination")
+        .add_event(
+            Event::new(event_type(&state, "remove_withdraw_destination"))
+                .add_attribute("address", address),
+        ))
+}
+
+// Set fee percentage (admin only)
+fn set_fee_percentage(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    percentage: Uint128,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if percentage > Uint128::new(10000) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Fee percentage must be between 0 and 10000 (100.00%)",
+        )));
+    }
+
+    // With a notice period configured, increases can't take effect immediately
+    let current = effective_fee_percentage(&state, env.block.time.seconds());
+    if state.fee_increase_delay > 0 && percentage > current {
+        return Err(ContractError::FeeIncreaseRequiresNotice {});
+    }
+
+    state.fee_percentage = percentage;
+    state.pending_fee = None;
+    state.fee_ramp = None;
+    CONFIG.save(deps.storage, &state)?;
+
+    let event = Event::new(event_type(&state, "set_fee_percentage"))
+        .add_attribute("percentage", percentage.to_string());
+    append_event_log(deps.storage, &env, "set_fee_percentage", &event)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_fee_percentage")
+        .add_event(event))
+}
+
+// Set the fee percentage only if the rate in force still equals `expected`, so
+// concurrent governance updates can't silently overwrite each other (admin only)
+fn set_fee_percentage_cas(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    expected: Uint128,
+    new: Uint128,
+) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    if effective_fee_percentage(&state, env.block.time.seconds()) != expected {
+        return Err(ContractError::Conflict {});
+    }
+    set_fee_percentage(deps, env, info, new)
+}
+
+// Choose whether fees are taken from attached funds or added on top (admin only)
+fn set_fee_mode(deps: DepsMut, info: MessageInfo, mode: FeeMode) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    state.fee_mode = mode;
+    CONFIG.save(deps.storage, &state)?;
+
+    let mode = match mode {
+        FeeMode::FromAmount => "from_amount",
+        FeeMode::OnTop => "on_top",
+    };
+    Ok(Response::new()
+        .add_attribute("action", "set_fee_mode")
+        .add_event(Event::new(event_type(&state, "set_fee_mode")).add_attribute("mode", mode)))
+}
+
+// Set or clear the fee floor, denominated in its own fee denom (admin only). Paid in
+// that denom, a computed fee is raised to the floor; paid in another denom, the floor
+// must be attached alongside in the fee denom and is collected with the fee
+fn set_min_fee(deps: DepsMut, info: MessageInfo, min_fee: Option<Coin>) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(floor) = &min_fee {
+        if !state.accepted_denoms.contains(&floor.denom) {
+            return Err(ContractError::UnsupportedDenom {});
+        }
+    }
+    state.min_fee = min_fee;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_min_fee")
+        .add_event(
+            Event::new(event_type(&state, "set_min_fee")).add_attribute(
+                "min_fee",
+                state.min_fee.as_ref().map_or("none".to_string(), |floor| floor.to_string()),
+            ),
+        ))
+}
+
+// Set the accrued-fee level for a denom that triggers an automatic withdrawal to the
+// fee recipient; zero disables it (admin only)
+fn set_auto_withdraw_threshold(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    threshold: Uint128,
+) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if !state.accepted_denoms.contains(&denom) {
+        return Err(ContractError::UnsupportedDenom {});
+    }
+    if threshold.is_zero() {
+        AUTO_WITHDRAW_THRESHOLDS.remove(deps.storage, &denom);
+    } else {
+        AUTO_WITHDRAW_THRESHOLDS.save(deps.storage, &denom, &threshold)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_auto_withdraw_threshold")
+        .add_event(
+            Event::new(event_type(&state, "set_auto_withdraw_threshold"))
+                .add_attribute("denom", denom)
+                .add_attribute("threshold", threshold.to_string()),
+        ))
+}
+
+// Permanently block a file hash from being recorded (admin only); existing
+// transfers of it are left for revocation
+fn block_file_hash(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    file_hash: String,
+) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    BLOCKED_FILE_HASHES.save(deps.storage, &file_hash, &())?;
+
+    let event = Event::new(event_type(&state, "block_file_hash")).add_attribute("file_hash", file_hash);
+    append_event_log(deps.storage, &env, "block_file_hash", &event)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "block_file_hash")
+        .add_event(event))
+}
+
+// Lift a file hash block (admin only)
+fn unblock_file_hash(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    file_hash: String,
+) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    BLOCKED_FILE_HASHES.remove(deps.storage, &file_hash);
+
+    let event = Event::new(event_type(&state, "unblock_file_hash")).add_attribute("file_hash", file_hash);
+    append_event_log(deps.storage, &env, "unblock_file_hash", &event)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "unblock_file_hash")
+        .add_event(event))
+}
+
+// Revoke a transfer (sender or admin); the record is kept for audit
+fn revoke_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    file_hash: String,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+
+    // The key always points at the latest record, so older revoked entries are skipped
+    let id = TRANSFER_KEYS
+        .may_load(deps.storage, (&file_hash, &recipient))?
+        .ok_or(ContractError::TransferNotFound {})?;
+    let mut transfer = TRANSFERS.load(deps.storage, id)?;
+    if transfer.status == TransferStatus::Revoked {
+        return Err(ContractError::AlreadyRevoked {});
+    }
+    if info.sender != transfer.sender && info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    transfer.status = TransferStatus::Revoked;
+    transfer.revoked_at = Some(env.block.time.seconds());
+    save_transfer(deps.storage, id, &mut transfer)?;
+    // Revoked records stay for audit, so auto-prune must not reach them
+    if let Some(expires_at) = transfer.expires_at {
+        TRANSFER_EXPIRIES.remove(deps.storage, (expires_at, id));
+    }
+
+    let event = Event::new(event_type(&state, "revoke_transfer"))
+        .add_attribute("file_hash", file_hash)
+        .add_attribute("recipient", recipient)
+        .add_attribute("revoked_by", info.sender);
+    append_event_log(deps.storage, &env, "revoke_transfer", &event)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_transfer")
+        .add_event(event))
+}
+
+// Set whether revoked (file_hash, recipient) keys may be recorded again (admin only)
+fn set_allow_rerecord(
+    deps: DepsMut,
+    info: MessageInfo,
+    allow: bool,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    state.allow_rerecord = allow;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_allow_rerecord")
+        .add_event(
+            Event::new(event_type(&state, "set_allow_rerecord"))
+                .add_attribute("allow", allow.to_string()),
+        ))
+}
+
+// Configure automatic fee splits to stakeholders (admin only)
+fn set_fee_splits(
+    deps: DepsMut,
+    info: MessageInfo,
+    splits: Vec<(String, u16)>,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let total: u32 = splits.iter().map(|(_, bps)| *bps as u32).sum();
+    if total > 10000 {
+        return Err(ContractError::InvalidFeeSplits {});
+    }
+
+    state.fee_splits = splits
+        .into_iter()
+        .map(|(addr, bps)| Ok((deps.api.addr_validate(&addr)?, bps)))
+        .collect::<StdResult<_>>()?;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_fee_splits")
+        .add_event(
+            Event::new(event_type(&state, "set_fee_splits"))
+                .add_attribute("total_bps", total.to_string()),
+        ))
+}
+
+// Set or clear the name-service contract used to resolve recipients (admin only)
+fn set_name_service(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    state.name_service = address.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_name_service")
+        .add_event(
+            Event::new(event_type(&state, "set_name_service")).add_attribute(
+                "address",
+                state.name_service.as_ref().map_or("none".to_string(), |a| a.to_string()),
+            ),
+        ))
+}
+
+// Cap the TTL senders may request; None removes the cap (admin only)
+fn set_max_ttl(
+    deps: DepsMut,
+    info: MessageInfo,
+    max_ttl_seconds: Option<u64>,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    state.max_ttl_seconds = max_ttl_seconds;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_max_ttl")
+        .add_event(
+            Event::new(event_type(&state, "set_max_ttl")).add_attribute(
+                "max_ttl_seconds",
+                max_ttl_seconds.map_or("none".to_string(), |max| max.to_string()),
+            ),
+        ))
+}
+
+// Denom of the attached payment; unfunded records fall back to the first accepted denom
+fn payment_denom(info: &MessageInfo, state: &State) -> Result<String, ContractError> {
+    match info.funds.first() {
+        Some(coin) if state.accepted_denoms.contains(&coin.denom) => Ok(coin.denom.clone()),
+        Some(coin) => Err(ContractError::UnexpectedFunds { denom: coin.denom.clone() }),
+        None => Ok(state.accepted_denoms[0].clone()),
+    }
+}
+
+// Set aside coins attached in the min-fee denom when paying in another denom, returning
+// the remaining funds and the set-aside amount
+fn split_fee_floor(info: MessageInfo, state: &State) -> (MessageInfo, Uint128) {
+    let floor_denom = match &state.min_fee {
+        Some(floor) if info.funds.iter().any(|c| c.denom != floor.denom) => &floor.denom,
+        _ => return (info, Uint128::zero()),
+    };
+    let (floor, funds): (Vec<Coin>, Vec<Coin>) =
+        info.funds.into_iter().partition(|c| &c.denom == floor_denom);
+    let attached = floor.iter().map(|c| c.amount).sum();
+    (MessageInfo { sender: info.sender, funds }, attached)
+}
+
+// Collect whatever floor coins the records left over: an overpayment is kept with the
+// fees, but a floor attached when no record owed one is rejected
+fn settle_fee_floor(
+    storage: &mut dyn Storage,
+    state: &State,
+    attached: Uint128,
+    left: Uint128,
+) -> Result<(), ContractError> {
+    let floor = match &state.min_fee {
+        Some(floor) if !left.is_zero() => floor,
+        _ => return Ok(()),
+    };
+    if left == attached {
+        return Err(ContractError::UnexpectedFunds { denom: floor.denom.clone() });
+    }
+    ACCRUED_FEES.update(storage, &floor.denom, |accrued| -> StdResult<_> {
+        Ok(accrued.unwrap_or_default() + left)
+    })?;
+    Ok(())
+}
+
+// Amount attached in the expected denom; any other denom is rejected rather than silently kept
+fn extract_funds(info: &MessageInfo, denom: &str) -> Result<Uint128, ContractError> {
+    if let Some(coin) = info.funds.iter().find(|c| c.denom != denom) {
+        return Err(ContractError::UnexpectedFunds { denom: coin.denom.clone() });
+    }
+    Ok(info
+        .funds
+        .iter()
+        .filter(|c| c.denom == denom)
+        .map(|c| c.amount)
+        .sum())
+}
+
+// Set the number of fee-exempt transfers each sender gets (admin only)
+fn set_free_transfer_quota(
+    deps: DepsMut,
+    info: MessageInfo,
+    quota: u32,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    state.free_transfer_quota = quota;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_free_transfer_quota")
+        .add_event(
+            Event::new(event_type(&state, "set_free_transfer_quota"))
+                .add_attribute("quota", quota.to_string()),
+        ))
+}
+
+// Announce a fee change (admin only); increases apply after the notice delay, decreases immediately
+fn propose_fee_percentage(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    percentage: Uint128,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if percentage > Uint128::new(10000) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Fee percentage must be between 0 and 10000 (100.00%)",
+        )));
+    }
+
+    // Settle any announced increase that has already taken effect
+    let now = env.block.time.seconds();
+    state.fee_percentage = effective_fee_percentage(&state, now);
+    state.fee_ramp = None;
+
+    let effective_at = if percentage > state.fee_percentage {
+        let effective_at = now + state.fee_increase_delay;
+        state.pending_fee = Some(PendingFee {
+            percentage,
+            effective_at,
+        });
+        effective_at
+    } else {
+        state.fee_percentage = percentage;
+        state.pending_fee = None;
+        now
+    };
+    CONFIG.save(deps.storage, &state)?;
+
+    let event = Event::new(event_type(&state, "propose_fee_percentage"))
+        .add_attribute("percentage", percentage.to_string())
+        .add_attribute("effective_at", effective_at.to_string());
+    append_event_log(deps.storage, &env, "propose_fee_percentage", &event)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "propose_fee_percentage")
+        .add_event(event))
+}
+
+// Set the notice period required before fee increases apply (admin only)
+fn set_fee_increase_delay(
+    deps: DepsMut,
+    info: MessageInfo,
+    seconds: u64,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    state.fee_increase_delay = seconds;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_fee_increase_delay")
+        .add_event(
+            Event::new(event_type(&state, "set_fee_increase_delay"))
+                .add_attribute("seconds", seconds.to_string()),
+        ))
+}
+
+// Toggle the extra gas-profiling attributes on record_transfer (admin only)
+fn set_verbose_events(
+    deps: DepsMut,
+    info: MessageInfo,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    state.verbose_events = enabled;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_verbose_events")
+        .add_event(
+            Event::new(event_type(&state, "set_verbose_events"))
+                .add_attribute("enabled", enabled.to_string()),
+        ))
+}
+
+// Replace the set of denoms accepted for transfer payments (admin only)
+fn set_accepted_denoms(
+    deps: DepsMut,
+    info: MessageInfo,
+    denoms: Vec<String>,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    validate_accepted_denoms(&denoms)?;
+
+    state.accepted_denoms = denoms;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_accepted_denoms")
+        .add_event(
+            Event::new(event_type(&state, "set_accepted_denoms"))
+                .add_attribute("denoms", state.accepted_denoms.join(",")),
+        ))
+}
+
+// Set how many blocks a proof's committed height may differ from the current one (admin only)
+fn set_proof_freshness_window(
+    deps: DepsMut,
+    info: MessageInfo,
+    blocks: Option<u64>,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    state.proof_freshness_window = blocks;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_proof_freshness_window")
+        .add_event(
+            Event::new(event_type(&state, "set_proof_freshness_window"))
+                .add_attribute("blocks", blocks.map_or("none".to_string(), |b| b.to_string())),
+        ))
+}
+
+// Opt the caller in to receiving transfers
+fn opt_in(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    OPTED_IN.save(deps.storage, &info.sender, &true)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "opt_in")
+        .add_event(Event::new(event_type(&state, "opt_in")).add_attribute("recipient", info.sender)))
+}
+
+// Withdraw the caller's opt-in
+fn opt_out(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    OPTED_IN.remove(deps.storage, &info.sender);
+
+    Ok(Response::new()
+        .add_attribute("action", "opt_out")
+        .add_event(Event::new(event_type(&state, "opt_out")).add_attribute("recipient", info.sender)))
+}
+
+// Require recipients to opt in before transfers can be recorded to them (admin only)
+fn set_opt_in_required(
+    deps: DepsMut,
+    info: MessageInfo,
+    required: bool,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !state.features.opt_in {
+        return Err(ContractError::FeatureDisabled { feature: "opt_in".to_string() });
+    }
+
+    state.opt_in_required = required;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_opt_in_required")
+        .add_event(
+            Event::new(event_type(&state, "set_opt_in_required"))
+                .add_attribute("required", required.to_string()),
+        ))
+}
+
+// Configure the m-of-n admin set for sensitive operations (admin only)
+fn set_admins(
+    deps: DepsMut,
+    info: MessageInfo,
+    admins: Vec<String>,
+    threshold: u32,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut validated: Vec<String> = vec![];
+    for admin in admins {
+        let admin = deps.api.addr_validate(&admin)?.to_string();
+        if !validated.contains(&admin) {
+            validated.push(admin);
+        }
+    }
+    if threshold == 0 || threshold as usize > validated.len().max(1) {
+        return Err(ContractError::InvalidThreshold {});
+    }
+
+    state.admins = validated;
+    state.admin_threshold = threshold;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_admins")
+        .add_event(
+            Event::new(event_type(&state, "set_admins"))
+                .add_attribute("admins", match state.admins.as_slice() {
+                    [] => "none".to_string(),
+                    admins => admins.join(","),
+                })
+                .add_attribute("threshold", threshold.to_string()),
+        ))
+}
+
+// Approve a pending operation; it executes once the approval threshold is met
+fn approve_operation(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    let approver = info.sender.to_string();
+    if !state.admins.contains(&approver) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut pending = PENDING_OPERATIONS
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::OperationNotFound {})?;
+    if pending.approvals.contains(&approver) {
+        return Err(ContractError::AlreadyApproved {});
+    }
+    pending.approvals.push(approver);
+    // Approvals from admins removed since they approved no longer count
+    pending.approvals.retain(|admin| state.admins.contains(admin));
+
+    if (pending.approvals.len() as u32) < state.admin_threshold {
+        PENDING_OPERATIONS.save(deps.storage, id, &pending)?;
+        return Ok(Response::new()
+            .add_attribute("action", "approve_operation")
+            .add_event(
+                Event::new(event_type(&state, "approve_operation"))
+                    .add_attribute("operation_id", id.to_string())
+                    .add_attribute("approvals", pending.approvals.len().to_string()),
+            ));
+    }
+
+    PENDING_OPERATIONS.remove(deps.storage, id);
+    match pending.operation {
+        AdminOperation::WithdrawFees { amount, to, denom } => {
+            execute_withdrawal(deps, env, &state, amount, to, denom)
+        }
+    }
+}
+
+// Designate who may take over after the admin has been inactive for a period (admin only)
+fn set_recovery_admin(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: Option<String>,
+    inactivity_seconds: u64,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    state.recovery_admin = address
+        .map(|addr| deps.api.addr_validate(&addr).map(|a| a.to_string()))
+        .transpose()?;
+    state.admin_inactivity_period = inactivity_seconds;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_recovery_admin")
+        .add_event(
+            Event::new(event_type(&state, "set_recovery_admin"))
+                .add_attribute("recovery_admin", state.recovery_admin.clone().unwrap_or_else(|| "none".to_string()))
+                .add_attribute("inactivity_seconds", inactivity_seconds.to_string()),
+        ))
+}
+
+// Let the recovery admin take over once the admin has been inactive long enough
+fn claim_admin(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    // A zero inactivity period leaves the switch disarmed
+    if state.recovery_admin.as_deref() != Some(info.sender.as_str()) || state.admin_inactivity_period == 0 {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let now = env.block.time.seconds();
+    if now < state.last_admin_action.saturating_add(state.admin_inactivity_period) {
+        return Err(ContractError::AdminStillActive {});
+    }
+
+    let previous_admin = std::mem::replace(&mut state.admin, info.sender.to_string());
+    state.recovery_admin = None;
+    state.last_admin_action = now;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "claim_admin")
+        .add_event(
+            Event::new(event_type(&state, "claim_admin"))
+                .add_attribute("previous_admin", previous_admin)
+                .add_attribute("new_admin", info.sender),
+        ))
+}
+
+// Set or clear a denom-specific fee rate (admin only)
+fn set_denom_fee_percentage(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    percentage: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // A rate for a denom that can't be paid in would never apply; clearing one left
+    // behind by a since-removed denom is still allowed
+    if percentage.is_some() && !state.accepted_denoms.contains(&denom) {
+        return Err(ContractError::UnsupportedDenom {});
+    }
+    match percentage {
+        Some(percentage) => {
+            if percentage > Uint128::new(10000) {
+                return Err(ContractError::Std(StdError::generic_err(
+                    "Fee percentage must be between 0 and 10000 (100.00%)",
+                )));
+            }
+            DENOM_FEE_PERCENTAGES.save(deps.storage, &denom, &percentage)?;
+        }
+        None => DENOM_FEE_PERCENTAGES.remove(deps.storage, &denom),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_denom_fee_percentage")
+        .add_event(
+            Event::new(event_type(&state, "set_denom_fee_percentage"))
+                .add_attribute("denom", denom)
+                .add_attribute("percentage", percentage.map_or("default".to_string(), |p| p.to_string())),
+        ))
+}
+
+// Set or clear the price oracle used for display-denom fee quotes (admin only)
+fn set_price_oracle(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    state.price_oracle = address.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_price_oracle")
+        .add_event(
+            Event::new(event_type(&state, "set_price_oracle")).add_attribute(
+                "address",
+                state.price_oracle.as_ref().map_or("none".to_string(), |a| a.to_string()),
+            ),
+        ))
+}
+
+// Set or clear the external contract proofs are verified by (admin only)
+fn set_verifier_contract(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    state.verifier_contract = address.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_verifier_contract")
+        .add_event(
+            Event::new(event_type(&state, "set_verifier_contract")).add_attribute(
+                "address",
+                state.verifier_contract.as_ref().map_or("none".to_string(), |a| a.to_string()),
+            ),
+        ))
+}
+
+// Commit the verifying key proofs are checked against, recording its SHA-256 so
+// clients can confirm their proving key matches before proving (admin only)
+fn set_verifying_key(
+    deps: DepsMut,
+    info: MessageInfo,
+    verifying_key: Binary,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if zk_proof::read_verifying_key(&verifying_key).is_none() {
+        return Err(ContractError::Std(StdError::generic_err("Invalid verifying key")));
+    }
+    let hash = Binary::from(Sha256::digest(&verifying_key).to_vec());
+    VERIFYING_KEY.save(deps.storage, &verifying_key)?;
+    state.verifying_key_hash = Some(hash.clone());
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_verifying_key")
+        .add_event(
+            Event::new(event_type(&state, "set_verifying_key")).add_attribute("hash", hash.to_base64()),
+        ))
+}
+
+// Advance the proof epoch, rejecting every proof generated for an earlier one, e.g.
+// during key rotation (admin only)
+fn bump_epoch(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    state.current_epoch += 1;
+    CONFIG.save(deps.storage, &state)?;
+
+    let event = Event::new(event_type(&state, "bump_epoch"))
+        .add_attribute("epoch", state.current_epoch.to_string());
+    append_event_log(deps.storage, &env, "bump_epoch", &event)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "bump_epoch")
+        .add_event(event))
+}
+
+// Toggle privacy mode, omitting transfer details from record events (admin only)
+fn set_minimal_events(
+    deps: DepsMut,
+    info: MessageInfo,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    state.minimal_events = enabled;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_minimal_events")
+        .add_event(
+            Event::new(event_type(&state, "set_minimal_events"))
+                .add_attribute("enabled", enabled.to_string()),
+        ))
+}
+
+// Cap the declared file_size of recorded transfers; None removes the cap (admin only)
+fn set_max_file_size(
+    deps: DepsMut,
+    info: MessageInfo,
+    max_file_size: Option<u64>,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    state.max_file_size = max_file_size;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_max_file_size")
+        .add_event(
+            Event::new(event_type(&state, "set_max_file_size")).add_attribute(
+                "max_file_size",
+                max_file_size.map_or("none".to_string(), |max| max.to_string()),
+            ),
+        ))
+}
+
+// Set the share of each fee, in basis points, that is burned instead of accrued (admin only)
+fn set_burn_percentage(
+    deps: DepsMut,
+    info: MessageInfo,
+    percentage: Uint128,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if percentage > Uint128::new(10000) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Burn percentage must be between 0 and 10000 (100.00%)",
+        )));
+    }
+
+    state.burn_percentage = percentage;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_burn_percentage")
+        .add_event(
+            Event::new(event_type(&state, "set_burn_percentage"))
+                .add_attribute("percentage", percentage.to_string()),
+        ))
+}
+
+// Register the caller's secp256k1 public key for metadata and delivery signatures
+fn register_pubkey(deps: DepsMut, info: MessageInfo, pubkey: Binary) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    ACCOUNT_PUBKEYS.save(deps.storage, &info.sender, &pubkey)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "register_pubkey")
+        .add_event(Event::new(event_type(&state, "register_pubkey")).add_attribute("sender", info.sender)))
+}
+
+// Set how many transfers a single list query may scan before returning early (admin only)
+fn set_query_scan_limit(deps: DepsMut, info: MessageInfo, limit: u32) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if limit == 0 {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Query scan limit must be greater than zero",
+        )));
+    }
+
+    state.query_scan_limit = limit;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_query_scan_limit")
+        .add_event(
+            Event::new(event_type(&state, "set_query_scan_limit")).add_attribute("limit", limit.to_string()),
+        ))
+}
+
+// Replace the per-query result caps (admin only)
+fn set_query_limits(deps: DepsMut, info: MessageInfo, limits: QueryLimits) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let all = [
+        limits.transfers,
+        limits.recent_transfers,
+        limits.event_log,
+        limits.updated_since,
+        limits.export_snapshot,
+    ];
+    if all.iter().any(|limit| limit.default == 0 || limit.default > limit.max) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Query limit defaults must be nonzero and at most their max",
+        )));
+    }
+
+    state.query_limits = limits;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_query_limits")
+        .add_event(Event::new(event_type(&state, "set_query_limits"))))
+}
+
+// Cap the fees a sender may pay within a rolling window; None lifts the cap (admin only)
+fn set_spend_cap(deps: DepsMut, info: MessageInfo, cap: Option<SpendCap>) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    if cap.is_some_and(|cap| cap.window_seconds == 0) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Spend cap window must be nonzero",
+        )));
+    }
+
+    state.spend_cap = cap;
+    CONFIG.save(deps.storage, &state)?;
+
+    let mut event = Event::new(event_type(&state, "set_spend_cap"));
+    if let Some(cap) = cap {
+        event = event
+            .add_attribute("amount", cap.amount.to_string())
+            .add_attribute("window_seconds", cap.window_seconds.to_string());
+    }
+    Ok(Response::new()
+        .add_attribute("action", "set_spend_cap")
+        .add_event(event))
+}
+
+// Require recipients to be addresses with the given bech32 prefix, e.g. "sei"; None
+// accepts any recipient (admin only)
+fn set_required_prefix(
+    deps: DepsMut,
+    info: MessageInfo,
+    prefix: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let valid = |p: &String| !p.is_empty() && p.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit());
+    if prefix.as_ref().is_some_and(|p| !valid(p)) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Prefix must be non-empty lowercase alphanumeric",
+        )));
+    }
+
+    state.required_prefix = prefix;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_required_prefix")
+        .add_event(
+            Event::new(event_type(&state, "set_required_prefix"))
+                .add_attribute("prefix", state.required_prefix.as_deref().unwrap_or("none")),
+        ))
+}
+
+// Have each record prune up to `limit` expired transfers (admin only)
+fn set_auto_prune(deps: DepsMut, info: MessageInfo, enabled: bool, limit: u32) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if limit == 0 || limit > MAX_AUTO_PRUNE_LIMIT {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "Auto-prune limit must be between 1 and {}",
+            MAX_AUTO_PRUNE_LIMIT
+        ))));
+    }
+
+    state.auto_prune = enabled;
+    state.auto_prune_limit = limit;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_auto_prune")
+        .add_event(
+            Event::new(event_type(&state, "set_auto_prune"))
+                .add_attribute("enabled", enabled.to_string())
+                .add_attribute("limit", limit.to_string()),
+        ))
+}
+
+// Set the most recipients one multi-recipient record may address (admin only)
+fn set_max_recipients(deps: DepsMut, info: MessageInfo, max: u32) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if max == 0 {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Max recipients must be greater than zero",
+        )));
+    }
+
+    state.max_recipients = max;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_max_recipients")
+        .add_event(
+            Event::new(event_type(&state, "set_max_recipients")).add_attribute("max", max.to_string()),
+        ))
+}
+
+// Schedule a linear move of the fee rate to `target` between `start` and `end` (admin only)
+fn schedule_fee_ramp(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    amount: Uint128,
+    target: Uint128,
+    start: u64,
+    end: u64,
 ) -> Result<Response, ContractError> {
-    let state: State = deps.storage.get(b"state").and_then(|data| Ok(cosmwasm_std::from_json(data)?)).unwrap();
+    let mut state = CONFIG.load(deps.storage)?;
     if info.sender != state.admin {
         return Err(ContractError::Unauthorized {});
     }
 
-    let balance = query_balance(deps.as_ref(), &_env.contract.address)?;
-    if balance < amount {
-        return Err(ContractError::InsufficientFunds {});
+    if target > Uint128::new(10000) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Fee percentage must be between 0 and 10000 (100.00%)",
+        )));
+    }
+    if start >= end {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Fee ramp must end after it starts",
+        )));
     }
 
-    let bank_msg = BankMsg::Send {
-        to_address: info.sender.to_string(),
-        amount: vec![cosmwasm_std::Coin {
-            denom: "usei".to_string(),
-            amount,
-        }],
+    // Settle whatever rate is in force now; it becomes the ramp's starting point
+    let now = env.block.time.seconds();
+    let from = effective_fee_percentage(&state, now);
+    if target > from && start < now + state.fee_increase_delay {
+        return Err(ContractError::FeeIncreaseRequiresNotice {});
+    }
+
+    state.fee_percentage = from;
+    state.pending_fee = None;
+    state.fee_ramp = Some(FeeRamp {
+        from,
+        target,
+        start,
+        end,
+    });
+    CONFIG.save(deps.storage, &state)?;
+
+    let event = Event::new(event_type(&state, "schedule_fee_ramp"))
+        .add_attribute("from", from.to_string())
+        .add_attribute("target", target.to_string())
+        .add_attribute("start", start.to_string())
+        .add_attribute("end", end.to_string());
+    append_event_log(deps.storage, &env, "schedule_fee_ramp", &event)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "schedule_fee_ramp")
+        .add_event(event))
+}
+
+// Acknowledge receipt of a live transfer (recipient only)
+fn acknowledge_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    file_hash: String,
+) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    let recipient = info.sender.to_string();
+
+    let id = TRANSFER_KEYS
+        .may_load(deps.storage, (&file_hash, &recipient))?
+        .ok_or(ContractError::TransferNotFound {})?;
+    let mut transfer = with_current_status(TRANSFERS.load(deps.storage, id)?, env.block.time.seconds());
+    if transfer.status != TransferStatus::Recorded {
+        return Err(ContractError::InvalidStatusTransition {
+            from: transfer.status,
+            to: TransferStatus::Acknowledged,
+        });
+    }
+
+    transfer.status = TransferStatus::Acknowledged;
+    save_transfer(deps.storage, id, &mut transfer)?;
+
+    // Acknowledging releases any escrowed share held for this transfer
+    let mut response = Response::new();
+    if let Some(escrow) = ESCROWS.may_load(deps.storage, (&file_hash, &recipient))? {
+        if escrow.transfer_id == id {
+            ESCROWS.remove(deps.storage, (&file_hash, &recipient));
+            release_escrowed(deps.storage, &escrow)?;
+            response = response.add_message(BankMsg::Send {
+                to_address: recipient.clone(),
+                amount: vec![Coin {
+                    denom: escrow.denom,
+                    amount: escrow.amount,
+                }],
+            });
+        }
+    }
+
+    Ok(response
+        .add_attribute("action", "acknowledge_transfer")
+        .add_event(
+            Event::new(event_type(&state, "acknowledge_transfer"))
+                .add_attribute("transfer_id", id.to_string())
+                .add_attribute("recipient", recipient),
+        ))
+}
+
+// Refund the sender's escrowed shares for a file_hash whose recipients haven't
+// acknowledged by the deadline; acknowledged shares were already released
+fn reclaim_escrow(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    file_hash: String,
+) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    let now = env.block.time.seconds();
+
+    let reclaimable = ESCROWS
+        .prefix(&file_hash)
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter(|item| {
+            item.as_ref()
+                .map_or(true, |(_, escrow)| escrow.sender == info.sender && now >= escrow.deadline)
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    if reclaimable.is_empty() {
+        return Err(ContractError::NothingToReclaim {});
+    }
+
+    let mut refunds = vec![];
+    for (recipient, escrow) in reclaimable {
+        if TRANSFERS.load(deps.storage, escrow.transfer_id)?.status == TransferStatus::Acknowledged {
+            continue;
+        }
+        ESCROWS.remove(deps.storage, (&file_hash, &recipient));
+        release_escrowed(deps.storage, &escrow)?;
+        let refund = Coin {
+            denom: escrow.denom,
+            amount: escrow.amount,
+        };
+        let send = BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![refund.clone()],
+        };
+        // With a fallback configured, a failed refund is rerouted from the reply handler
+        refunds.push(match state.fallback_recipient {
+            Some(_) => {
+                let reply_id = NEXT_REPLY_ID.may_load(deps.storage)?.unwrap_or_default();
+                NEXT_REPLY_ID.save(deps.storage, &(reply_id + 1))?;
+                REFUND_REPLIES.save(deps.storage, reply_id, &refund)?;
+                SubMsg::reply_always(send, reply_id)
+            }
+            None => SubMsg::new(send),
+        });
+    }
+
+    let reclaimed = refunds.len();
+    Ok(Response::new()
+        .add_submessages(refunds)
+        .add_attribute("action", "reclaim_escrow")
+        .add_event(
+            Event::new(event_type(&state, "reclaim_escrow"))
+                .add_attribute("file_hash", file_hash)
+                .add_attribute("sender", info.sender)
+                .add_attribute("reclaimed", reclaimed.to_string()),
+        ))
+}
+
+// Move up to `limit` escrows left unclaimed for ESCROW_ABANDON_PERIOD past their
+// deadline into accrued fees (admin only)
+fn sweep_abandoned_escrow(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    limit: u32,
+) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    let now = env.block.time.seconds();
+
+    // A deadline too far out to extend by the abandon period is never abandoned
+    let abandoned = ESCROWS
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter(|item| {
+            item.as_ref().map_or(true, |(_, escrow)| {
+                escrow
+                    .deadline
+                    .checked_add(ESCROW_ABANDON_PERIOD)
+                    .is_some_and(|abandoned_at| now >= abandoned_at)
+            })
+        })
+        .take(limit.min(MAX_PAGE_LIMIT) as usize)
+        .collect::<StdResult<Vec<_>>>()?;
+    if abandoned.is_empty() {
+        return Err(ContractError::NothingToReclaim {});
+    }
+
+    let mut swept = 0u32;
+    for ((file_hash, recipient), escrow) in abandoned {
+        if TRANSFERS.load(deps.storage, escrow.transfer_id)?.status == TransferStatus::Acknowledged {
+            continue;
+        }
+        ESCROWS.remove(deps.storage, (&file_hash, &recipient));
+        release_escrowed(deps.storage, &escrow)?;
+        ACCRUED_FEES.update(deps.storage, &escrow.denom, |accrued| -> StdResult<_> {
+            Ok(accrued.unwrap_or_default() + escrow.amount)
+        })?;
+        swept += 1;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "sweep_abandoned_escrow")
+        .add_event(
+            Event::new(event_type(&state, "sweep_abandoned_escrow")).add_attribute("swept", swept.to_string()),
+        ))
+}
+
+// Drop an escrow's amount from the per-denom escrowed total
+fn release_escrowed(storage: &mut dyn Storage, escrow: &Escrow) -> StdResult<Uint128> {
+    ESCROWED.update(storage, &escrow.denom, |total| -> StdResult<_> {
+        Ok(total.unwrap_or_default().saturating_sub(escrow.amount))
+    })
+}
+
+// Set which senders and payment denoms may record without proof verification (admin only)
+fn set_verification_policy(
+    deps: DepsMut,
+    info: MessageInfo,
+    trusted_senders: Vec<String>,
+    trusted_denoms: Vec<String>,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    state.verification_policy = VerificationPolicy {
+        trusted_senders: trusted_senders
+            .iter()
+            .map(|addr| deps.api.addr_validate(addr))
+            .collect::<StdResult<_>>()?,
+        trusted_denoms,
+    };
+    CONFIG.save(deps.storage, &state)?;
+
+    // Attribute values must be non-empty
+    let trusted_denoms = match state.verification_policy.trusted_denoms.as_slice() {
+        [] => "none".to_string(),
+        denoms => denoms.join(","),
+    };
+    Ok(Response::new()
+        .add_attribute("action", "set_verification_policy")
+        .add_event(
+            Event::new(event_type(&state, "set_verification_policy"))
+                .add_attribute("trusted_senders", state.verification_policy.trusted_senders.len().to_string())
+                .add_attribute("trusted_denoms", trusted_denoms),
+        ))
+}
+
+// Pause or unpause new records (admin only); unpausing restarts the auto-pause window
+fn set_paused(deps: DepsMut, info: MessageInfo, paused: bool) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !state.features.pausable {
+        return Err(ContractError::FeatureDisabled { feature: "pausable".to_string() });
+    }
+
+    state.paused = paused;
+    CONFIG.save(deps.storage, &state)?;
+    if !paused {
+        RECENT_RECORD_TIMES.remove(deps.storage);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_paused")
+        .add_event(Event::new(event_type(&state, "set_paused")).add_attribute("paused", paused.to_string())))
+}
+
+// Pause automatically once more than `threshold` records land within `window_seconds`;
+// a zero threshold disables it (admin only)
+fn set_auto_pause(
+    deps: DepsMut,
+    info: MessageInfo,
+    threshold: u32,
+    window_seconds: u64,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !state.features.pausable {
+        return Err(ContractError::FeatureDisabled { feature: "pausable".to_string() });
+    }
+
+    state.auto_pause_threshold = threshold;
+    state.auto_pause_window = window_seconds;
+    CONFIG.save(deps.storage, &state)?;
+    RECENT_RECORD_TIMES.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "set_auto_pause")
+        .add_event(
+            Event::new(event_type(&state, "set_auto_pause"))
+                .add_attribute("threshold", threshold.to_string())
+                .add_attribute("window_seconds", window_seconds.to_string()),
+        ))
+}
+
+// Issue one-time fee waiver codes (admin only); codes already issued keep their state
+fn issue_coupons(deps: DepsMut, info: MessageInfo, codes: Vec<String>) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut issued = 0u32;
+    for code in &codes {
+        if !COUPONS.has(deps.storage, code) {
+            COUPONS.save(deps.storage, code, &false)?;
+            issued += 1;
+        }
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "issue_coupons")
+        .add_event(
+            Event::new(event_type(&state, "issue_coupons")).add_attribute("issued", issued.to_string()),
+        ))
+}
+
+// Store the recipient's signature over the file_hash as proof of delivery (recipient only)
+fn confirm_delivery(
+    deps: DepsMut,
+    info: MessageInfo,
+    file_hash: String,
+    signature: Binary,
+) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    let recipient = info.sender.to_string();
+
+    let id = TRANSFER_KEYS
+        .may_load(deps.storage, (&file_hash, &recipient))?
+        .ok_or(ContractError::TransferNotFound {})?;
+    let mut transfer = TRANSFERS.load(deps.storage, id)?;
+    if transfer.status == TransferStatus::Revoked {
+        return Err(ContractError::AlreadyRevoked {});
+    }
+
+    let pubkey = ACCOUNT_PUBKEYS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::InvalidSignature {})?;
+    let message_hash = Sha256::digest(file_hash.as_bytes());
+    let valid = deps
+        .api
+        .secp256k1_verify(&message_hash, &signature, &pubkey)
+        .unwrap_or(false);
+    if !valid {
+        return Err(ContractError::InvalidSignature {});
+    }
+
+    transfer.delivery_signature = Some(signature);
+    save_transfer(deps.storage, id, &mut transfer)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "confirm_delivery")
+        .add_event(
+            Event::new(event_type(&state, "confirm_delivery"))
+                .add_attribute("transfer_id", id.to_string())
+                .add_attribute("recipient", recipient),
+        ))
+}
+
+// Set the minimum block gap between proof-verifying records, globally or per sender (admin only)
+fn set_min_verify_interval(
+    deps: DepsMut,
+    info: MessageInfo,
+    blocks: u64,
+    per_sender: bool,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    state.min_verify_interval = blocks;
+    state.verify_interval_per_sender = per_sender;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_min_verify_interval")
+        .add_event(
+            Event::new(event_type(&state, "set_min_verify_interval"))
+                .add_attribute("blocks", blocks.to_string())
+                .add_attribute("per_sender", per_sender.to_string()),
+        ))
+}
+
+// Set or clear the minimum amount a record must attach in a denom (admin only)
+fn set_min_transfer_amount(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    // As with denom fee rates, a minimum for an unaccepted denom could never apply
+    if amount.is_some() && !state.accepted_denoms.contains(&denom) {
+        return Err(ContractError::UnsupportedDenom {});
+    }
+
+    match amount {
+        Some(amount) => MIN_TRANSFER_AMOUNTS.save(deps.storage, &denom, &amount)?,
+        None => MIN_TRANSFER_AMOUNTS.remove(deps.storage, &denom),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_min_transfer_amount")
+        .add_event(
+            Event::new(event_type(&state, "set_min_transfer_amount"))
+                .add_attribute("denom", denom)
+                .add_attribute("amount", amount.map_or("none".to_string(), |a| a.to_string())),
+        ))
+}
+
+// Set or clear a negotiated fee for a sender's records of a file (admin only); a
+// coupon still waives it, and it never exceeds the attached amount
+fn set_fee_override(
+    deps: DepsMut,
+    info: MessageInfo,
+    sender: String,
+    file_hash: String,
+    fee: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let sender = deps.api.addr_validate(&sender)?;
+    match fee {
+        Some(fee) => FEE_OVERRIDES.save(deps.storage, (&sender, &file_hash), &fee)?,
+        None => FEE_OVERRIDES.remove(deps.storage, (&sender, &file_hash)),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_fee_override")
+        .add_event(
+            Event::new(event_type(&state, "set_fee_override"))
+                .add_attribute("sender", sender)
+                .add_attribute("file_hash", file_hash)
+                .add_attribute("fee", fee.map_or("none".to_string(), |f| f.to_string())),
+        ))
+}
+
+// Set or clear a denom's decimal exponent for fee normalization (admin only)
+fn set_denom_exponent(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    exponent: Option<u32>,
+) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    match exponent {
+        Some(exponent) => {
+            if exponent > 2 * NORMALIZED_EXPONENT {
+                return Err(ContractError::Std(StdError::generic_err(format!(
+                    "Denom exponent must be at most {}",
+                    2 * NORMALIZED_EXPONENT
+                ))));
+            }
+            DENOM_EXPONENTS.save(deps.storage, &denom, &exponent)?;
+        }
+        None => DENOM_EXPONENTS.remove(deps.storage, &denom),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_denom_exponent")
+        .add_event(
+            Event::new(event_type(&state, "set_denom_exponent"))
+                .add_attribute("denom", denom)
+                .add_attribute("exponent", exponent.map_or("default".to_string(), |e| e.to_string())),
+        ))
+}
+
+// Push back a live transfer's expiry, keeping its total TTL within max_ttl (sender only)
+fn extend_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    file_hash: String,
+    recipient: String,
+    additional_seconds: u64,
+) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+
+    let id = TRANSFER_KEYS
+        .may_load(deps.storage, (&file_hash, &recipient))?
+        .ok_or(ContractError::TransferNotFound {})?;
+    let mut transfer = with_current_status(TRANSFERS.load(deps.storage, id)?, env.block.time.seconds());
+    if info.sender != transfer.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let expires_at = match (transfer.status, transfer.expires_at) {
+        (TransferStatus::Recorded, Some(expires_at)) => expires_at
+            .checked_add(additional_seconds)
+            .ok_or(ContractError::TransferNotExtendable {})?,
+        _ => return Err(ContractError::TransferNotExtendable {}),
+    };
+    if let Some(max) = state.max_ttl_seconds {
+        if expires_at - transfer.timestamp > max {
+            return Err(ContractError::TtlTooLong { max });
+        }
+    }
+
+    let amendment = Amendment {
+        field: "expires_at".to_string(),
+        old_value: transfer.expires_at.unwrap_or_default().to_string(),
+        new_value: expires_at.to_string(),
+        timestamp: env.block.time.seconds(),
+    };
+    record_amendment(deps.storage, id, amendment)?;
+    if let Some(old) = transfer.expires_at {
+        TRANSFER_EXPIRIES.remove(deps.storage, (old, id));
+    }
+    TRANSFER_EXPIRIES.save(deps.storage, (expires_at, id), &())?;
+    transfer.expires_at = Some(expires_at);
+    save_transfer(deps.storage, id, &mut transfer)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "extend_transfer")
+        .add_event(
+            Event::new(event_type(&state, "extend_transfer"))
+                .add_attribute("transfer_id", id.to_string())
+                .add_attribute("expires_at", expires_at.to_string()),
+        ))
+}
+
+// Set or clear where escrow refunds go when sending to the sender fails (admin only)
+fn set_fallback_recipient(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    state.fallback_recipient = address.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
+    CONFIG.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_fallback_recipient")
+        .add_event(
+            Event::new(event_type(&state, "set_fallback_recipient")).add_attribute(
+                "address",
+                state
+                    .fallback_recipient
+                    .as_ref()
+                    .map_or("none".to_string(), |addr| addr.to_string()),
+            ),
+        ))
+}
+
+// Apply several settings at once (admin only); every provided field is validated
+// as its dedicated setter would, and nothing is saved unless all of them pass
+fn update_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    update: ConfigUpdate,
+) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    if info.sender != state.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut updated = vec![];
+    if let Some(percentage) = update.fee_percentage {
+        if percentage > Uint128::new(10000) {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Fee percentage must be between 0 and 10000 (100.00%)",
+            )));
+        }
+        // Checked against the notice period in force before this update
+        let current = effective_fee_percentage(&state, env.block.time.seconds());
+        if state.fee_increase_delay > 0 && percentage > current {
+            return Err(ContractError::FeeIncreaseRequiresNotice {});
+        }
+        state.fee_percentage = percentage;
+        state.pending_fee = None;
+        state.fee_ramp = None;
+        updated.push("fee_percentage");
+    }
+    if let Some(paused) = update.paused {
+        if !state.features.pausable {
+            return Err(ContractError::FeatureDisabled { feature: "pausable".to_string() });
+        }
+        state.paused = paused;
+        if !paused {
+            RECENT_RECORD_TIMES.remove(deps.storage);
+        }
+        updated.push("paused");
+    }
+    if let Some(denoms) = update.accepted_denoms {
+        validate_accepted_denoms(&denoms)?;
+        state.accepted_denoms = denoms;
+        updated.push("accepted_denoms");
+    }
+    if let Some(allow) = update.allow_rerecord {
+        state.allow_rerecord = allow;
+        updated.push("allow_rerecord");
+    }
+    if let Some(quota) = update.free_transfer_quota {
+        state.free_transfer_quota = quota;
+        updated.push("free_transfer_quota");
+    }
+    if let Some(seconds) = update.fee_increase_delay {
+        state.fee_increase_delay = seconds;
+        updated.push("fee_increase_delay");
+    }
+    if let Some(enabled) = update.verbose_events {
+        state.verbose_events = enabled;
+        updated.push("verbose_events");
+    }
+    if let Some(enabled) = update.minimal_events {
+        state.minimal_events = enabled;
+        updated.push("minimal_events");
+    }
+    if let Some(percentage) = update.burn_percentage {
+        if percentage > Uint128::new(10000) {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Burn percentage must be between 0 and 10000 (100.00%)",
+            )));
+        }
+        state.burn_percentage = percentage;
+        updated.push("burn_percentage");
+    }
+    if let Some(limit) = update.query_scan_limit {
+        if limit == 0 {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Query scan limit must be greater than zero",
+            )));
+        }
+        state.query_scan_limit = limit;
+        updated.push("query_scan_limit");
+    }
+    CONFIG.save(deps.storage, &state)?;
+
+    let updated = match updated.as_slice() {
+        [] => "none".to_string(),
+        fields => fields.join(","),
     };
+    let event = Event::new(event_type(&state, "update_config")).add_attribute("updated", updated);
+    append_event_log(deps.storage, &env, "update_config", &event)?;
 
     Ok(Response::new()
-        .add_message(CosmosMsg::Bank(bank_msg))
-        .add_attribute("action", "withdraw_fees")
-        .add_attribute("amount", amount.to_string()))
+        .add_attribute("action", "update_config")
+        .add_event(event))
 }
 
-// Set fee percentage (admin only)
-fn set_fee_percentage(
+// Change where withdrawn fees go (admin only); the contract itself is rejected
+// since fees sent there could never be withdrawn again
+fn set_fee_recipient(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    percentage: Uint128,
+    address: String,
 ) -> Result<Response, ContractError> {
-    let mut state: State = deps.storage.get(b"state").and_then(|data| Ok(cosmwasm_std::from_json(data)?)).unwrap();
+    let mut state = CONFIG.load(deps.storage)?;
     if info.sender != state.admin {
         return Err(ContractError::Unauthorized {});
     }
 
-    if percentage > Uint128::new(10000) {
-        return Err(ContractError::Std(StdError::generic_err(
-            "Fee percentage must be between 0 and 10000 (100.00%)",
-        )));
+    let recipient = deps.api.addr_validate(&address)?;
+    if recipient == env.contract.address {
+        return Err(ContractError::InvalidFeeRecipient {});
     }
-
-    state.fee_percentage = percentage;
-    deps.storage.set(b"state", &to_json_binary(&state)?);
+    state.fee_recipient = recipient.to_string();
+    CONFIG.save(deps.storage, &state)?;
 
     Ok(Response::new()
-        .add_attribute("action", "set_fee_percentage")
-        .add_attribute("percentage", percentage.to_string()))
+        .add_attribute("action", "set_fee_recipient")
+        .add_event(
+            Event::new(event_type(&state, "set_fee_recipient"))
+                .add_attribute("address", recipient.to_string()),
+        ))
+}
+
+// Fee charged on a transfer amount; fee_percentage is in basis points
+fn compute_fee(
+    storage: &dyn Storage,
+    state: &State,
+    now: u64,
+    denom: &str,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    let rate = fee_rate(storage, state, now, denom)?;
+    Ok(fee_at_rate(state.fee_mode, amount, rate))
+}
+
+// Rate for `denom`: its own override, else the scheduled or ramped default
+fn fee_rate(storage: &dyn Storage, state: &State, now: u64, denom: &str) -> StdResult<Uint128> {
+    match DENOM_FEE_PERCENTAGES.may_load(storage, denom)? {
+        Some(rate) => Ok(rate),
+        None => Ok(effective_fee_percentage(state, now)),
+    }
+}
+
+// Fee compute_fee charges, raised to the min_fee floor when the floor is in the same
+// denom; a floor in another denom is attached alongside the transfer instead
+fn floored_fee(storage: &dyn Storage, state: &State, now: u64, denom: &str, amount: Uint128) -> StdResult<Uint128> {
+    let fee = compute_fee(storage, state, now, denom, amount)?;
+    Ok(match &state.min_fee {
+        Some(floor) if floor.denom == denom => fee.max(floor.amount),
+        _ => fee,
+    })
+}
+
+// Fee at `rate` basis points on attached funds; on top, the rate applies to the
+// net amount, leaving attached * rate / (10000 + rate) as the fee
+fn fee_at_rate(mode: FeeMode, amount: Uint128, rate: Uint128) -> Uint128 {
+    match mode {
+        FeeMode::FromAmount => amount * rate / Uint128::new(10000),
+        FeeMode::OnTop => amount.multiply_ratio(rate, Uint128::new(10000) + rate),
+    }
+}
+
+// Scale an amount in `denom`'s base units to NORMALIZED_EXPONENT decimals
+fn normalize_amount(storage: &dyn Storage, denom: &str, amount: Uint128) -> StdResult<Uint128> {
+    let exponent = DENOM_EXPONENTS
+        .may_load(storage, denom)?
+        .unwrap_or(DEFAULT_DENOM_EXPONENT);
+    if exponent <= NORMALIZED_EXPONENT {
+        Ok(amount.checked_mul(Uint128::new(10u128.pow(NORMALIZED_EXPONENT - exponent)))?)
+    } else {
+        Ok(amount / Uint128::new(10u128.pow(exponent - NORMALIZED_EXPONENT)))
+    }
+}
+
+// Rate in force at `now`, following a scheduled ramp or taking an announced increase
+// into account once it is effective
+fn effective_fee_percentage(state: &State, now: u64) -> Uint128 {
+    if let Some(ramp) = &state.fee_ramp {
+        if now <= ramp.start {
+            return ramp.from;
+        }
+        if now >= ramp.end {
+            return ramp.target;
+        }
+        let (elapsed, duration) = (now - ramp.start, ramp.end - ramp.start);
+        return if ramp.target >= ramp.from {
+            ramp.from + (ramp.target - ramp.from).multiply_ratio(elapsed, duration)
+        } else {
+            ramp.from - (ramp.from - ramp.target).multiply_ratio(elapsed, duration)
+        };
+    }
+    match &state.pending_fee {
+        Some(pending) if now >= pending.effective_at => pending.percentage,
+        _ => state.fee_percentage,
+    }
+}
+
+// Resolve a recipient given as a human-readable name via the configured name service
+fn resolve_recipient(deps: Deps, state: &State, recipient: String) -> Result<String, ContractError> {
+    let name_service = match &state.name_service {
+        Some(name_service) if deps.api.addr_validate(&recipient).is_err() => name_service,
+        _ => return Ok(recipient),
+    };
+
+    let resolved: ResolveRecordResponse = deps
+        .querier
+        .query_wasm_smart(name_service, &NameServiceQueryMsg::ResolveRecord { name: recipient })
+        .map_err(|_| ContractError::UnresolvableRecipient {})?;
+    let address = resolved.address.ok_or(ContractError::UnresolvableRecipient {})?;
+    deps.api
+        .addr_validate(&address)
+        .map(|addr| addr.to_string())
+        .map_err(|_| ContractError::UnresolvableRecipient {})
+}
+
+// Helper function to get the human-readable part of a bech32 address, everything
+// before the last '1' separator
+fn bech32_prefix(address: &str) -> Option<&str> {
+    address.rsplit_once('1').map(|(hrp, _)| hrp).filter(|hrp| !hrp.is_empty())
+}
+
+// Any execute by the admin counts as activity for the dead-man's switch
+fn record_admin_activity(storage: &mut dyn Storage, env: &Env, info: &MessageInfo) -> StdResult<()> {
+    let mut state = CONFIG.load(storage)?;
+    if info.sender == state.admin {
+        state.last_admin_action = env.block.time.seconds();
+        CONFIG.save(storage, &state)?;
+    }
+    Ok(())
+}
+
+// Hash covering the proof bytes and every public input, stored per transfer in place
+// of the proof itself
+fn proof_hash(zk_proof: &[u8], inputs: &ProofInputs) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    for part in [
+        zk_proof,
+        inputs.file_hash.as_bytes(),
+        inputs.recipient,
+        &inputs.metadata_commitment,
+    ] {
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    }
+    hasher.update(inputs.proved_height.to_be_bytes());
+    hasher.update(inputs.epoch.to_be_bytes());
+    hasher.finalize().to_vec()
+}
+
+// Cache key binding a proof hash to the verifying key it was checked against, so a hit
+// can't be replayed against different inputs or survive a key rotation
+fn proof_cache_key(proof_hash: &[u8], verifying_key_hash: Option<&Binary>) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(proof_hash);
+    if let Some(key_hash) = verifying_key_hash {
+        hasher.update(key_hash.as_slice());
+    }
+    hasher.finalize().to_vec()
+}
+
+// Append an emitted event to the log under the next sequence number
+fn append_event_log(storage: &mut dyn Storage, env: &Env, action: &str, event: &Event) -> StdResult<u64> {
+    let seq = NEXT_EVENT_SEQ.may_load(storage)?.unwrap_or_default();
+    let record = EventRecord {
+        seq,
+        action: action.to_string(),
+        height: env.block.height,
+        timestamp: env.block.time.seconds(),
+        attributes: event
+            .attributes
+            .iter()
+            .map(|attr| (attr.key.clone(), attr.value.clone()))
+            .collect(),
+    };
+    EVENT_LOG.save(storage, seq, &record)?;
+    NEXT_EVENT_SEQ.save(storage, &(seq + 1))?;
+    Ok(seq)
+}
+
+// Namespaced event type so multiple deployments on one chain can be told apart
+fn event_type(state: &State, action: &str) -> String {
+    format!("{}/{}", state.event_namespace, action)
 }
 
 // Contract queries
 #[entry_point]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::GetFileTransfers {} => to_json_binary(&query_file_transfers(deps)?),
-        QueryMsg::VerifyTransfer { file_hash, recipient } => to_json_binary(&query_verify_transfer(deps, file_hash, recipient)?),
-        QueryMsg::GetContractBalance {} => to_json_binary(&query_contract_balance(deps, _env)?),
-        QueryMsg::GetFeePercentage {} => to_json_binary(&query_fee_percentage(deps)?),
+        QueryMsg::GetFileTransfers { page } => {
+            to_json_binary(&versioned(query_file_transfers(deps, _env, page)?))
+        }
+        QueryMsg::VerifyTransfer { file_hash, recipient } => {
+            to_json_binary(&versioned(query_verify_transfer(deps, _env, file_hash, recipient)?))
+        }
+        QueryMsg::VerifyTransferDetailed { file_hash, recipient } => {
+            to_json_binary(&versioned(query_verify_transfer_detailed(deps, _env, file_hash, recipient)?))
+        }
+        QueryMsg::GetContractBalance {} => to_json_binary(&versioned(query_contract_balance(deps, _env)?)),
+        QueryMsg::GetFeePercentage {} => to_json_binary(&versioned(query_fee_percentage(deps, _env)?)),
+        QueryMsg::GetFeesPaidBy { sender } => to_json_binary(&versioned(query_fees_paid_by(deps, sender)?)),
+        QueryMsg::QuoteFee { amount, denom } => to_json_binary(&versioned(query_quote_fee(deps, _env, amount, denom)?)),
+        QueryMsg::GetRevokedTransfers { page } => to_json_binary(&versioned(query_revoked_transfers(deps, _env, page)?)),
+        QueryMsg::ReverifyTransfer { file_hash, recipient, zk_proof } => {
+            to_json_binary(&versioned(query_reverify_transfer(deps, _env, file_hash, recipient, zk_proof)?))
+        }
+        QueryMsg::GetRecentTransfers { limit } => to_json_binary(&versioned(query_recent_transfers(deps, _env, limit)?)),
+        QueryMsg::GetTreasury {} => to_json_binary(&versioned(query_treasury(deps, _env)?)),
+        QueryMsg::GetTransfersByIds { ids } => to_json_binary(&versioned(query_transfers_by_ids(deps, _env, ids)?)),
+        QueryMsg::GetVolume {} => to_json_binary(&versioned(query_volume(deps)?)),
+        QueryMsg::QuoteFeeInDisplay { amount, display_denom } => {
+            to_json_binary(&versioned(query_quote_fee_in_display(deps, _env, amount, display_denom)?))
+        }
+        QueryMsg::FileExists { file_hash } => to_json_binary(&versioned(query_file_exists(deps, _env, file_hash)?)),
+        QueryMsg::GetFeeSchedule {} => to_json_binary(&versioned(query_fee_schedule(deps, _env)?)),
+        QueryMsg::GetTransfersByTimeRange { start, end, page } => {
+            to_json_binary(&versioned(query_transfers_by_time_range(deps, _env, start, end, page)?))
+        }
+        QueryMsg::GetLatestForRecipient { recipient } => {
+            to_json_binary(&versioned(query_latest_for_recipient(deps, _env, recipient)?))
+        }
+        QueryMsg::GetTransfersByStatus { status, limit } => {
+            to_json_binary(&versioned(query_transfers_by_status(deps, _env, status, limit)?))
+        }
+        QueryMsg::EstimateRecordGas {} => to_json_binary(&versioned(query_estimate_record_gas())),
+        QueryMsg::GetEventLog { start_after, limit } => {
+            to_json_binary(&versioned(query_event_log(deps, start_after, limit)?))
+        }
+        QueryMsg::TopRecipientsByFees { limit } => {
+            to_json_binary(&versioned(query_top_recipients_by_fees(deps, limit)?))
+        }
+        QueryMsg::GetSeenDenoms {} => to_json_binary(&versioned(query_seen_denoms(deps)?)),
+        QueryMsg::GetDeliveryProof { file_hash, recipient } => {
+            to_json_binary(&versioned(query_delivery_proof(deps, _env, file_hash, recipient)?))
+        }
+        QueryMsg::GetEffectiveFeeRate { amount, denom } => {
+            to_json_binary(&versioned(query_effective_fee_rate(deps, _env, amount, denom)?))
+        }
+        QueryMsg::GetUpdatedSince { seq, limit } => {
+            to_json_binary(&versioned(query_updated_since(deps, _env, seq, limit)?))
+        }
+        QueryMsg::GetProofStats {} => to_json_binary(&versioned(query_proof_stats(deps)?)),
+        QueryMsg::GetRoles { address } => to_json_binary(&versioned(query_roles(deps, address)?)),
+        QueryMsg::GetTransferHistory { file_hash, recipient } => {
+            to_json_binary(&versioned(query_transfer_history(deps, _env, file_hash, recipient)?))
+        }
+        QueryMsg::ExportSnapshot { start_after, limit } => {
+            to_json_binary(&versioned(query_export_snapshot(deps, _env, start_after, limit)?))
+        }
+        QueryMsg::GetPublicInputs { file_hash, recipient } => {
+            to_json_binary(&versioned(query_public_inputs(deps, _env, file_hash, recipient)?))
+        }
+        QueryMsg::GetDashboard { recent_limit } => {
+            to_json_binary(&versioned(query_dashboard(deps, _env, recent_limit)?))
+        }
+        QueryMsg::GetVerifyingKeyHash {} => to_json_binary(&versioned(query_verifying_key_hash(deps)?)),
+        QueryMsg::GetParameters {} => to_json_binary(&versioned(query_parameters(deps)?)),
+        QueryMsg::ComputeKey { file_hash, recipient } => {
+            to_json_binary(&versioned(query_compute_key(&file_hash, &recipient)))
+        }
+        QueryMsg::GetContractBalances {} => to_json_binary(&versioned(query_contract_balances(deps, _env)?)),
     }
 }
 
-// Query function to get all file transfers
-fn query_file_transfers(deps: Deps) -> StdResult<Vec<FileTransfer>> {
-    let state: State = deps.storage.get(b"state").and_then(|data| Ok(cosmwasm_std::from_json(data)?)).unwrap();
-    Ok(state.file_transfers)
+// Query function to get all file transfers; embargoed ones are left out
+fn query_file_transfers(deps: Deps, env: Env, page: Option<PageRequest>) -> StdResult<PageResponse<FileTransfer>> {
+    paginate_transfers(deps.storage, env.block.time.seconds(), page, |_| true)
 }
 
-// Query function to verify a specific transfer
-fn query_verify_transfer(deps: Deps, file_hash: String, recipient: String) -> StdResult<bool> {
-    let state: State = deps.storage.get(b"state").and_then(|data| Ok(cosmwasm_std::from_json(data)?)).unwrap();
-    Ok(state
-        .file_transfers
-        .iter()
-        .any(|t| t.file_hash == file_hash && t.recipient == recipient))
+// Query function to verify a specific transfer; an embargoed transfer doesn't
+// verify until its release time
+fn query_verify_transfer(deps: Deps, env: Env, file_hash: String, recipient: String) -> StdResult<bool> {
+    let now = env.block.time.seconds();
+    Ok(load_visible_transfer(deps.storage, &file_hash, &recipient, now)?.is_some_and(|t| {
+        matches!(
+            with_current_status(t, now).status,
+            TransferStatus::Recorded | TransferStatus::Acknowledged
+        )
+    }))
+}
+
+// Query function to get a transfer's full record, with the same embargo rule as
+// VerifyTransfer; the status tells whether it still verifies
+fn query_verify_transfer_detailed(
+    deps: Deps,
+    env: Env,
+    file_hash: String,
+    recipient: String,
+) -> StdResult<Option<FileTransfer>> {
+    let now = env.block.time.seconds();
+    Ok(load_visible_transfer(deps.storage, &file_hash, &recipient, now)?.map(|t| with_current_status(t, now)))
 }
 
 // Query function to get contract balance
@@ -378,10 +4884,897 @@ fn query_contract_balance(deps: Deps, env: Env) -> StdResult<Uint128> {
     query_balance(deps, &env.contract.address)
 }
 
+// Query function to get the contract's balance in every denom it holds; GetContractBalance
+// stays usei-only for existing clients
+fn query_contract_balances(deps: Deps, env: Env) -> StdResult<Vec<Coin>> {
+    deps.querier.query_all_balances(&env.contract.address)
+}
+
 // Query function to get fee percentage
-fn query_fee_percentage(deps: Deps) -> StdResult<Uint128> {
-    let state: State = deps.storage.get(b"state").and_then(|data| Ok(cosmwasm_std::from_json(data)?)).unwrap();
-    Ok(state.fee_percentage)
+fn query_fee_percentage(deps: Deps, env: Env) -> StdResult<Uint128> {
+    let state = CONFIG.load(deps.storage)?;
+    Ok(effective_fee_percentage(&state, env.block.time.seconds()))
+}
+
+// Query function to get the total fees paid by a sender, in normalized units
+fn query_fees_paid_by(deps: Deps, sender: String) -> StdResult<Uint128> {
+    let sender = deps.api.addr_validate(&sender)?;
+    Ok(FEES_PAID.may_load(deps.storage, &sender)?.unwrap_or_default())
+}
+
+// Query function to preview the fee for a hypothetical transfer amount, raised to a
+// min_fee floor in the same denom
+fn query_quote_fee(deps: Deps, env: Env, amount: Uint128, denom: Option<String>) -> StdResult<Uint128> {
+    let state = CONFIG.load(deps.storage)?;
+    let denom = denom.unwrap_or_else(|| state.accepted_denoms[0].clone());
+    if !state.accepted_denoms.contains(&denom) {
+        return Err(StdError::generic_err(format!("Denom {} is not accepted", denom)));
+    }
+    floored_fee(deps.storage, &state, env.block.time.seconds(), &denom, amount)
+}
+
+// Query function to list revoked transfers for auditing
+fn query_revoked_transfers(
+    deps: Deps,
+    env: Env,
+    page: Option<PageRequest>,
+) -> StdResult<PageResponse<FileTransfer>> {
+    paginate_transfers(deps.storage, env.block.time.seconds(), page, |t| {
+        t.status == TransferStatus::Revoked
+    })
+}
+
+// Query function to re-run verification of a resubmitted proof; only the hash of
+// the proof and its public inputs is stored, so the proof must match it first
+fn query_reverify_transfer(
+    deps: Deps,
+    env: Env,
+    file_hash: String,
+    recipient: String,
+    zk_proof: Binary,
+) -> StdResult<bool> {
+    let transfer = load_visible_transfer(deps.storage, &file_hash, &recipient, env.block.time.seconds())?
+        .ok_or_else(|| StdError::not_found("FileTransfer"))?;
+
+    let inputs = match stored_proof_inputs(&transfer) {
+        Some(inputs) => inputs,
+        None => return Ok(false),
+    };
+    if proof_hash(&zk_proof, &inputs) != transfer.proof_hash.as_slice() {
+        return Ok(false);
+    }
+    let state = CONFIG.load(deps.storage)?;
+    check_proof(deps, &state, &zk_proof, &inputs)
+}
+
+// Public inputs a proof is verified against
+struct ProofInputs<'a> {
+    file_hash: &'a str,
+    recipient: &'a [u8],
+    metadata_commitment: [u8; 32],
+    proved_height: u64,
+    epoch: u64,
+}
+
+// Helper function to get the public inputs a transfer's proof was verified against;
+// None if the stored commitment isn't 32 bytes
+fn stored_proof_inputs(transfer: &FileTransfer) -> Option<ProofInputs<'_>> {
+    let metadata_commitment = match &transfer.metadata_commitment {
+        Some(c) => c.as_slice().try_into().ok()?,
+        None => [0u8; 32],
+    };
+    let recipient = match &transfer.recipient_set_hash {
+        Some(set_hash) => set_hash.as_slice(),
+        None => transfer.recipient.as_bytes(),
+    };
+    Some(ProofInputs {
+        file_hash: &transfer.file_hash,
+        recipient,
+        metadata_commitment,
+        proved_height: transfer.proved_height,
+        epoch: transfer.epoch,
+    })
+}
+
+// Check a proof against its public inputs, delegating to the verifier contract
+// when one is configured and trusting its answer
+fn check_proof(deps: Deps, state: &State, zk_proof: &[u8], inputs: &ProofInputs) -> StdResult<bool> {
+    match &state.verifier_contract {
+        Some(verifier) => {
+            let response: VerifyProofResponse = deps.querier.query_wasm_smart(
+                verifier,
+                &VerifierQueryMsg::VerifyProof {
+                    proof: Binary::from(zk_proof),
+                    file_hash: inputs.file_hash.to_string(),
+                    recipient: Binary::from(inputs.recipient),
+                    metadata_commitment: Binary::from(inputs.metadata_commitment.as_slice()),
+                    proved_height: inputs.proved_height,
+                    epoch: inputs.epoch,
+                },
+            )?;
+            Ok(response.valid)
+        }
+        None => {
+            let verifying_key = VERIFYING_KEY.may_load(deps.storage)?;
+            let proof = zk_proof::Proof(zk_proof.to_vec());
+            Ok(proof.verify(
+                inputs.file_hash.as_bytes(),
+                inputs.recipient,
+                &inputs.metadata_commitment,
+                inputs.proved_height,
+                inputs.epoch,
+                verifying_key.as_ref().map(|vk| vk.as_slice()),
+            ))
+        }
+    }
+}
+
+// Hash of a sorted recipient set, used as the proof's recipient input; the top
+// bits are cleared so the digest is always a canonical scalar
+fn recipient_set_hash(sorted_recipients: &[String]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for recipient in sorted_recipients {
+        hasher.update((recipient.len() as u64).to_be_bytes());
+        hasher.update(recipient.as_bytes());
+    }
+    let mut hash: [u8; 32] = hasher.finalize().into();
+    hash[31] &= 0x1f;
+    hash
+}
+
+// Helper function to check an accepted denom list is non-empty and every entry follows
+// Cosmos SDK denom rules: 3-128 chars of [a-zA-Z0-9/:._-] starting with a letter,
+// `ibc/` followed by a 64-char hex hash, `factory/` followed by creator and subdenom
+fn validate_accepted_denoms(denoms: &[String]) -> Result<(), ContractError> {
+    if denoms.is_empty() {
+        return Err(ContractError::NoAcceptedDenoms {});
+    }
+    for denom in denoms {
+        let well_formed = (3..=128).contains(&denom.len())
+            && denom.starts_with(|c: char| c.is_ascii_alphabetic())
+            && denom
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | ':' | '.' | '_' | '-'));
+        let prefix_ok = if let Some(hash) = denom.strip_prefix("ibc/") {
+            hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit())
+        } else if let Some(rest) = denom.strip_prefix("factory/") {
+            matches!(rest.split_once('/'), Some((creator, subdenom)) if !creator.is_empty() && !subdenom.is_empty())
+        } else {
+            true
+        };
+        if !well_formed || !prefix_ok {
+            return Err(ContractError::InvalidDenom {});
+        }
+    }
+    Ok(())
+}
+
+// Helper function to bump the distinct-recipient count for a file_hash
+fn increment_file_hash_count(storage: &mut dyn Storage, file_hash: &str) -> StdResult<u32> {
+    FILE_HASH_COUNTS.update(storage, file_hash, |count| -> StdResult<_> {
+        Ok(count.unwrap_or_default() + 1)
+    })
+}
+
+// Helper function to attribute a fee to its recipient; totals only grow, so a
+// recipient enters the bounded leaderboard exactly when it overtakes the last entry
+fn record_recipient_fees(storage: &mut dyn Storage, recipient: &str, fee: Uint128) -> StdResult<()> {
+    let fees = FEES_BY_RECIPIENT.update(storage, recipient, |fees| -> StdResult<_> {
+        Ok(fees.unwrap_or_default() + fee)
+    })?;
+
+    let mut leaderboard = FEE_LEADERBOARD.may_load(storage)?.unwrap_or_default();
+    leaderboard.retain(|entry| entry.recipient != recipient);
+    let position = leaderboard.partition_point(|entry| entry.fees >= fees);
+    if position < MAX_FEE_LEADERBOARD {
+        leaderboard.insert(
+            position,
+            RecipientFees {
+                recipient: recipient.to_string(),
+                fees,
+            },
+        );
+        leaderboard.truncate(MAX_FEE_LEADERBOARD);
+        FEE_LEADERBOARD.save(storage, &leaderboard)?;
+    }
+    Ok(())
+}
+
+// Helper function to count a record against the rolling auto-pause window; trips the
+// pause (without failing this record) and returns true once the threshold is exceeded
+fn track_record_rate(storage: &mut dyn Storage, state: &State, now: u64) -> StdResult<bool> {
+    if !state.features.pausable || state.auto_pause_threshold == 0 {
+        return Ok(false);
+    }
+
+    let mut times = RECENT_RECORD_TIMES.may_load(storage)?.unwrap_or_default();
+    times.retain(|time| now.saturating_sub(*time) < state.auto_pause_window);
+    times.push(now);
+    let tripped = times.len() > state.auto_pause_threshold as usize;
+    if tripped {
+        let mut state = state.clone();
+        state.paused = true;
+        CONFIG.save(storage, &state)?;
+        times.clear();
+    }
+    RECENT_RECORD_TIMES.save(storage, &times)?;
+    Ok(tripped)
+}
+
+// Helper function to count a fee against the sender's rolling spend-cap window, failing
+// the record if it would take the window's total over the cap
+fn track_sender_spend(
+    storage: &mut dyn Storage,
+    state: &State,
+    sender: &Addr,
+    now: u64,
+    fee: Uint128,
+) -> Result<(), ContractError> {
+    let cap = match state.spend_cap {
+        Some(cap) if !fee.is_zero() => cap,
+        _ => return Ok(()),
+    };
+
+    let mut spend = SENDER_SPEND.may_load(storage, sender)?.unwrap_or_default();
+    spend.retain(|(time, _)| now.saturating_sub(*time) < cap.window_seconds);
+    let total: Uint128 = spend.iter().map(|(_, amount)| *amount).sum();
+    if total + fee > cap.amount {
+        return Err(ContractError::SpendCapExceeded { cap: cap.amount });
+    }
+    spend.push((now, fee));
+    SENDER_SPEND.save(storage, sender, &spend)?;
+    Ok(())
+}
+
+// Helper function to enforce the minimum block gap before running another proof verification
+fn throttle_verification(
+    storage: &dyn Storage,
+    state: &State,
+    sender: &Addr,
+    height: u64,
+) -> Result<(), ContractError> {
+    if state.min_verify_interval == 0 {
+        return Ok(());
+    }
+
+    let last = if state.verify_interval_per_sender {
+        SENDER_LAST_VERIFY_HEIGHT.may_load(storage, sender)?
+    } else {
+        LAST_VERIFY_HEIGHT.may_load(storage)?
+    };
+    if last.is_some_and(|last| height < last + state.min_verify_interval) {
+        return Err(ContractError::TooSoon {});
+    }
+    Ok(())
+}
+
+// Helper function to start the verification interval once a proof has verified, so a
+// rejected proof doesn't hold back the sender's next record
+fn note_verification(
+    storage: &mut dyn Storage,
+    state: &State,
+    sender: &Addr,
+    height: u64,
+) -> Result<(), ContractError> {
+    if state.min_verify_interval == 0 {
+        return Ok(());
+    }
+    if state.verify_interval_per_sender {
+        SENDER_LAST_VERIFY_HEIGHT.save(storage, sender, &height)?;
+    } else {
+        LAST_VERIFY_HEIGHT.save(storage, &height)?;
+    }
+    Ok(())
+}
+
+// Helper function to check whether a transfer is past its release time. The embargo
+// is best-effort: queries can't authenticate their caller, so every query hides an
+// embargoed transfer from everyone, while the record's own events and the raw
+// contract storage stay public
+fn is_visible(transfer: &FileTransfer, now: u64) -> bool {
+    transfer.visible_at.is_none_or(|visible_at| now >= visible_at)
+}
+
+// Helper function to report live transfers past their expiry as Expired
+fn with_current_status(mut transfer: FileTransfer, now: u64) -> FileTransfer {
+    let live = matches!(transfer.status, TransferStatus::Recorded | TransferStatus::Acknowledged);
+    if live && transfer.expires_at.is_some_and(|expires_at| now >= expires_at) {
+        transfer.status = TransferStatus::Expired;
+    }
+    transfer
+}
+
+// Helper function to persist a transfer, stamping it with the next update sequence
+fn save_transfer(storage: &mut dyn Storage, id: u64, transfer: &mut FileTransfer) -> StdResult<()> {
+    // Drop the previous stamp so each transfer appears once in the update feed
+    if TRANSFER_UPDATES.may_load(storage, transfer.update_seq)? == Some(id) {
+        TRANSFER_UPDATES.remove(storage, transfer.update_seq);
+    }
+    let seq = NEXT_UPDATE_SEQ.may_load(storage)?.unwrap_or(1);
+    transfer.update_seq = seq;
+    TRANSFER_UPDATES.save(storage, seq, &id)?;
+    NEXT_UPDATE_SEQ.save(storage, &(seq + 1))?;
+    TRANSFERS.save(storage, id, transfer)
+}
+
+// Helper function to delete up to `limit` transfers whose expiry has passed, oldest
+// expiry first, along with their indexes; ones still holding escrow are left for the
+// escrow flow. Returns how many were deleted
+fn prune_expired(storage: &mut dyn Storage, now: u64, limit: u32) -> StdResult<u32> {
+    let expired: Vec<(u64, u64)> = TRANSFER_EXPIRIES
+        .keys(storage, None, Some(Bound::inclusive((now, u64::MAX))), Order::Ascending)
+        .take(limit as usize)
+        .collect::<StdResult<_>>()?;
+
+    let mut pruned = 0;
+    for (expires_at, id) in expired {
+        TRANSFER_EXPIRIES.remove(storage, (expires_at, id));
+        let transfer = match TRANSFERS.may_load(storage, id)? {
+            Some(transfer) => transfer,
+            None => continue,
+        };
+        let key = (transfer.file_hash.as_str(), transfer.recipient.as_str());
+        let current = TRANSFER_KEYS.may_load(storage, key)? == Some(id);
+        if current && ESCROWS.has(storage, key) {
+            continue;
+        }
+
+        if current {
+            TRANSFER_KEYS.remove(storage, key);
+            match FILE_HASH_COUNTS.may_load(storage, &transfer.file_hash)?.unwrap_or_default() {
+                0 | 1 => FILE_HASH_COUNTS.remove(storage, &transfer.file_hash),
+                count => FILE_HASH_COUNTS.save(storage, &transfer.file_hash, &(count - 1))?,
+            }
+        }
+        RECIPIENT_TRANSFERS.remove(storage, (&transfer.recipient, id));
+        if TRANSFER_UPDATES.may_load(storage, transfer.update_seq)? == Some(id) {
+            TRANSFER_UPDATES.remove(storage, transfer.update_seq);
+        }
+        TRANSFER_HISTORY.remove(storage, id);
+        TRANSFERS.remove(storage, id);
+        pruned += 1;
+    }
+    Ok(pruned)
+}
+
+// Helper function to add a transfer with a TTL to the expiry index auto-prune walks;
+// revoked transfers are kept for audit and never indexed
+fn index_expiry(storage: &mut dyn Storage, id: u64, transfer: &FileTransfer) -> StdResult<()> {
+    match transfer.expires_at {
+        Some(expires_at) if transfer.status != TransferStatus::Revoked => {
+            TRANSFER_EXPIRIES.save(storage, (expires_at, id), &())
+        }
+        _ => Ok(()),
+    }
+}
+
+// Helper function to append to a transfer's amendment history, dropping the oldest
+// entries beyond MAX_TRANSFER_HISTORY
+fn record_amendment(storage: &mut dyn Storage, id: u64, amendment: Amendment) -> StdResult<()> {
+    let mut history = TRANSFER_HISTORY.may_load(storage, id)?.unwrap_or_default();
+    history.push(amendment);
+    if history.len() > MAX_TRANSFER_HISTORY {
+        history.drain(..history.len() - MAX_TRANSFER_HISTORY);
+    }
+    TRANSFER_HISTORY.save(storage, id, &history)
+}
+
+// Helper function to load the latest transfer recorded for a (file_hash, recipient) key
+fn load_transfer(storage: &dyn Storage, file_hash: &str, recipient: &str) -> StdResult<Option<FileTransfer>> {
+    match TRANSFER_KEYS.may_load(storage, (file_hash, recipient))? {
+        Some(id) => TRANSFERS.may_load(storage, id),
+        None => Ok(None),
+    }
+}
+
+// Helper function to load a transfer for a query, treating an embargoed one as absent
+fn load_visible_transfer(
+    storage: &dyn Storage,
+    file_hash: &str,
+    recipient: &str,
+    now: u64,
+) -> StdResult<Option<FileTransfer>> {
+    Ok(load_transfer(storage, file_hash, recipient)?.filter(|t| is_visible(t, now)))
+}
+
+// Query function to get the most recent transfers, newest first
+fn query_recent_transfers(deps: Deps, env: Env, limit: u32) -> StdResult<Vec<FileTransfer>> {
+    let state = CONFIG.load(deps.storage)?;
+    let limit = state.query_limits.recent_transfers.clamp(Some(limit));
+    let now = env.block.time.seconds();
+    TRANSFERS
+        .range(deps.storage, None, None, Order::Descending)
+        .filter(|item| item.as_ref().map_or(true, |(_, t)| is_visible(t, now)))
+        .take(limit)
+        .map(|item| item.map(|(_, t)| with_current_status(t, now)))
+        .collect()
+}
+
+// Query function to list accepted denoms with accrued fees and the live contract balance
+fn query_treasury(deps: Deps, env: Env) -> StdResult<Vec<TreasuryEntry>> {
+    let state = CONFIG.load(deps.storage)?;
+    state
+        .accepted_denoms
+        .into_iter()
+        .map(|denom| {
+            let accrued = ACCRUED_FEES.may_load(deps.storage, &denom)?.unwrap_or_default();
+            let contract_balance = deps.querier.query_balance(&env.contract.address, &denom)?.amount;
+            Ok(TreasuryEntry {
+                denom,
+                accrued,
+                contract_balance,
+            })
+        })
+        .collect()
+}
+
+// Query function to look up several transfers by id, aligned with the input order
+fn query_transfers_by_ids(deps: Deps, env: Env, ids: Vec<u64>) -> StdResult<Vec<Option<FileTransfer>>> {
+    if ids.len() > MAX_BATCH_QUERY_IDS {
+        return Err(StdError::generic_err(format!(
+            "At most {} ids may be queried at once",
+            MAX_BATCH_QUERY_IDS
+        )));
+    }
+    let now = env.block.time.seconds();
+    ids.into_iter()
+        .map(|id| {
+            Ok(TRANSFERS
+                .may_load(deps.storage, id)?
+                .filter(|t| is_visible(t, now))
+                .map(|t| with_current_status(t, now)))
+        })
+        .collect()
+}
+
+// Query function to get the total value transferred per denom
+fn query_volume(deps: Deps) -> StdResult<Vec<Coin>> {
+    VOLUME
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+        .collect()
+}
+
+// Helper function to page through transfers matching `filter`, resuming after an opaque cursor
+fn paginate_transfers(
+    storage: &dyn Storage,
+    now: u64,
+    page: Option<PageRequest>,
+    filter: impl Fn(&FileTransfer) -> bool,
+) -> StdResult<PageResponse<FileTransfer>> {
+    let page = page.unwrap_or_default();
+    let state = CONFIG.load(storage)?;
+    let limit = state.query_limits.transfers.clamp(page.limit);
+    let cursor = page
+        .key
+        .map(|key| {
+            let bytes: [u8; 8] = key
+                .as_slice()
+                .try_into()
+                .map_err(|_| StdError::generic_err("Invalid page key"))?;
+            Ok::<_, StdError>(Bound::exclusive(u64::from_be_bytes(bytes)))
+        })
+        .transpose()?;
+    let (min, max, order) = if page.reverse {
+        (None, cursor, Order::Descending)
+    } else {
+        (cursor, None, Order::Ascending)
+    };
+
+    // Stop at a full page or once the scan budget is spent, whichever comes first; the
+    // cursor resumes after the last scanned id so sparse filters still make progress
+    let scan_limit = state.query_scan_limit as usize;
+    let mut items = vec![];
+    let mut last_scanned = None;
+    let mut truncated = false;
+    for (scanned, item) in TRANSFERS.range(storage, min, max, order).enumerate() {
+        let (id, transfer) = item?;
+        let transfer = with_current_status(transfer, now);
+        let matches = is_visible(&transfer, now) && filter(&transfer);
+        if scanned >= scan_limit || (matches && items.len() >= limit) {
+            truncated = true;
+            break;
+        }
+        last_scanned = Some(id);
+        if matches {
+            items.push(transfer);
+        }
+    }
+    let next_key = if truncated {
+        last_scanned.map(|id| Binary::from(id.to_be_bytes().to_vec()))
+    } else {
+        None
+    };
+
+    Ok(PageResponse {
+        items,
+        next_key,
+        truncated,
+    })
+}
+
+// Query function to quote the fee for an amount in the default denom, converted to a display denom
+fn query_quote_fee_in_display(deps: Deps, env: Env, amount: Uint128, display_denom: String) -> StdResult<Coin> {
+    let state = CONFIG.load(deps.storage)?;
+    let oracle = state
+        .price_oracle
+        .as_ref()
+        .ok_or_else(|| StdError::generic_err("No price oracle configured"))?;
+
+    let base_denom = state.accepted_denoms[0].clone();
+    let fee = compute_fee(deps.storage, &state, env.block.time.seconds(), &base_denom, amount)?;
+    let price: PriceResponse = deps.querier.query_wasm_smart(
+        oracle,
+        &PriceOracleQueryMsg::Price {
+            base_denom,
+            quote_denom: display_denom.clone(),
+        },
+    )?;
+
+    Ok(Coin {
+        denom: display_denom,
+        amount: fee.mul_floor(price.rate),
+    })
+}
+
+// Query whether a file_hash has been recorded for any recipient, not counting
+// transfers still under embargo
+fn query_file_exists(deps: Deps, env: Env, file_hash: String) -> StdResult<bool> {
+    if FILE_HASH_COUNTS.may_load(deps.storage, &file_hash)?.unwrap_or_default() == 0 {
+        return Ok(false);
+    }
+    let now = env.block.time.seconds();
+    for item in TRANSFER_KEYS
+        .prefix(&file_hash)
+        .range(deps.storage, None, None, Order::Ascending)
+    {
+        let (_, id) = item?;
+        if is_visible(&TRANSFERS.load(deps.storage, id)?, now) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+// Query function to get the full fee schedule in one response
+fn query_fee_schedule(deps: Deps, env: Env) -> StdResult<FeeSchedule> {
+    let state = CONFIG.load(deps.storage)?;
+    let denom_overrides = DENOM_FEE_PERCENTAGES
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    Ok(FeeSchedule {
+        base_percentage: state.fee_percentage,
+        effective_percentage: effective_fee_percentage(&state, env.block.time.seconds()),
+        pending_fee: state.pending_fee,
+        fee_ramp: state.fee_ramp,
+        denom_overrides,
+        free_transfer_quota: state.free_transfer_quota,
+        burn_percentage: state.burn_percentage,
+        fee_splits: state.fee_splits,
+        // compute_fee truncates toward zero
+        rounding: "floor".to_string(),
+        fee_mode: state.fee_mode,
+        min_fee: state.min_fee,
+    })
+}
+
+// Query function to list transfers recorded within [start, end)
+fn query_transfers_by_time_range(
+    deps: Deps,
+    env: Env,
+    start: u64,
+    end: u64,
+    page: Option<PageRequest>,
+) -> StdResult<PageResponse<FileTransfer>> {
+    paginate_transfers(deps.storage, env.block.time.seconds(), page, |t| {
+        t.timestamp >= start && t.timestamp < end
+    })
+}
+
+// Query function to get the newest transfer addressed to a recipient
+fn query_latest_for_recipient(deps: Deps, env: Env, recipient: String) -> StdResult<Option<FileTransfer>> {
+    let now = env.block.time.seconds();
+    for id in RECIPIENT_TRANSFERS
+        .prefix(&recipient)
+        .keys(deps.storage, None, None, Order::Descending)
+    {
+        let transfer = TRANSFERS.load(deps.storage, id?)?;
+        if is_visible(&transfer, now) {
+            return Ok(Some(with_current_status(transfer, now)));
+        }
+    }
+    Ok(None)
+}
+
+// Query function to list transfers currently in the given lifecycle status
+fn query_transfers_by_status(
+    deps: Deps,
+    env: Env,
+    status: TransferStatus,
+    limit: Option<u32>,
+) -> StdResult<PageResponse<FileTransfer>> {
+    let page = PageRequest {
+        limit,
+        ..PageRequest::default()
+    };
+    paginate_transfers(deps.storage, env.block.time.seconds(), Some(page), |t| t.status == status)
+}
+
+// Query function to get the documented gas cost model for recording transfers
+fn query_estimate_record_gas() -> GasEstimate {
+    GasEstimate {
+        base: GAS_RECORD_BASE,
+        per_transfer: GAS_RECORD_PER_TRANSFER,
+        proof_verify: GAS_PROOF_VERIFY,
+        single_record: GAS_RECORD_BASE + GAS_RECORD_PER_TRANSFER + GAS_PROOF_VERIFY,
+    }
+}
+
+// Query function to read the event log in sequence order
+fn query_event_log(deps: Deps, start_after: Option<u64>, limit: Option<u32>) -> StdResult<Vec<EventRecord>> {
+    let limit = CONFIG.load(deps.storage)?.query_limits.event_log.clamp(limit);
+    EVENT_LOG
+        .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, record)| record))
+        .collect()
+}
+
+// Query function to rank recipients by the fees their transfers generated
+fn query_top_recipients_by_fees(deps: Deps, limit: u32) -> StdResult<Vec<RecipientFees>> {
+    let mut leaderboard = FEE_LEADERBOARD.may_load(deps.storage)?.unwrap_or_default();
+    leaderboard.truncate(limit as usize);
+    Ok(leaderboard)
+}
+
+// Query function to list every denom transfers have been paid in
+fn query_seen_denoms(deps: Deps) -> StdResult<Vec<String>> {
+    SEEN_DENOMS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect()
+}
+
+// Query function to get the recipient's delivery signature for a transfer, if confirmed
+fn query_delivery_proof(deps: Deps, env: Env, file_hash: String, recipient: String) -> StdResult<Option<Binary>> {
+    let transfer = load_visible_transfer(deps.storage, &file_hash, &recipient, env.block.time.seconds())?
+        .ok_or_else(|| StdError::not_found("FileTransfer"))?;
+    Ok(transfer.delivery_signature)
+}
+
+// Query function to resolve the denom rate, scheduled increase or ramp in force at the
+// current block, and the min_fee floor on top of it
+fn query_effective_fee_rate(
+    deps: Deps,
+    env: Env,
+    amount: Option<Uint128>,
+    denom: Option<String>,
+) -> StdResult<EffectiveFeeRate> {
+    let state = CONFIG.load(deps.storage)?;
+    let denom = denom.unwrap_or_else(|| state.accepted_denoms[0].clone());
+    if !state.accepted_denoms.contains(&denom) {
+        return Err(StdError::generic_err(format!("Denom {} is not accepted", denom)));
+    }
+    let now = env.block.time.seconds();
+    Ok(EffectiveFeeRate {
+        percentage: fee_rate(deps.storage, &state, now, &denom)?,
+        fee: amount
+            .map(|amount| floored_fee(deps.storage, &state, now, &denom, amount))
+            .transpose()?,
+        min_fee: state.min_fee,
+        denom,
+    })
+}
+
+// Query function to list transfers changed after an update sequence, oldest change first.
+// Embargoed transfers are skipped, and releasing one does not re-stamp it, so a change
+// made during its embargo only surfaces through the other listing queries
+fn query_updated_since(deps: Deps, env: Env, seq: u64, limit: Option<u32>) -> StdResult<Vec<FileTransfer>> {
+    let limit = CONFIG.load(deps.storage)?.query_limits.updated_since.clamp(limit);
+    let now = env.block.time.seconds();
+    TRANSFER_UPDATES
+        .range(deps.storage, Some(Bound::exclusive(seq)), None, Order::Ascending)
+        .map(|item| TRANSFERS.load(deps.storage, item?.1))
+        .filter(|item| item.as_ref().map_or(true, |t| is_visible(t, now)))
+        .take(limit)
+        .map(|item| item.map(|t| with_current_status(t, now)))
+        .collect()
+}
+
+// Query function to get proof verification counters
+fn query_proof_stats(deps: Deps) -> StdResult<ProofStats> {
+    Ok(ProofStats {
+        proofs_verified: PROOFS_VERIFIED.may_load(deps.storage)?.unwrap_or_default(),
+        proofs_rejected: PROOFS_REJECTED.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
+// Query function to get the roles an address holds; members of the approval
+// admin set count as admins
+fn query_roles(deps: Deps, address: String) -> StdResult<Roles> {
+    let address = deps.api.addr_validate(&address)?;
+    let state = CONFIG.load(deps.storage)?;
+    Ok(Roles {
+        is_admin: address == state.admin || state.admins.contains(&address.to_string()),
+        is_fee_recipient: address == state.fee_recipient,
+        is_allowlisted: state.withdraw_whitelist.contains(&address.to_string()),
+        is_opted_in: OPTED_IN.may_load(deps.storage, &address)?.unwrap_or(false),
+    })
+}
+
+// Query function to get the amendments made to a transfer, oldest first
+fn query_transfer_history(deps: Deps, env: Env, file_hash: String, recipient: String) -> StdResult<Vec<Amendment>> {
+    let id = TRANSFER_KEYS
+        .may_load(deps.storage, (&file_hash, &recipient))?
+        .ok_or_else(|| StdError::not_found("FileTransfer"))?;
+    if !is_visible(&TRANSFERS.load(deps.storage, id)?, env.block.time.seconds()) {
+        return Err(StdError::not_found("FileTransfer"));
+    }
+    Ok(TRANSFER_HISTORY.may_load(deps.storage, id)?.unwrap_or_default())
+}
+
+// Query function to export config and transfers in the preload instantiate format;
+// transfers superseded by a re-record are skipped since preloading rejects duplicates,
+// and embargoed ones since queries never reveal them before release
+fn query_export_snapshot(deps: Deps, env: Env, start_after: Option<u64>, limit: Option<u32>) -> StdResult<Snapshot> {
+    let state = CONFIG.load(deps.storage)?;
+    let limit = state.query_limits.export_snapshot.clamp(limit);
+    let config = match start_after {
+        Some(_) => None,
+        None => Some(InstantiateMsg {
+            fee_percentage: state.fee_percentage,
+            event_namespace: Some(state.event_namespace),
+            fee_recipient: Some(state.fee_recipient),
+            allow_rerecord: Some(state.allow_rerecord),
+            name_service: state.name_service.map(|addr| addr.to_string()),
+            max_ttl_seconds: state.max_ttl_seconds,
+            fee_increase_delay: Some(state.fee_increase_delay),
+            accepted_denoms: Some(state.accepted_denoms),
+            initial_transfers: None,
+            features: Some(state.features),
+        }),
+    };
+
+    let mut transfers = vec![];
+    let mut last_id = None;
+    // The limit bounds entries scanned, so a page may hold fewer after skipping
+    for item in TRANSFERS
+        .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .take(limit)
+    {
+        let (id, transfer) = item?;
+        last_id = Some(id);
+        let current = TRANSFER_KEYS.may_load(deps.storage, (&transfer.file_hash, &transfer.recipient))? == Some(id);
+        if current && is_visible(&transfer, env.block.time.seconds()) {
+            transfers.push(transfer);
+        }
+    }
+    Ok(Snapshot {
+        config,
+        transfers,
+        last_id,
+    })
+}
+
+// Query function to get the public inputs a transfer's proof is verified against,
+// derived exactly as on-chain verification does
+fn query_public_inputs(deps: Deps, env: Env, file_hash: String, recipient: String) -> StdResult<PublicInputs> {
+    let transfer = load_visible_transfer(deps.storage, &file_hash, &recipient, env.block.time.seconds())?
+        .ok_or_else(|| StdError::not_found("FileTransfer"))?;
+    let [file_hash, recipient, metadata_commitment, block_height, epoch] = stored_proof_inputs(&transfer)
+        .and_then(|inputs| {
+            zk_proof::public_inputs(
+                inputs.file_hash.as_bytes(),
+                inputs.recipient,
+                &inputs.metadata_commitment,
+                inputs.proved_height,
+                inputs.epoch,
+            )
+        })
+        .ok_or_else(|| StdError::generic_err("Transfer inputs are not valid field elements"))?;
+    let hex = |scalar: Scalar| scalar.to_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(PublicInputs {
+        file_hash: hex(file_hash),
+        recipient: hex(recipient),
+        metadata_commitment: hex(metadata_commitment),
+        block_height: hex(block_height),
+        epoch: hex(epoch),
+    })
+}
+
+// Query function to get config, fee and proof stats, and the newest transfers at once
+fn query_dashboard(deps: Deps, env: Env, recent_limit: u32) -> StdResult<Dashboard> {
+    Ok(Dashboard {
+        config: CONFIG.load(deps.storage)?,
+        fee_schedule: query_fee_schedule(deps, env.clone())?,
+        treasury: query_treasury(deps, env.clone())?,
+        volume: query_volume(deps)?,
+        transfer_count: NEXT_TRANSFER_ID.may_load(deps.storage)?.unwrap_or_default(),
+        proof_stats: query_proof_stats(deps)?,
+        recent_transfers: query_recent_transfers(deps, env, recent_limit)?,
+    })
+}
+
+// Query function to get the SHA-256 of the committed verifying key, if any
+fn query_verifying_key_hash(deps: Deps) -> StdResult<Option<Binary>> {
+    Ok(CONFIG.load(deps.storage)?.verifying_key_hash)
+}
+
+// Query function to list every admin-tunable parameter with its current and default value
+fn query_parameters(deps: Deps) -> StdResult<Vec<Parameter>> {
+    fn parameter<T: Serialize>(name: &str, current: &T, default: Option<&T>) -> StdResult<Parameter> {
+        Ok(Parameter {
+            name: name.to_string(),
+            current: cosmwasm_std::to_json_string(current)?,
+            default: default.map(cosmwasm_std::to_json_string).transpose()?,
+        })
+    }
+    fn per_denom<T: Serialize + serde::de::DeserializeOwned>(
+        storage: &dyn Storage,
+        map: Map<&str, T>,
+    ) -> StdResult<Vec<(String, T)>> {
+        map.range(storage, None, None, Order::Ascending).collect()
+    }
+
+    // Runtime state that changes without an admin setting it, such as a pending fee,
+    // the current epoch or the pause flag, is left out
+    let state = CONFIG.load(deps.storage)?;
+    let no_addr: Option<Addr> = None;
+    Ok(vec![
+        parameter("fee_percentage", &state.fee_percentage, None)?,
+        parameter("fee_recipient", &state.fee_recipient, None)?,
+        parameter("withdraw_whitelist", &state.withdraw_whitelist, Some(&vec![]))?,
+        parameter("fee_splits", &state.fee_splits, Some(&vec![]))?,
+        parameter("event_namespace", &state.event_namespace, Some(&DEFAULT_EVENT_NAMESPACE.to_string()))?,
+        parameter("accepted_denoms", &state.accepted_denoms, Some(&vec!["usei".to_string()]))?,
+        parameter("allow_rerecord", &state.allow_rerecord, Some(&false))?,
+        parameter("max_ttl_seconds", &state.max_ttl_seconds, Some(&None))?,
+        parameter("fee_increase_delay", &state.fee_increase_delay, Some(&0))?,
+        parameter("free_transfer_quota", &state.free_transfer_quota, Some(&0))?,
+        parameter("fee_mode", &state.fee_mode, Some(&FeeMode::FromAmount))?,
+        parameter("min_fee", &state.min_fee, Some(&None))?,
+        parameter("burn_percentage", &state.burn_percentage, Some(&Uint128::zero()))?,
+        parameter("verbose_events", &state.verbose_events, Some(&false))?,
+        parameter("minimal_events", &state.minimal_events, Some(&false))?,
+        parameter("proof_freshness_window", &state.proof_freshness_window, Some(&None))?,
+        parameter("opt_in_required", &state.opt_in_required, Some(&Features::default().opt_in))?,
+        parameter("verification_policy", &state.verification_policy, Some(&VerificationPolicy::default()))?,
+        parameter("features", &state.features, Some(&Features::default()))?,
+        parameter("admins", &state.admins, Some(&vec![]))?,
+        parameter("admin_threshold", &state.admin_threshold, Some(&1))?,
+        parameter("recovery_admin", &state.recovery_admin, Some(&None))?,
+        parameter("admin_inactivity_period", &state.admin_inactivity_period, Some(&0))?,
+        parameter("max_file_size", &state.max_file_size, Some(&None))?,
+        parameter("query_scan_limit", &state.query_scan_limit, Some(&DEFAULT_QUERY_SCAN_LIMIT))?,
+        parameter("query_limits", &state.query_limits, Some(&QueryLimits::default()))?,
+        parameter("auto_pause_threshold", &state.auto_pause_threshold, Some(&0))?,
+        parameter("auto_pause_window", &state.auto_pause_window, Some(&0))?,
+        parameter("min_verify_interval", &state.min_verify_interval, Some(&0))?,
+        parameter("verify_interval_per_sender", &state.verify_interval_per_sender, Some(&false))?,
+        parameter("max_recipients", &state.max_recipients, Some(&DEFAULT_MAX_RECIPIENTS))?,
+        parameter("name_service", &state.name_service, Some(&no_addr))?,
+        parameter("price_oracle", &state.price_oracle, Some(&no_addr))?,
+        parameter("fallback_recipient", &state.fallback_recipient, Some(&no_addr))?,
+        parameter("verifier_contract", &state.verifier_contract, Some(&no_addr))?,
+        parameter("spend_cap", &state.spend_cap, Some(&None))?,
+        parameter("auto_prune", &state.auto_prune, Some(&false))?,
+        parameter("auto_prune_limit", &state.auto_prune_limit, Some(&DEFAULT_AUTO_PRUNE_LIMIT))?,
+        parameter("required_prefix", &state.required_prefix, Some(&None))?,
+        parameter("verifying_key_hash", &state.verifying_key_hash, Some(&None))?,
+        parameter("denom_fee_percentages", &per_denom(deps.storage, DENOM_FEE_PERCENTAGES)?, Some(&vec![]))?,
+        parameter("denom_exponents", &per_denom(deps.storage, DENOM_EXPONENTS)?, Some(&vec![]))?,
+        parameter("min_transfer_amounts", &per_denom(deps.storage, MIN_TRANSFER_AMOUNTS)?, Some(&vec![]))?,
+        parameter(
+            "auto_withdraw_thresholds",
+            &per_denom(deps.storage, AUTO_WITHDRAW_THRESHOLDS)?,
+            Some(&vec![]),
+        )?,
+    ])
+}
+
+// Query function to get the raw storage key under which a (file_hash, recipient) pair's
+// latest transfer id is kept, for clients reading contract state directly
+fn query_compute_key(file_hash: &str, recipient: &str) -> Binary {
+    Binary::from(TRANSFER_KEYS.key((file_hash, recipient)).to_vec())
 }
 
 // Helper function to query balance
@@ -391,4 +5784,6 @@ fn query_balance(deps: Deps, address: &Addr) -> StdResult<Uint128> {
         denom: "usei".to_string(),
     }))?;
     Ok(balance.amount.amount)
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests;
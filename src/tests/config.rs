@@ -0,0 +1,285 @@
+use super::*;
+use cosmwasm_std::coins;
+use cosmwasm_std::testing::MOCK_CONTRACT_ADDR;
+
+#[test]
+fn events_carry_the_configured_namespace() {
+    let mut deps = setup_with(InstantiateMsg {
+        event_namespace: Some("myorg/zk_file_transfer".to_string()),
+        ..instantiate_msg()
+    });
+
+    let response = record(&mut deps, "file", RECIPIENT, &[]).unwrap();
+    assert_eq!(response.events[0].ty, "myorg/zk_file_transfer/record_transfer");
+}
+
+#[test]
+fn events_default_to_the_contract_namespace() {
+    let mut deps = setup();
+
+    let response = record(&mut deps, "file", RECIPIENT, &[]).unwrap();
+    assert_eq!(response.events[0].ty, "zk_file_transfer/record_transfer");
+}
+
+#[test]
+fn event_namespace_must_be_nonempty_and_bounded() {
+    for namespace in [String::new(), "x".repeat(MAX_EVENT_NAMESPACE_LEN + 1)] {
+        let msg = InstantiateMsg {
+            event_namespace: Some(namespace),
+            ..instantiate_msg()
+        };
+        let err = instantiate(mock_dependencies().as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidEventNamespace { max: MAX_EVENT_NAMESPACE_LEN }));
+    }
+}
+
+// Contract where "rescuer" may take over after 1000 seconds of admin inactivity
+fn setup_recovery() -> TestDeps {
+    let mut deps = setup();
+    let set = ExecuteMsg::SetRecoveryAdmin {
+        address: Some("rescuer".to_string()),
+        inactivity_seconds: 1000,
+    };
+    exec(&mut deps, ADMIN, &[], set).unwrap();
+    deps
+}
+
+#[test]
+fn claim_admin_fails_while_the_admin_is_active() {
+    let mut deps = setup_recovery();
+    let err = exec_at(&mut deps, env_after(999), "rescuer", &[], ExecuteMsg::ClaimAdmin {}).unwrap_err();
+    assert!(matches!(err, ContractError::AdminStillActive {}));
+
+    // Any admin execute restarts the inactivity window
+    exec_at(&mut deps, env_after(500), ADMIN, &[], ExecuteMsg::SetVerboseEvents { enabled: false }).unwrap();
+    let err = exec_at(&mut deps, env_after(1000), "rescuer", &[], ExecuteMsg::ClaimAdmin {}).unwrap_err();
+    assert!(matches!(err, ContractError::AdminStillActive {}));
+}
+
+#[test]
+fn claim_admin_succeeds_after_inactivity() {
+    let mut deps = setup_recovery();
+    let err = exec_at(&mut deps, env_after(1000), "stranger", &[], ExecuteMsg::ClaimAdmin {}).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    exec_at(&mut deps, env_after(1000), "rescuer", &[], ExecuteMsg::ClaimAdmin {}).unwrap();
+    let state = CONFIG.load(&deps.storage).unwrap();
+    assert_eq!(state.admin, "rescuer");
+    assert_eq!(state.recovery_admin, None);
+}
+
+#[test]
+fn huge_inactivity_period_never_lets_the_recovery_admin_claim() {
+    let mut deps = setup();
+    let set = ExecuteMsg::SetRecoveryAdmin {
+        address: Some("rescuer".to_string()),
+        inactivity_seconds: u64::MAX,
+    };
+    exec(&mut deps, ADMIN, &[], set).unwrap();
+
+    let err = exec_at(&mut deps, env_after(1), "rescuer", &[], ExecuteMsg::ClaimAdmin {}).unwrap_err();
+    assert!(matches!(err, ContractError::AdminStillActive {}));
+    assert_eq!(CONFIG.load(&deps.storage).unwrap().admin, ADMIN);
+}
+
+fn setup_auto_pause() -> TestDeps {
+    let mut deps = setup();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetAutoPause { threshold: 2, window_seconds: 60 }).unwrap();
+    deps
+}
+
+#[test]
+fn crossing_the_volume_threshold_pauses_records() {
+    let mut deps = setup_auto_pause();
+    for file_hash in ["a", "b"] {
+        let response = record(&mut deps, file_hash, RECIPIENT, &[]).unwrap();
+        assert_eq!(event_attr(&response, "alert"), None);
+    }
+    // The tripping record itself still lands
+    let response = record(&mut deps, "c", RECIPIENT, &[]).unwrap();
+    assert_eq!(event_attr(&response, "alert").as_deref(), Some("auto_paused"));
+    stored_transfer(&deps, "c", RECIPIENT);
+
+    let err = record(&mut deps, "d", RECIPIENT, &[]).unwrap_err();
+    assert!(matches!(err, ContractError::Paused {}));
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetPaused { paused: false }).unwrap();
+    record(&mut deps, "d", RECIPIENT, &[]).unwrap();
+}
+
+#[test]
+fn records_spread_beyond_the_window_do_not_pause() {
+    let mut deps = setup_auto_pause();
+    for (i, file_hash) in ["a", "b", "c", "d"].into_iter().enumerate() {
+        exec_at(&mut deps, env_after(i as u64 * 40), SENDER, &[], record_msg(file_hash, RECIPIENT)).unwrap();
+    }
+    assert!(!CONFIG.load(&deps.storage).unwrap().paused);
+}
+
+fn setup_features(features: Features) -> TestDeps {
+    setup_with(InstantiateMsg { features: Some(features), ..instantiate_msg() })
+}
+
+fn escrowed_multi_record() -> ExecuteMsg {
+    ExecuteMsg::RecordMultiRecipient {
+        file_hash: "file".to_string(),
+        recipients: vec!["alice".to_string(), "bob".to_string()],
+        zk_proof: vec![],
+        proved_height: None,
+        escrow_deadline: Some(mock_env().block.time.seconds() + 100),
+    }
+}
+
+fn disabled_feature(result: Result<Response, ContractError>) -> String {
+    match result {
+        Err(ContractError::FeatureDisabled { feature }) => feature,
+        other => panic!("expected FeatureDisabled, got {:?}", other),
+    }
+}
+
+#[test]
+fn features_default_when_omitted() {
+    let deps = setup();
+    let state = CONFIG.load(&deps.storage).unwrap();
+    assert_eq!(state.features, Features::default());
+    assert!(!state.opt_in_required);
+}
+
+#[test]
+fn disabled_features_gate_their_handlers() {
+    let mut deps = setup_features(Features { pausable: false, escrow: false, allowlist: false, opt_in: false });
+
+    let paused = exec(&mut deps, ADMIN, &[], ExecuteMsg::SetPaused { paused: true });
+    assert_eq!(disabled_feature(paused), "pausable");
+    let allowlist = ExecuteMsg::AddWithdrawDestination { address: "treasury".to_string() };
+    assert_eq!(disabled_feature(exec(&mut deps, ADMIN, &[], allowlist)), "allowlist");
+    let opt_in = exec(&mut deps, ADMIN, &[], ExecuteMsg::SetOptInRequired { required: true });
+    assert_eq!(disabled_feature(opt_in), "opt_in");
+    let escrow = exec(&mut deps, SENDER, &coins(1000, "usei"), escrowed_multi_record());
+    assert_eq!(disabled_feature(escrow), "escrow");
+
+    // Plain records are unaffected
+    record(&mut deps, "file", RECIPIENT, &[]).unwrap();
+}
+
+#[test]
+fn enabled_features_allow_their_handlers() {
+    let mut deps = setup_features(Features { pausable: true, escrow: true, allowlist: true, opt_in: true });
+    assert!(CONFIG.load(&deps.storage).unwrap().opt_in_required);
+
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetOptInRequired { required: false }).unwrap();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::AddWithdrawDestination { address: "treasury".to_string() }).unwrap();
+    exec(&mut deps, SENDER, &coins(1000, "usei"), escrowed_multi_record()).unwrap();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetPaused { paused: true }).unwrap();
+    let err = record(&mut deps, "paused", RECIPIENT, &[]).unwrap_err();
+    assert!(matches!(err, ContractError::Paused {}));
+}
+
+fn config_update() -> ExecuteMsg {
+    ExecuteMsg::UpdateConfig {
+        fee_percentage: None,
+        paused: None,
+        accepted_denoms: None,
+        allow_rerecord: None,
+        free_transfer_quota: None,
+        fee_increase_delay: None,
+        verbose_events: None,
+        minimal_events: None,
+        burn_percentage: None,
+        query_scan_limit: None,
+    }
+}
+
+#[test]
+fn partial_update_leaves_other_fields_unchanged() {
+    let mut deps = setup();
+    let before = CONFIG.load(&deps.storage).unwrap();
+    let mut msg = config_update();
+    if let ExecuteMsg::UpdateConfig { fee_percentage, paused, .. } = &mut msg {
+        *fee_percentage = Some(Uint128::new(50));
+        *paused = Some(true);
+    }
+
+    let response = exec(&mut deps, ADMIN, &[], msg).unwrap();
+    assert_eq!(event_attr(&response, "updated").as_deref(), Some("fee_percentage,paused"));
+    let after = CONFIG.load(&deps.storage).unwrap();
+    assert_eq!(after, State { fee_percentage: Uint128::new(50), paused: true, ..before });
+}
+
+#[test]
+fn empty_update_changes_nothing() {
+    let mut deps = setup();
+    let before = CONFIG.load(&deps.storage).unwrap();
+    let response = exec(&mut deps, ADMIN, &[], config_update()).unwrap();
+    assert_eq!(event_attr(&response, "updated").as_deref(), Some("none"));
+    assert_eq!(CONFIG.load(&deps.storage).unwrap(), before);
+}
+
+#[test]
+fn one_invalid_field_rejects_the_whole_update() {
+    let mut deps = setup();
+    let before = CONFIG.load(&deps.storage).unwrap();
+    let mut msg = config_update();
+    if let ExecuteMsg::UpdateConfig { allow_rerecord, burn_percentage, .. } = &mut msg {
+        *allow_rerecord = Some(true);
+        *burn_percentage = Some(Uint128::new(10001));
+    }
+
+    exec(&mut deps, ADMIN, &[], msg.clone()).unwrap_err();
+    assert_eq!(CONFIG.load(&deps.storage).unwrap(), before);
+    let err = exec(&mut deps, SENDER, &[], msg).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+}
+
+#[test]
+fn fee_recipient_cannot_be_the_contract() {
+    let mut deps = setup();
+    let set = |address: &str| ExecuteMsg::SetFeeRecipient { address: address.to_string() };
+    let err = exec(&mut deps, ADMIN, &[], set(MOCK_CONTRACT_ADDR)).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidFeeRecipient {}));
+
+    exec(&mut deps, ADMIN, &[], set("treasury")).unwrap();
+    assert_eq!(CONFIG.load(&deps.storage).unwrap().fee_recipient, "treasury");
+}
+
+#[test]
+fn instantiate_rejects_the_contract_as_fee_recipient() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg { fee_recipient: Some(MOCK_CONTRACT_ADDR.to_string()), ..instantiate_msg() };
+    let err = instantiate(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidFeeRecipient {}));
+}
+
+fn set_denoms(deps: &mut TestDeps, denoms: &[&str]) -> Result<Response, ContractError> {
+    let denoms = denoms.iter().map(|denom| denom.to_string()).collect();
+    exec(deps, ADMIN, &[], ExecuteMsg::SetAcceptedDenoms { denoms })
+}
+
+#[test]
+fn native_ibc_and_factory_denoms_are_accepted() {
+    let mut deps = setup();
+    let ibc = format!("ibc/{}", "27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2");
+    let denoms = ["usei", ibc.as_str(), "factory/sei1creator/mytoken", "gravity0x1234.erc-20_v2"];
+    set_denoms(&mut deps, &denoms).unwrap();
+    assert_eq!(CONFIG.load(&deps.storage).unwrap().accepted_denoms, denoms);
+}
+
+#[test]
+fn malformed_denoms_are_rejected() {
+    let mut deps = setup();
+    let short_hash = "ibc/ABC123".to_string();
+    let long = format!("u{}", "a".repeat(128));
+    for denom in ["", "us", "1usei", "u sei", "usei!", "ibc/", short_hash.as_str(), "factory/", "factory/creator", "factory//sub", long.as_str()] {
+        let err = set_denoms(&mut deps, &["usei", denom]).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidDenom {}), "{} was accepted", denom);
+    }
+    assert!(matches!(set_denoms(&mut deps, &[]), Err(ContractError::NoAcceptedDenoms {})));
+    assert_eq!(CONFIG.load(&deps.storage).unwrap().accepted_denoms, ["usei"]);
+}
+
+#[test]
+fn instantiate_validates_accepted_denoms() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg { accepted_denoms: Some(vec!["u sei".to_string()]), ..instantiate_msg() };
+    let err = instantiate(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidDenom {}));
+}
@@ -0,0 +1,740 @@
+use super::*;
+use cosmwasm_std::{coin, coins};
+
+// One usei in the 18-decimal unit fee totals are normalized to
+const USEI: u128 = 1_000_000_000_000;
+
+#[test]
+fn fees_paid_accumulate_per_sender() {
+    let mut deps = setup();
+    record(&mut deps, "a", RECIPIENT, &coins(1000, "usei")).unwrap();
+    record(&mut deps, "b", RECIPIENT, &coins(2000, "usei")).unwrap();
+    record(&mut deps, "c", RECIPIENT, &coins(500, "usei")).unwrap();
+
+    let paid: Uint128 = query_data(&deps, QueryMsg::GetFeesPaidBy { sender: SENDER.to_string() });
+    assert_eq!(paid, Uint128::new(35 * USEI));
+
+    let other: Uint128 = query_data(&deps, QueryMsg::GetFeesPaidBy { sender: "other".to_string() });
+    assert_eq!(other, Uint128::zero());
+}
+
+fn quote(deps: &TestDeps, amount: u128, denom: Option<&str>) -> Uint128 {
+    query_data(deps, QueryMsg::QuoteFee {
+        amount: Uint128::new(amount),
+        denom: denom.map(str::to_string),
+    })
+}
+
+#[test]
+fn quote_fee_applies_the_rate_for_the_denom() {
+    let mut deps = setup();
+    let denoms = ExecuteMsg::SetAcceptedDenoms { denoms: vec!["usei".to_string(), "uatom".to_string()] };
+    exec(&mut deps, ADMIN, &[], denoms).unwrap();
+    let rate = ExecuteMsg::SetDenomFeePercentage {
+        denom: "uatom".to_string(),
+        percentage: Some(Uint128::new(250)),
+    };
+    exec(&mut deps, ADMIN, &[], rate).unwrap();
+
+    assert_eq!(quote(&deps, 10000, None), Uint128::new(100));
+    assert_eq!(quote(&deps, 10000, Some("usei")), Uint128::new(100));
+    assert_eq!(quote(&deps, 10000, Some("uatom")), Uint128::new(250));
+    // Truncated toward zero
+    assert_eq!(quote(&deps, 199, None), Uint128::new(1));
+}
+
+#[test]
+fn quote_fee_matches_the_recorded_fee() {
+    let mut deps = setup();
+    let quoted = quote(&deps, 12345, None);
+
+    record(&mut deps, "file", RECIPIENT, &coins(12345, "usei")).unwrap();
+    assert_eq!(stored_transfer(&deps, "file", RECIPIENT).transfer_fee, quoted);
+}
+
+#[test]
+fn quote_fee_rejects_unaccepted_denoms() {
+    let deps = setup();
+    let msg = QueryMsg::QuoteFee {
+        amount: Uint128::new(100),
+        denom: Some("uatom".to_string()),
+    };
+    assert!(query(deps.as_ref(), mock_env(), msg).is_err());
+}
+
+#[test]
+fn fee_splits_stream_each_share_and_accrue_the_rest() {
+    let mut deps = setup();
+    let splits = ExecuteMsg::SetFeeSplits {
+        splits: vec![("dao".to_string(), 3000), ("dev".to_string(), 2000)],
+    };
+    exec(&mut deps, ADMIN, &[], splits).unwrap();
+
+    let response = record(&mut deps, "file", RECIPIENT, &coins(10000, "usei")).unwrap();
+
+    assert_eq!(
+        sent_to(&response),
+        vec![("dao".to_string(), coins(30, "usei")), ("dev".to_string(), coins(20, "usei"))]
+    );
+    assert_eq!(ACCRUED_FEES.load(&deps.storage, "usei").unwrap(), Uint128::new(50));
+}
+
+#[test]
+fn fee_splits_must_not_exceed_the_whole_fee() {
+    let mut deps = setup();
+    let splits = ExecuteMsg::SetFeeSplits {
+        splits: vec![("dao".to_string(), 6000), ("dev".to_string(), 4001)],
+    };
+
+    let err = exec(&mut deps, ADMIN, &[], splits).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidFeeSplits {}));
+}
+
+#[test]
+fn extract_funds_with_no_funds_is_zero() {
+    assert_eq!(extract_funds(&mock_info(SENDER, &[]), "usei").unwrap(), Uint128::zero());
+}
+
+#[test]
+fn extract_funds_sums_the_expected_denom() {
+    let info = mock_info(SENDER, &[coin(300, "usei"), coin(200, "usei")]);
+    assert_eq!(extract_funds(&info, "usei").unwrap(), Uint128::new(500));
+}
+
+#[test]
+fn extract_funds_rejects_mixed_denoms() {
+    let info = mock_info(SENDER, &[coin(300, "usei"), coin(200, "uatom")]);
+    let err = extract_funds(&info, "usei").unwrap_err();
+    assert!(matches!(err, ContractError::UnexpectedFunds { denom } if denom == "uatom"));
+}
+
+#[test]
+fn record_rejects_unaccepted_denoms() {
+    let mut deps = setup();
+    let err = record(&mut deps, "file", RECIPIENT, &coins(100, "uatom")).unwrap_err();
+    assert!(matches!(err, ContractError::UnexpectedFunds { denom } if denom == "uatom"));
+}
+
+#[test]
+fn free_quota_waives_the_first_records() {
+    let mut deps = setup();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetFreeTransferQuota { quota: 2 }).unwrap();
+
+    for file_hash in ["a", "b", "c"] {
+        record(&mut deps, file_hash, RECIPIENT, &coins(1000, "usei")).unwrap();
+    }
+
+    let fee = |file_hash| stored_transfer(&deps, file_hash, RECIPIENT).transfer_fee;
+    assert_eq!(fee("a"), Uint128::zero());
+    assert_eq!(fee("b"), Uint128::zero());
+    assert_eq!(fee("c"), Uint128::new(10));
+    // The quota is per sender
+    exec(&mut deps, ADMIN, &[], policy_trusting(&[SENDER, "other"])).unwrap();
+    exec(&mut deps, "other", &coins(1000, "usei"), record_msg("d", RECIPIENT)).unwrap();
+    assert_eq!(stored_transfer(&deps, "d", RECIPIENT).transfer_fee, Uint128::zero());
+}
+
+fn setup_with_fee_delay(delay: u64) -> TestDeps {
+    setup_with(InstantiateMsg {
+        fee_increase_delay: Some(delay),
+        ..instantiate_msg()
+    })
+}
+
+fn quote_at(deps: &TestDeps, env: Env, amount: u128) -> Uint128 {
+    query_data_at(deps, env, QueryMsg::QuoteFee {
+        amount: Uint128::new(amount),
+        denom: None,
+    })
+}
+
+#[test]
+fn fee_increase_waits_for_the_announce_delay() {
+    let mut deps = setup_with_fee_delay(100);
+    let propose = ExecuteMsg::ProposeFeePercentage { percentage: Uint128::new(500) };
+    exec(&mut deps, ADMIN, &[], propose).unwrap();
+
+    assert_eq!(quote_at(&deps, env_after(99), 10000), Uint128::new(100));
+    exec_at(&mut deps, env_after(99), SENDER, &coins(10000, "usei"), record_msg("early", RECIPIENT)).unwrap();
+    assert_eq!(stored_transfer(&deps, "early", RECIPIENT).transfer_fee, Uint128::new(100));
+
+    assert_eq!(quote_at(&deps, env_after(100), 10000), Uint128::new(500));
+    exec_at(&mut deps, env_after(100), SENDER, &coins(10000, "usei"), record_msg("late", RECIPIENT)).unwrap();
+    assert_eq!(stored_transfer(&deps, "late", RECIPIENT).transfer_fee, Uint128::new(500));
+}
+
+#[test]
+fn fee_decrease_applies_immediately() {
+    let mut deps = setup_with_fee_delay(100);
+    let propose = ExecuteMsg::ProposeFeePercentage { percentage: Uint128::new(50) };
+    exec(&mut deps, ADMIN, &[], propose).unwrap();
+    assert_eq!(quote_at(&deps, mock_env(), 10000), Uint128::new(50));
+}
+
+#[test]
+fn set_fee_percentage_cannot_raise_the_fee_directly() {
+    let mut deps = setup_with_fee_delay(100);
+    let err = exec(&mut deps, ADMIN, &[], ExecuteMsg::SetFeePercentage { percentage: Uint128::new(500) }).unwrap_err();
+    assert!(matches!(err, ContractError::FeeIncreaseRequiresNotice {}));
+}
+
+#[test]
+fn records_are_charged_the_rate_of_their_denom() {
+    let mut deps = setup();
+    let denoms = ExecuteMsg::SetAcceptedDenoms {
+        denoms: vec!["usei".to_string(), "uatom".to_string(), "uosmo".to_string()],
+    };
+    exec(&mut deps, ADMIN, &[], denoms).unwrap();
+    for (denom, rate) in [("usei", 300), ("uatom", 50)] {
+        let msg = ExecuteMsg::SetDenomFeePercentage { denom: denom.to_string(), percentage: Some(Uint128::new(rate)) };
+        exec(&mut deps, ADMIN, &[], msg).unwrap();
+    }
+
+    record(&mut deps, "sei", RECIPIENT, &coins(10000, "usei")).unwrap();
+    record(&mut deps, "atom", RECIPIENT, &coins(10000, "uatom")).unwrap();
+    // Unlisted denoms fall back to the default rate
+    record(&mut deps, "osmo", RECIPIENT, &coins(10000, "uosmo")).unwrap();
+
+    let fee = |file_hash| stored_transfer(&deps, file_hash, RECIPIENT).transfer_fee;
+    assert_eq!(fee("sei"), Uint128::new(300));
+    assert_eq!(fee("atom"), Uint128::new(50));
+    assert_eq!(fee("osmo"), Uint128::new(100));
+}
+
+#[test]
+fn denom_rate_for_an_unaccepted_denom_is_rejected() {
+    let mut deps = setup();
+    let set = ExecuteMsg::SetDenomFeePercentage { denom: "uatom".to_string(), percentage: Some(Uint128::new(250)) };
+    let err = exec(&mut deps, ADMIN, &[], set).unwrap_err();
+    assert!(matches!(err, ContractError::UnsupportedDenom {}));
+    assert!(!DENOM_FEE_PERCENTAGES.has(&deps.storage, "uatom"));
+
+    // A rate can still be cleared for a denom no longer accepted
+    let clear = ExecuteMsg::SetDenomFeePercentage { denom: "uatom".to_string(), percentage: None };
+    exec(&mut deps, ADMIN, &[], clear).unwrap();
+}
+
+#[test]
+fn cleared_denom_rate_falls_back_to_the_default() {
+    let mut deps = setup();
+    let set = ExecuteMsg::SetDenomFeePercentage { denom: "usei".to_string(), percentage: Some(Uint128::new(300)) };
+    exec(&mut deps, ADMIN, &[], set).unwrap();
+    let clear = ExecuteMsg::SetDenomFeePercentage { denom: "usei".to_string(), percentage: None };
+    exec(&mut deps, ADMIN, &[], clear).unwrap();
+
+    assert_eq!(quote(&deps, 10000, Some("usei")), Uint128::new(100));
+}
+
+#[test]
+fn volume_sums_the_full_attached_amount_per_denom() {
+    let mut deps = setup();
+    let denoms = ExecuteMsg::SetAcceptedDenoms { denoms: vec!["usei".to_string(), "uatom".to_string()] };
+    exec(&mut deps, ADMIN, &[], denoms).unwrap();
+    record(&mut deps, "a", RECIPIENT, &coins(1000, "usei")).unwrap();
+    record(&mut deps, "b", RECIPIENT, &coins(2500, "usei")).unwrap();
+    record(&mut deps, "c", RECIPIENT, &coins(700, "uatom")).unwrap();
+
+    let volume: Vec<Coin> = query_data(&deps, QueryMsg::GetVolume {});
+    assert_eq!(volume, vec![coin(700, "uatom"), coin(3500, "usei")]);
+}
+
+// Bank burns in a response
+fn burned(response: &Response) -> Vec<Coin> {
+    response
+        .messages
+        .iter()
+        .flat_map(|sub| match &sub.msg {
+            CosmosMsg::Bank(BankMsg::Burn { amount }) => amount.clone(),
+            _ => vec![],
+        })
+        .collect()
+}
+
+#[test]
+fn burned_share_of_the_fee_does_not_accrue() {
+    let mut deps = setup();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetBurnPercentage { percentage: Uint128::new(2500) }).unwrap();
+
+    let response = record(&mut deps, "file", RECIPIENT, &coins(10000, "usei")).unwrap();
+    assert_eq!(burned(&response), coins(25, "usei"));
+    assert_eq!(event_attr(&response, "burned").as_deref(), Some("25"));
+    assert_eq!(ACCRUED_FEES.load(&deps.storage, "usei").unwrap(), Uint128::new(75));
+}
+
+#[test]
+fn nothing_is_burned_by_default() {
+    let mut deps = setup();
+    let response = record(&mut deps, "file", RECIPIENT, &coins(10000, "usei")).unwrap();
+    assert!(burned(&response).is_empty());
+    assert_eq!(ACCRUED_FEES.load(&deps.storage, "usei").unwrap(), Uint128::new(100));
+}
+
+#[test]
+fn fee_schedule_reflects_every_fee_setting() {
+    let mut deps = setup();
+    let now = mock_env().block.time.seconds();
+    for msg in [
+        ExecuteMsg::SetDenomFeePercentage { denom: "usei".to_string(), percentage: Some(Uint128::new(300)) },
+        ExecuteMsg::SetFreeTransferQuota { quota: 3 },
+        ExecuteMsg::SetBurnPercentage { percentage: Uint128::new(1000) },
+        ExecuteMsg::SetFeeSplits { splits: vec![("dev".to_string(), 2000)] },
+        ExecuteMsg::SetFeeMode { mode: FeeMode::OnTop },
+        ExecuteMsg::SetMinFee { min_fee: Some(coin(5, "usei")) },
+        ExecuteMsg::ScheduleFeeRamp { target: Uint128::new(50), start: now + 100, end: now + 200 },
+    ] {
+        exec(&mut deps, ADMIN, &[], msg).unwrap();
+    }
+
+    let schedule: FeeSchedule = query_data(&deps, QueryMsg::GetFeeSchedule {});
+    assert_eq!(schedule, FeeSchedule {
+        base_percentage: Uint128::new(100),
+        effective_percentage: Uint128::new(100),
+        pending_fee: None,
+        fee_ramp: Some(FeeRamp { from: Uint128::new(100), target: Uint128::new(50), start: now + 100, end: now + 200 }),
+        denom_overrides: vec![("usei".to_string(), Uint128::new(300))],
+        free_transfer_quota: 3,
+        burn_percentage: Uint128::new(1000),
+        fee_splits: vec![(Addr::unchecked("dev"), 2000)],
+        rounding: "floor".to_string(),
+        fee_mode: FeeMode::OnTop,
+        min_fee: Some(coin(5, "usei")),
+    });
+}
+
+#[test]
+fn fee_ramp_interpolates_linearly() {
+    let mut deps = setup();
+    let start = mock_env().block.time.seconds() + 100;
+    let ramp = ExecuteMsg::ScheduleFeeRamp { target: Uint128::new(50), start, end: start + 100 };
+    exec(&mut deps, ADMIN, &[], ramp).unwrap();
+
+    // 1% at the start, 0.75% halfway, 0.5% from the end on
+    assert_eq!(quote_at(&deps, env_after(100), 10000), Uint128::new(100));
+    assert_eq!(quote_at(&deps, env_after(150), 10000), Uint128::new(75));
+    assert_eq!(quote_at(&deps, env_after(200), 10000), Uint128::new(50));
+    assert_eq!(quote_at(&deps, env_after(1000), 10000), Uint128::new(50));
+
+    exec_at(&mut deps, env_after(150), SENDER, &coins(10000, "usei"), record_msg("mid", RECIPIENT)).unwrap();
+    assert_eq!(stored_transfer(&deps, "mid", RECIPIENT).transfer_fee, Uint128::new(75));
+}
+
+#[test]
+fn fee_ramp_must_end_after_it_starts() {
+    let mut deps = setup();
+    let now = mock_env().block.time.seconds();
+    let ramp = ExecuteMsg::ScheduleFeeRamp { target: Uint128::new(50), start: now + 100, end: now + 100 };
+    assert!(exec(&mut deps, ADMIN, &[], ramp).is_err());
+}
+
+#[test]
+fn recipients_are_ranked_by_the_fees_they_generated() {
+    let mut deps = setup();
+    record(&mut deps, "a", "alice", &coins(3000, "usei")).unwrap();
+    record(&mut deps, "b", "bob", &coins(1000, "usei")).unwrap();
+    record(&mut deps, "c", "carol", &coins(2000, "usei")).unwrap();
+    // Bob overtakes Carol once his fees add up
+    record(&mut deps, "d", "bob", &coins(1500, "usei")).unwrap();
+
+    let top: Vec<RecipientFees> = query_data(&deps, QueryMsg::TopRecipientsByFees { limit: 2 });
+    assert_eq!(top, vec![
+        RecipientFees { recipient: "alice".to_string(), fees: Uint128::new(30 * USEI) },
+        RecipientFees { recipient: "bob".to_string(), fees: Uint128::new(25 * USEI) },
+    ]);
+}
+
+fn record_with_coupon(deps: &mut TestDeps, file_hash: &str, code: &str) -> Result<Response, ContractError> {
+    let mut msg = record_msg(file_hash, RECIPIENT);
+    if let ExecuteMsg::RecordTransfer { coupon, .. } = &mut msg {
+        *coupon = Some(code.to_string());
+    }
+    exec(deps, SENDER, &coins(10000, "usei"), msg)
+}
+
+fn setup_with_coupon() -> TestDeps {
+    let mut deps = setup();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::IssueCoupons { codes: vec!["FREE1".to_string()] }).unwrap();
+    deps
+}
+
+#[test]
+fn valid_coupon_waives_the_fee() {
+    let mut deps = setup_with_coupon();
+    record_with_coupon(&mut deps, "file", "FREE1").unwrap();
+    assert_eq!(stored_transfer(&deps, "file", RECIPIENT).transfer_fee, Uint128::zero());
+}
+
+#[test]
+fn used_coupon_is_rejected() {
+    let mut deps = setup_with_coupon();
+    record_with_coupon(&mut deps, "first", "FREE1").unwrap();
+    let err = record_with_coupon(&mut deps, "second", "FREE1").unwrap_err();
+    assert!(matches!(err, ContractError::CouponAlreadyUsed {}));
+}
+
+#[test]
+fn unknown_coupon_is_rejected() {
+    let mut deps = setup_with_coupon();
+    let err = record_with_coupon(&mut deps, "file", "BOGUS").unwrap_err();
+    assert!(matches!(err, ContractError::InvalidCoupon {}));
+}
+
+fn effective_rate(deps: &TestDeps, env: Env, amount: u128, denom: Option<&str>) -> EffectiveFeeRate {
+    query_data_at(deps, env, QueryMsg::GetEffectiveFeeRate {
+        amount: Some(Uint128::new(amount)),
+        denom: denom.map(str::to_string),
+    })
+}
+
+#[test]
+fn effective_rate_follows_a_ramp() {
+    let mut deps = setup();
+    let start = mock_env().block.time.seconds();
+    let ramp = ExecuteMsg::ScheduleFeeRamp { target: Uint128::new(50), start, end: start + 100 };
+    exec(&mut deps, ADMIN, &[], ramp).unwrap();
+
+    let rate = effective_rate(&deps, env_after(50), 10000, None);
+    assert_eq!(rate.denom, "usei");
+    assert_eq!(rate.percentage, Uint128::new(75));
+    assert_eq!(rate.fee, Some(quote_at(&deps, env_after(50), 10000)));
+}
+
+#[test]
+fn effective_rate_uses_the_denom_override() {
+    let mut deps = setup();
+    let denoms = ExecuteMsg::SetAcceptedDenoms { denoms: vec!["usei".to_string(), "uatom".to_string()] };
+    exec(&mut deps, ADMIN, &[], denoms).unwrap();
+    let set = ExecuteMsg::SetDenomFeePercentage { denom: "uatom".to_string(), percentage: Some(Uint128::new(250)) };
+    exec(&mut deps, ADMIN, &[], set).unwrap();
+
+    let rate = effective_rate(&deps, mock_env(), 10000, Some("uatom"));
+    assert_eq!((rate.percentage, rate.fee), (Uint128::new(250), Some(Uint128::new(250))));
+    let rate = effective_rate(&deps, mock_env(), 10000, Some("usei"));
+    assert_eq!((rate.percentage, rate.fee), (Uint128::new(100), Some(Uint128::new(100))));
+}
+
+#[test]
+fn effective_rate_includes_the_min_fee_floor() {
+    let mut deps = setup();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetMinFee { min_fee: Some(coin(50, "usei")) }).unwrap();
+
+    // 1% of 1000 is 10, raised to the floor
+    let rate = effective_rate(&deps, mock_env(), 1000, None);
+    assert_eq!(rate.fee, Some(Uint128::new(50)));
+    assert_eq!(rate.min_fee, Some(coin(50, "usei")));
+    record(&mut deps, "file", RECIPIENT, &coins(1000, "usei")).unwrap();
+    assert_eq!(stored_transfer(&deps, "file", RECIPIENT).transfer_fee, Uint128::new(50));
+}
+
+fn fees_paid(deps: &TestDeps, sender: &str) -> Uint128 {
+    query_data(deps, QueryMsg::GetFeesPaidBy { sender: sender.to_string() })
+}
+
+#[test]
+fn fees_in_different_exponents_normalize_to_the_same_total() {
+    let mut deps = setup();
+    let denoms = ExecuteMsg::SetAcceptedDenoms { denoms: vec!["uatom".to_string(), "aevm".to_string()] };
+    exec(&mut deps, ADMIN, &[], denoms).unwrap();
+    let exponent = ExecuteMsg::SetDenomExponent { denom: "aevm".to_string(), exponent: Some(18) };
+    exec(&mut deps, ADMIN, &[], exponent).unwrap();
+    exec(&mut deps, ADMIN, &[], policy_trusting(&["sixdecimals", "eighteendecimals"])).unwrap();
+
+    // One whole token in each: 10^6 uatom and 10^18 aevm
+    exec(&mut deps, "sixdecimals", &coins(1_000_000, "uatom"), record_msg("a", RECIPIENT)).unwrap();
+    exec(&mut deps, "eighteendecimals", &coins(10u128.pow(18), "aevm"), record_msg("b", RECIPIENT)).unwrap();
+
+    assert_eq!(fees_paid(&deps, "sixdecimals"), Uint128::new(10u128.pow(16)));
+    assert_eq!(fees_paid(&deps, "sixdecimals"), fees_paid(&deps, "eighteendecimals"));
+}
+
+#[test]
+fn only_the_admin_sets_denom_exponents() {
+    let mut deps = setup();
+    let set = |exponent| ExecuteMsg::SetDenomExponent { denom: "aevm".to_string(), exponent };
+    let err = exec(&mut deps, SENDER, &[], set(Some(18))).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    assert!(exec(&mut deps, ADMIN, &[], set(Some(37))).is_err());
+
+    exec(&mut deps, ADMIN, &[], set(Some(18))).unwrap();
+    assert_eq!(DENOM_EXPONENTS.load(&deps.storage, "aevm").unwrap(), 18);
+    exec(&mut deps, ADMIN, &[], set(None)).unwrap();
+    assert!(!DENOM_EXPONENTS.has(&deps.storage, "aevm"));
+}
+
+fn set_min_amount(deps: &mut TestDeps, denom: &str, amount: Option<u128>) {
+    let msg = ExecuteMsg::SetMinTransferAmount { denom: denom.to_string(), amount: amount.map(Uint128::new) };
+    exec(deps, ADMIN, &[], msg).unwrap();
+}
+
+#[test]
+fn each_denom_has_its_own_minimum() {
+    let mut deps = setup();
+    let denoms = ExecuteMsg::SetAcceptedDenoms { denoms: vec!["usei".to_string(), "uatom".to_string()] };
+    exec(&mut deps, ADMIN, &[], denoms).unwrap();
+    set_min_amount(&mut deps, "usei", Some(1000));
+    set_min_amount(&mut deps, "uatom", Some(50));
+
+    // 100 clears the uatom floor but not the usei one
+    record(&mut deps, "atom", RECIPIENT, &coins(100, "uatom")).unwrap();
+    let err = record(&mut deps, "sei", RECIPIENT, &coins(100, "usei")).unwrap_err();
+    assert!(matches!(err, ContractError::BelowMinimumAmount { denom, min } if denom == "usei" && min == Uint128::new(1000)));
+    record(&mut deps, "sei", RECIPIENT, &coins(1000, "usei")).unwrap();
+    let err = record(&mut deps, "low", RECIPIENT, &coins(49, "uatom")).unwrap_err();
+    assert!(matches!(err, ContractError::BelowMinimumAmount { denom, .. } if denom == "uatom"));
+}
+
+#[test]
+fn cleared_minimum_no_longer_applies() {
+    let mut deps = setup();
+    set_min_amount(&mut deps, "usei", Some(1000));
+    set_min_amount(&mut deps, "usei", None);
+    record(&mut deps, "file", RECIPIENT, &coins(1, "usei")).unwrap();
+
+    let msg = ExecuteMsg::SetMinTransferAmount { denom: "usei".to_string(), amount: None };
+    let err = exec(&mut deps, SENDER, &[], msg).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+}
+
+#[test]
+fn minimum_for_an_unaccepted_denom_is_rejected() {
+    let mut deps = setup();
+    let set = ExecuteMsg::SetMinTransferAmount { denom: "uatom".to_string(), amount: Some(Uint128::new(50)) };
+    let err = exec(&mut deps, ADMIN, &[], set).unwrap_err();
+    assert!(matches!(err, ContractError::UnsupportedDenom {}));
+    assert!(!MIN_TRANSFER_AMOUNTS.has(&deps.storage, "uatom"));
+
+    // A minimum can still be cleared for a denom no longer accepted
+    set_min_amount(&mut deps, "uatom", None);
+}
+
+fn cas(deps: &mut TestDeps, expected: u128, new: u128) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::SetFeePercentageCas { expected: Uint128::new(expected), new: Uint128::new(new) };
+    exec(deps, ADMIN, &[], msg)
+}
+
+#[test]
+fn compare_and_set_applies_when_the_rate_matches() {
+    let mut deps = setup();
+    cas(&mut deps, 100, 50).unwrap();
+    assert_eq!(CONFIG.load(&deps.storage).unwrap().fee_percentage, Uint128::new(50));
+}
+
+#[test]
+fn compare_and_set_conflicts_on_a_stale_rate() {
+    let mut deps = setup();
+    cas(&mut deps, 100, 50).unwrap();
+
+    // A second proposal based on the original rate lost the race
+    let err = cas(&mut deps, 100, 75).unwrap_err();
+    assert!(matches!(err, ContractError::Conflict {}));
+    assert_eq!(CONFIG.load(&deps.storage).unwrap().fee_percentage, Uint128::new(50));
+
+    let msg = ExecuteMsg::SetFeePercentageCas { expected: Uint128::new(50), new: Uint128::new(10) };
+    let err = exec(&mut deps, SENDER, &[], msg).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+}
+
+fn set_override(deps: &mut TestDeps, file_hash: &str, fee: Option<u128>) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::SetFeeOverride {
+        sender: SENDER.to_string(),
+        file_hash: file_hash.to_string(),
+        fee: fee.map(Uint128::new),
+    };
+    exec(deps, ADMIN, &[], msg)
+}
+
+#[test]
+fn fee_override_replaces_the_computed_fee() {
+    let mut deps = setup();
+    set_override(&mut deps, "deal", Some(7)).unwrap();
+
+    record(&mut deps, "deal", RECIPIENT, &coins(10000, "usei")).unwrap();
+    record(&mut deps, "standard", RECIPIENT, &coins(10000, "usei")).unwrap();
+    assert_eq!(stored_transfer(&deps, "deal", RECIPIENT).transfer_fee, Uint128::new(7));
+    assert_eq!(stored_transfer(&deps, "standard", RECIPIENT).transfer_fee, Uint128::new(100));
+}
+
+#[test]
+fn fee_override_is_scoped_to_its_sender_and_capped_by_the_amount() {
+    let mut deps = setup();
+    set_override(&mut deps, "deal", Some(5000)).unwrap();
+    exec(&mut deps, ADMIN, &[], policy_trusting(&[SENDER, "other"])).unwrap();
+
+    exec(&mut deps, "other", &coins(10000, "usei"), record_msg("deal", "elsewhere")).unwrap();
+    assert_eq!(stored_transfer(&deps, "deal", "elsewhere").transfer_fee, Uint128::new(100));
+    record(&mut deps, "deal", RECIPIENT, &coins(1000, "usei")).unwrap();
+    assert_eq!(stored_transfer(&deps, "deal", RECIPIENT).transfer_fee, Uint128::new(1000));
+}
+
+#[test]
+fn cleared_override_falls_back_to_the_default() {
+    let mut deps = setup();
+    set_override(&mut deps, "deal", Some(7)).unwrap();
+    set_override(&mut deps, "deal", None).unwrap();
+    record(&mut deps, "deal", RECIPIENT, &coins(10000, "usei")).unwrap();
+    assert_eq!(stored_transfer(&deps, "deal", RECIPIENT).transfer_fee, Uint128::new(100));
+
+    let msg = ExecuteMsg::SetFeeOverride { sender: SENDER.to_string(), file_hash: "x".to_string(), fee: None };
+    assert!(matches!(exec(&mut deps, SENDER, &[], msg), Err(ContractError::Unauthorized {})));
+}
+
+// Fee and remainder recorded for 10100 usei attached under `mode`, at the 1% default rate
+fn fee_and_remainder(mode: FeeMode) -> (Uint128, Uint128) {
+    let mut deps = setup();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetFeeMode { mode }).unwrap();
+    record(&mut deps, "file", RECIPIENT, &coins(10100, "usei")).unwrap();
+    let fee = stored_transfer(&deps, "file", RECIPIENT).transfer_fee;
+    assert_eq!(ACCRUED_FEES.load(&deps.storage, "usei").unwrap(), fee);
+    (fee, Uint128::new(10100) - fee)
+}
+
+#[test]
+fn fee_from_amount_is_taken_out_of_attached_funds() {
+    assert_eq!(fee_and_remainder(FeeMode::FromAmount), (Uint128::new(101), Uint128::new(9999)));
+}
+
+#[test]
+fn fee_on_top_leaves_the_net_amount_as_remainder() {
+    assert_eq!(fee_and_remainder(FeeMode::OnTop), (Uint128::new(100), Uint128::new(10000)));
+}
+
+#[test]
+fn fee_mode_is_admin_only_and_reported_in_the_schedule() {
+    let mut deps = setup();
+    let msg = ExecuteMsg::SetFeeMode { mode: FeeMode::OnTop };
+    assert!(matches!(exec(&mut deps, SENDER, &[], msg.clone()), Err(ContractError::Unauthorized {})));
+
+    exec(&mut deps, ADMIN, &[], msg).unwrap();
+    let schedule: FeeSchedule = query_data(&deps, QueryMsg::GetFeeSchedule {});
+    assert_eq!(schedule.fee_mode, FeeMode::OnTop);
+    assert_eq!(quote(&deps, 10100, None), Uint128::new(100));
+}
+
+// Contract accepting usei and uatom, with a 50 usei fee floor
+fn setup_usei_floor() -> TestDeps {
+    let mut deps = setup();
+    let denoms = ExecuteMsg::SetAcceptedDenoms { denoms: vec!["usei".to_string(), "uatom".to_string()] };
+    exec(&mut deps, ADMIN, &[], denoms).unwrap();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetMinFee { min_fee: Some(coin(50, "usei")) }).unwrap();
+    deps
+}
+
+fn accrued(deps: &TestDeps, denom: &str) -> Uint128 {
+    ACCRUED_FEES.may_load(&deps.storage, denom).unwrap().unwrap_or_default()
+}
+
+#[test]
+fn min_fee_floor_is_attached_in_its_own_denom() {
+    let mut deps = setup_usei_floor();
+
+    let err = record(&mut deps, "a", RECIPIENT, &coins(10000, "uatom")).unwrap_err();
+    assert!(matches!(err, ContractError::MinFeeNotMet { .. }));
+    let short = [coin(10000, "uatom"), coin(49, "usei")];
+    assert!(matches!(record(&mut deps, "a", RECIPIENT, &short), Err(ContractError::MinFeeNotMet { .. })));
+
+    record(&mut deps, "a", RECIPIENT, &[coin(10000, "uatom"), coin(50, "usei")]).unwrap();
+    assert_eq!(stored_transfer(&deps, "a", RECIPIENT).transfer_fee, Uint128::new(100));
+    assert_eq!(accrued(&deps, "uatom"), Uint128::new(100));
+    assert_eq!(accrued(&deps, "usei"), Uint128::new(50));
+
+    // Paid in the fee denom itself, the computed fee is raised to the floor
+    record(&mut deps, "b", RECIPIENT, &coins(1000, "usei")).unwrap();
+    assert_eq!(stored_transfer(&deps, "b", RECIPIENT).transfer_fee, Uint128::new(50));
+    assert_eq!(accrued(&deps, "usei"), Uint128::new(100));
+}
+
+#[test]
+fn batch_draws_one_floor_per_item_from_the_fee_denom() {
+    let mut deps = setup_usei_floor();
+    let batch = |first: &str, second: &str| ExecuteMsg::BatchRecordTransfer {
+        transfers: vec![batch_item(first, RECIPIENT, 4000), batch_item(second, RECIPIENT, 6000)],
+    };
+
+    let short = [coin(10000, "uatom"), coin(50, "usei")];
+    let err = exec(&mut setup_usei_floor(), SENDER, &short, batch("a", "b")).unwrap_err();
+    assert!(matches!(err, ContractError::MinFeeNotMet { .. }));
+
+    exec(&mut deps, SENDER, &[coin(10000, "uatom"), coin(100, "usei")], batch("a", "b")).unwrap();
+    assert_eq!(accrued(&deps, "uatom"), Uint128::new(100));
+    assert_eq!(accrued(&deps, "usei"), Uint128::new(100));
+}
+
+#[test]
+fn multi_recipient_draws_one_floor_per_recipient() {
+    let mut deps = setup_usei_floor();
+    let multi = |file_hash: &str| ExecuteMsg::RecordMultiRecipient {
+        file_hash: file_hash.to_string(),
+        recipients: vec!["alice".to_string(), "bob".to_string()],
+        zk_proof: vec![],
+        proved_height: None,
+        escrow_deadline: None,
+    };
+
+    let short = [coin(10000, "uatom"), coin(99, "usei")];
+    let err = exec(&mut setup_usei_floor(), SENDER, &short, multi("a")).unwrap_err();
+    assert!(matches!(err, ContractError::MinFeeNotMet { .. }));
+
+    // Overpaying the floor is kept with the fees
+    exec(&mut deps, SENDER, &[coin(10000, "uatom"), coin(120, "usei")], multi("a")).unwrap();
+    assert_eq!(accrued(&deps, "uatom"), Uint128::new(100));
+    assert_eq!(accrued(&deps, "usei"), Uint128::new(120));
+}
+
+#[test]
+fn floor_attached_without_a_computed_fee_is_rejected() {
+    let mut deps = setup_usei_floor();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetFreeTransferQuota { quota: 1 }).unwrap();
+
+    let err = record(&mut deps, "a", RECIPIENT, &[coin(10000, "uatom"), coin(50, "usei")]).unwrap_err();
+    assert!(matches!(err, ContractError::UnexpectedFunds { denom } if denom == "usei"));
+}
+
+#[test]
+fn quote_fee_applies_the_min_fee_floor_in_its_denom() {
+    let deps = setup_usei_floor();
+    assert_eq!(quote(&deps, 1000, Some("usei")), Uint128::new(50));
+    assert_eq!(quote(&deps, 10000, Some("usei")), Uint128::new(100));
+    // Paid in another denom the floor is attached separately
+    assert_eq!(quote(&deps, 1000, Some("uatom")), Uint128::new(10));
+}
+
+// Contract capping each sender at 250 usei of fees per hour
+fn setup_spend_cap() -> TestDeps {
+    let mut deps = setup();
+    exec(&mut deps, ADMIN, &[], policy_trusting(&[SENDER, "other"])).unwrap();
+    let cap = SpendCap {
+        amount: Uint128::new(250 * USEI),
+        window_seconds: 3600,
+    };
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetSpendCap { cap: Some(cap) }).unwrap();
+    deps
+}
+
+fn record_as_at(deps: &mut TestDeps, sender: &str, seconds: u64, file_hash: &str) -> Result<Response, ContractError> {
+    exec_at(deps, env_after(seconds), sender, &coins(10000, "usei"), record_msg(file_hash, RECIPIENT))
+}
+
+#[test]
+fn records_over_the_windowed_spend_cap_are_rejected() {
+    let mut deps = setup_spend_cap();
+    record_as_at(&mut deps, SENDER, 0, "a").unwrap();
+    record_as_at(&mut deps, SENDER, 10, "b").unwrap();
+
+    // Rejected records use their own files, as mock storage isn't rolled back
+    let err = record_as_at(&mut deps, SENDER, 20, "rejected").unwrap_err();
+    assert!(matches!(err, ContractError::SpendCapExceeded { cap } if cap == Uint128::new(250 * USEI)));
+    // The cap is per sender
+    record_as_at(&mut deps, "other", 20, "c").unwrap();
+}
+
+#[test]
+fn spend_cap_frees_up_as_the_window_rolls_past() {
+    let mut deps = setup_spend_cap();
+    record_as_at(&mut deps, SENDER, 0, "a").unwrap();
+    record_as_at(&mut deps, SENDER, 1800, "b").unwrap();
+    assert!(record_as_at(&mut deps, SENDER, 3599, "rejected").is_err());
+
+    // The first fee leaves the window at 3600; the second still counts until 5400
+    record_as_at(&mut deps, SENDER, 3600, "c").unwrap();
+    assert!(record_as_at(&mut deps, SENDER, 5399, "rejected again").is_err());
+    record_as_at(&mut deps, SENDER, 5400, "d").unwrap();
+}
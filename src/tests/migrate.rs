@@ -0,0 +1,273 @@
+use super::*;
+use cosmwasm_std::testing::MOCK_CONTRACT_ADDR;
+use cosmwasm_std::{coins, to_json_vec};
+
+// The single JSON blob the first release kept under "state"
+fn legacy_blob() -> Vec<u8> {
+    to_json_vec(&legacy_state()).unwrap()
+}
+
+fn legacy_state() -> LegacyState {
+    LegacyState {
+        file_transfers: vec![
+            LegacyFileTransfer {
+                file_hash: "hash1".to_string(),
+                sender: SENDER.to_string(),
+                recipient: RECIPIENT.to_string(),
+                timestamp: 1_600_000_000,
+                transfer_fee: Uint128::new(10),
+            },
+            LegacyFileTransfer {
+                file_hash: "hash2".to_string(),
+                sender: SENDER.to_string(),
+                recipient: "other".to_string(),
+                timestamp: 1_600_000_100,
+                transfer_fee: Uint128::new(20),
+            },
+        ],
+        admin: ADMIN.to_string(),
+        fee_percentage: Uint128::new(250),
+    }
+}
+
+fn migrated() -> TestDeps {
+    let mut deps = mock_dependencies();
+    deps.storage.set(LEGACY_STATE_KEY, &legacy_blob());
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    deps
+}
+
+#[test]
+fn legacy_blob_decodes_from_baseline_json() {
+    let raw = br#"{"file_transfers":[{"file_hash":"h","sender":"s","recipient":"r","timestamp":1,"transfer_fee":"5"}],"admin":"admin","fee_percentage":"100"}"#;
+    let legacy: LegacyState = from_json(raw.as_slice()).unwrap();
+    assert_eq!(legacy.admin, ADMIN);
+    assert_eq!(legacy.file_transfers[0].transfer_fee, Uint128::new(5));
+}
+
+#[test]
+fn migrate_maps_legacy_config_onto_instantiate_defaults() {
+    let deps = migrated();
+    let state = CONFIG.load(&deps.storage).unwrap();
+    assert_eq!(
+        state,
+        default_state(ADMIN.to_string(), Uint128::new(250), mock_env().block.time.seconds())
+    );
+    assert_eq!(state.fee_recipient, ADMIN);
+    assert!(deps.storage.get(LEGACY_STATE_KEY).is_none());
+}
+
+#[test]
+fn migrate_round_trips_legacy_transfers() {
+    let deps = migrated();
+    for legacy in legacy_state().file_transfers {
+        let transfer = stored_transfer(&deps, &legacy.file_hash, &legacy.recipient);
+        assert_eq!(
+            LegacyFileTransfer {
+                file_hash: transfer.file_hash,
+                sender: transfer.sender,
+                recipient: transfer.recipient,
+                timestamp: transfer.timestamp,
+                transfer_fee: transfer.transfer_fee,
+            },
+            legacy
+        );
+        assert_eq!(transfer.status, TransferStatus::Recorded);
+        assert!(query_data::<bool>(&deps, QueryMsg::FileExists { file_hash: legacy.file_hash }));
+    }
+    assert_eq!(NEXT_TRANSFER_ID.load(&deps.storage).unwrap(), 2);
+}
+
+#[test]
+fn migrated_contract_keeps_recording() {
+    let mut deps = migrated();
+    exec(&mut deps, ADMIN, &[], policy_trusting(&[SENDER])).unwrap();
+    record(&mut deps, "hash3", RECIPIENT, &[]).unwrap();
+    assert_eq!(TRANSFER_KEYS.load(&deps.storage, ("hash3", RECIPIENT)).unwrap(), 2);
+}
+
+#[test]
+fn migrate_without_legacy_blob_is_a_noop() {
+    let mut deps = setup();
+    let before = CONFIG.load(&deps.storage).unwrap();
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(CONFIG.load(&deps.storage).unwrap(), before);
+}
+
+// Transfer exported from another deployment
+fn exported(file_hash: &str, recipient: &str) -> FileTransfer {
+    FileTransfer {
+        file_hash: file_hash.to_string(),
+        sender: SENDER.to_string(),
+        recipient: recipient.to_string(),
+        timestamp: 1_600_000_000,
+        transfer_fee: Uint128::new(10),
+        status: TransferStatus::Recorded,
+        revoked_at: None,
+        expires_at: None,
+        metadata_commitment: None,
+        proof_hash: Binary::default(),
+        proved_height: 0,
+        wrapped_key: None,
+        file_size: None,
+        recipient_set_hash: None,
+        delivery_signature: None,
+        update_seq: 0,
+        visible_at: None,
+        memo: None,
+        epoch: 0,
+    }
+}
+
+fn instantiate_preloaded(transfers: Vec<FileTransfer>) -> Result<TestDeps, ContractError> {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        initial_transfers: Some(transfers),
+        ..instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg)?;
+    Ok(deps)
+}
+
+#[test]
+fn preloaded_transfers_are_queryable() {
+    let deps = instantiate_preloaded(vec![exported("hash1", RECIPIENT), exported("hash2", "other")]).unwrap();
+
+    let transfers: PageResponse<FileTransfer> = query_data(&deps, QueryMsg::GetFileTransfers { page: None });
+    let keys: Vec<_> = transfers.items.iter().map(|t| (t.file_hash.as_str(), t.recipient.as_str())).collect();
+    assert_eq!(keys, [("hash1", RECIPIENT), ("hash2", "other")]);
+    assert!(query_data::<bool>(&deps, QueryMsg::FileExists { file_hash: "hash2".to_string() }));
+    assert_eq!(NEXT_TRANSFER_ID.load(&deps.storage).unwrap(), 2);
+}
+
+#[test]
+fn preloaded_duplicates_are_rejected() {
+    let result = instantiate_preloaded(vec![exported("hash1", RECIPIENT), exported("hash1", RECIPIENT)]);
+    assert!(matches!(result, Err(ContractError::DuplicateTransfer {})));
+}
+
+#[test]
+fn preloaded_addresses_are_validated() {
+    assert!(instantiate_preloaded(vec![exported("hash1", "Not-An-Address")]).is_err());
+}
+
+// Every page of ExportSnapshot, `limit` transfers at a time
+fn export_all(deps: &TestDeps, limit: u32) -> (InstantiateMsg, Vec<FileTransfer>) {
+    let mut config = None;
+    let mut transfers = vec![];
+    let mut start_after = None;
+    loop {
+        let page: Snapshot = query_data(deps, QueryMsg::ExportSnapshot { start_after, limit: Some(limit) });
+        config = config.or(page.config);
+        transfers.extend(page.transfers);
+        match page.last_id {
+            Some(id) => start_after = Some(id),
+            None => return (config.unwrap(), transfers),
+        }
+    }
+}
+
+fn listed(deps: &TestDeps) -> Vec<FileTransfer> {
+    let page: PageResponse<FileTransfer> = query_data(deps, QueryMsg::GetFileTransfers { page: None });
+    // Sequences are stamped afresh by whichever instance stores the transfer
+    page.items.into_iter().map(|t| FileTransfer { update_seq: 0, ..t }).collect()
+}
+
+#[test]
+fn exported_snapshot_preloads_an_identical_instance() {
+    let mut source = setup_with(InstantiateMsg {
+        event_namespace: Some("exported".to_string()),
+        max_ttl_seconds: Some(3600),
+        accepted_denoms: Some(vec!["usei".to_string(), "uatom".to_string()]),
+        ..instantiate_msg()
+    });
+    record(&mut source, "a", RECIPIENT, &coins(1000, "usei")).unwrap();
+    record(&mut source, "b", "other", &coins(500, "uatom")).unwrap();
+    record(&mut source, "c", RECIPIENT, &[]).unwrap();
+    let revoke = ExecuteMsg::RevokeTransfer { file_hash: "a".to_string(), recipient: RECIPIENT.to_string() };
+    exec(&mut source, SENDER, &[], revoke).unwrap();
+
+    let (config, transfers) = export_all(&source, 2);
+    assert_eq!(transfers.len(), 3);
+    let mut target = mock_dependencies();
+    let msg = InstantiateMsg { initial_transfers: Some(transfers), ..config };
+    instantiate(target.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+
+    assert_eq!(listed(&target), listed(&source));
+    let (source_state, target_state) = (CONFIG.load(&source.storage).unwrap(), CONFIG.load(&target.storage).unwrap());
+    assert_eq!(target_state.event_namespace, "exported");
+    assert_eq!(target_state.max_ttl_seconds, source_state.max_ttl_seconds);
+    assert_eq!(target_state.accepted_denoms, source_state.accepted_denoms);
+    assert_eq!(target_state.features, source_state.features);
+    assert_eq!(export_all(&target, 10).1.len(), 3);
+}
+
+#[test]
+fn preloaded_expiries_are_indexed_except_revoked() {
+    let expiring = FileTransfer { expires_at: Some(1_600_000_100), ..exported("hash1", RECIPIENT) };
+    let revoked = FileTransfer {
+        expires_at: Some(1_600_000_100),
+        status: TransferStatus::Revoked,
+        revoked_at: Some(1_600_000_050),
+        ..exported("hash2", RECIPIENT)
+    };
+    let deps = instantiate_preloaded(vec![expiring, revoked, exported("hash3", RECIPIENT)]).unwrap();
+
+    let indexed: Vec<(u64, u64)> = TRANSFER_EXPIRIES
+        .keys(&deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()
+        .unwrap();
+    assert_eq!(indexed, vec![(1_600_000_100, 0)]);
+}
+
+#[test]
+fn migrate_indexes_expiries_recorded_before_the_index() {
+    let mut deps = setup();
+    let mut msg = record_msg("file", RECIPIENT);
+    if let ExecuteMsg::RecordTransfer { ttl_seconds, .. } = &mut msg {
+        *ttl_seconds = Some(100);
+    }
+    exec(&mut deps, SENDER, &[], msg).unwrap();
+    record(&mut deps, "forever", RECIPIENT, &[]).unwrap();
+    // As stored by a build without the expiry index
+    let expires_at = stored_transfer(&deps, "file", RECIPIENT).expires_at.unwrap();
+    TRANSFER_EXPIRIES.remove(&mut deps.storage, (expires_at, 0));
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert!(TRANSFER_EXPIRIES.has(&deps.storage, (expires_at, 0)));
+    assert!(!TRANSFER_EXPIRIES.has(&deps.storage, (expires_at, 1)));
+}
+
+// Legacy deployment holding `balance` usei, migrated
+fn migrated_holding(balance: u128) -> TestDeps {
+    let mut deps = mock_dependencies();
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, coins(balance, "usei"));
+    deps.storage.set(LEGACY_STATE_KEY, &legacy_blob());
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    deps
+}
+
+#[test]
+fn migrated_fees_can_be_withdrawn() {
+    let mut deps = migrated_holding(1000);
+    assert_eq!(ACCRUED_FEES.load(&deps.storage, "usei").unwrap(), Uint128::new(30));
+    let paid: Uint128 = query_data(&deps, QueryMsg::GetFeesPaidBy { sender: SENDER.to_string() });
+    assert_eq!(paid, Uint128::new(30_000_000_000_000));
+
+    let withdraw = ExecuteMsg::WithdrawFees { amount: Uint128::new(30), to: None, denom: None };
+    let response = exec(&mut deps, ADMIN, &[], withdraw).unwrap();
+    assert_eq!(sent_to(&response), vec![(ADMIN.to_string(), coins(30, "usei"))]);
+    assert_eq!(ACCRUED_FEES.load(&deps.storage, "usei").unwrap(), Uint128::zero());
+}
+
+#[test]
+fn migrated_fees_are_capped_at_the_held_balance() {
+    // Part of the legacy fees was already withdrawn before the upgrade
+    let mut deps = migrated_holding(25);
+    assert_eq!(ACCRUED_FEES.load(&deps.storage, "usei").unwrap(), Uint128::new(25));
+
+    let withdraw = |amount| ExecuteMsg::WithdrawFees { amount: Uint128::new(amount), to: None, denom: None };
+    let err = exec(&mut deps, ADMIN, &[], withdraw(26)).unwrap_err();
+    assert!(matches!(err, ContractError::InsufficientFunds {}));
+    exec(&mut deps, ADMIN, &[], withdraw(25)).unwrap();
+}
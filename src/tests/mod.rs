@@ -0,0 +1,176 @@
+// Unit tests for the contract entry points, grouped by feature area
+
+mod config;
+mod fees;
+mod migrate;
+mod multitest;
+mod proofs;
+mod queries;
+mod record;
+mod storage;
+mod treasury;
+
+use super::*;
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage};
+use cosmwasm_std::{from_json, CosmosMsg, OwnedDeps};
+use k256::ecdsa::signature::hazmat::PrehashSigner;
+use k256::ecdsa::{Signature, SigningKey};
+use serde::de::DeserializeOwned;
+
+const ADMIN: &str = "admin";
+const SENDER: &str = "sender";
+const RECIPIENT: &str = "recipient";
+
+type TestDeps = OwnedDeps<MockStorage, MockApi, MockQuerier>;
+
+// Instantiate message with a 1% fee and every optional setting left at its default
+fn instantiate_msg() -> InstantiateMsg {
+    InstantiateMsg {
+        fee_percentage: Uint128::new(100),
+        event_namespace: None,
+        fee_recipient: None,
+        allow_rerecord: None,
+        name_service: None,
+        max_ttl_seconds: None,
+        fee_increase_delay: None,
+        accepted_denoms: None,
+        initial_transfers: None,
+        features: None,
+    }
+}
+
+// Fresh contract instantiated by ADMIN, with SENDER trusted to record without a proof
+fn setup() -> TestDeps {
+    setup_with(instantiate_msg())
+}
+
+fn setup_with(msg: InstantiateMsg) -> TestDeps {
+    let mut deps = mock_dependencies();
+    instantiate(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    exec(&mut deps, ADMIN, &[], policy_trusting(&[SENDER])).unwrap();
+    deps
+}
+
+// Verification policy letting `senders` record without a proof
+fn policy_trusting(senders: &[&str]) -> ExecuteMsg {
+    ExecuteMsg::SetVerificationPolicy {
+        trusted_senders: senders.iter().map(|sender| sender.to_string()).collect(),
+        trusted_denoms: vec![],
+    }
+}
+
+// mock_env with the block clock moved `seconds` forward
+fn env_after(seconds: u64) -> Env {
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(seconds);
+    env
+}
+
+fn exec(deps: &mut TestDeps, sender: &str, funds: &[Coin], msg: ExecuteMsg) -> Result<Response, ContractError> {
+    exec_at(deps, mock_env(), sender, funds, msg)
+}
+
+fn exec_at(
+    deps: &mut TestDeps,
+    env: Env,
+    sender: &str,
+    funds: &[Coin],
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    execute(deps.as_mut(), env, mock_info(sender, funds), msg)
+}
+
+// RecordTransfer with no proof and every optional field unset
+fn record_msg(file_hash: &str, recipient: &str) -> ExecuteMsg {
+    ExecuteMsg::RecordTransfer {
+        file_hash: file_hash.to_string(),
+        recipient: recipient.to_string(),
+        zk_proof: vec![],
+        ttl_seconds: None,
+        metadata_commitment: None,
+        proved_height: None,
+        wrapped_key: None,
+        file_size: None,
+        metadata_signature: None,
+        coupon: None,
+        visible_at: None,
+        memo: None,
+    }
+}
+
+// BatchRecordTransfer entry with no proof and every optional field unset
+fn batch_item(file_hash: &str, recipient: &str, amount: u128) -> BatchTransferItem {
+    BatchTransferItem {
+        file_hash: file_hash.to_string(),
+        recipient: recipient.to_string(),
+        zk_proof: vec![],
+        ttl_seconds: None,
+        metadata_commitment: None,
+        proved_height: None,
+        wrapped_key: None,
+        file_size: None,
+        metadata_signature: None,
+        coupon: None,
+        visible_at: None,
+        memo: None,
+        amount: Uint128::new(amount),
+    }
+}
+
+// Record as the trusted SENDER
+fn record(deps: &mut TestDeps, file_hash: &str, recipient: &str, funds: &[Coin]) -> Result<Response, ContractError> {
+    exec(deps, SENDER, funds, record_msg(file_hash, recipient))
+}
+
+// Run a query and unwrap its versioned envelope
+fn query_data<T: DeserializeOwned>(deps: &TestDeps, msg: QueryMsg) -> T {
+    query_data_at(deps, mock_env(), msg)
+}
+
+fn query_data_at<T: DeserializeOwned>(deps: &TestDeps, env: Env, msg: QueryMsg) -> T {
+    let versioned: Versioned<T> = from_json(query(deps.as_ref(), env, msg).unwrap()).unwrap();
+    versioned.data
+}
+
+// Latest transfer stored under a (file_hash, recipient) key, read straight from storage
+fn stored_transfer(deps: &TestDeps, file_hash: &str, recipient: &str) -> FileTransfer {
+    load_transfer(&deps.storage, file_hash, recipient).unwrap().unwrap()
+}
+
+// Bank sends in a response, as (to_address, amount)
+fn sent_to(response: &Response) -> Vec<(String, Vec<Coin>)> {
+    response
+        .messages
+        .iter()
+        .filter_map(|sub| match &sub.msg {
+            CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => Some((to_address.clone(), amount.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+// Value of the first event attribute named `key`, across all events in a response
+fn event_attr(response: &Response, key: &str) -> Option<String> {
+    response
+        .events
+        .iter()
+        .flat_map(|event| &event.attributes)
+        .find(|attr| attr.key == key)
+        .map(|attr| attr.value.clone())
+}
+
+// Deterministic secp256k1 key for signature tests
+fn signing_key(seed: u8) -> SigningKey {
+    SigningKey::from_slice(&[seed; 32]).unwrap()
+}
+
+// Compressed public key, as accepted by RegisterPubkey
+fn pubkey(key: &SigningKey) -> Binary {
+    Binary::from(key.verifying_key().to_encoded_point(true).as_bytes())
+}
+
+// 64-byte signature over the SHA-256 of `message`, the digest the contract verifies
+fn sign(key: &SigningKey, message: &[u8]) -> Binary {
+    let signature: Signature = key.sign_prehash(&Sha256::digest(message)).unwrap();
+    Binary::from(signature.to_bytes().as_slice())
+}
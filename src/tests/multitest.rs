@@ -0,0 +1,424 @@
+// Tests against neighbouring contracts and the bank module, run in cw-multi-test
+use super::*;
+use anyhow::{bail, Result as AnyResult};
+use cosmwasm_std::{coin, coins, to_json_binary, Api, BankQuery, BlockInfo, CustomQuery, Empty, Querier};
+use cw_multi_test::{
+    App, AppBuilder, AppResponse, Bank, BankKeeper, BankSudo, Contract, ContractWrapper, CosmosRouter, Executor, Module,
+};
+use schemars::JsonSchema;
+use std::fmt::Debug;
+
+fn contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(execute, instantiate, query).with_reply(reply))
+}
+
+// Contract that only answers queries, standing in for a name service, oracle or verifier
+fn stub<Q: DeserializeOwned + Clone + Debug + 'static>(query: fn(Deps, Env, Q) -> StdResult<Binary>) -> Box<dyn Contract<Empty>> {
+    let noop = |_: DepsMut, _: Env, _: MessageInfo, _: Empty| -> StdResult<Response> { Ok(Response::new()) };
+    Box::new(ContractWrapper::new(noop, noop, query))
+}
+
+fn instantiate_stub(app: &mut App, code: Box<dyn Contract<Empty>>, label: &str) -> Addr {
+    let code_id = app.store_code(code);
+    app.instantiate_contract(code_id, Addr::unchecked(ADMIN), &Empty {}, &[], label, None)
+        .unwrap()
+}
+
+// App running this contract, with SENDER trusted to record without a proof
+fn setup_app(app: &mut App) -> Addr {
+    let code_id = app.store_code(contract());
+    let contract = app
+        .instantiate_contract(code_id, Addr::unchecked(ADMIN), &instantiate_msg(), &[], "zk", None)
+        .unwrap();
+    app.execute_contract(Addr::unchecked(ADMIN), contract.clone(), &policy_trusting(&[SENDER]), &[])
+        .unwrap();
+    contract
+}
+
+fn app_query<T: DeserializeOwned>(app: &App, contract: &Addr, msg: &QueryMsg) -> T {
+    let versioned: Versioned<T> = app.wrap().query_wasm_smart(contract, msg).unwrap();
+    versioned.data
+}
+
+fn contract_error(err: anyhow::Error) -> ContractError {
+    err.downcast().unwrap()
+}
+
+// Resolves the single name "Alice.sei"; the mixed case keeps it from passing as an address
+fn resolve_alice(_: Deps, _: Env, msg: NameServiceQueryMsg) -> StdResult<Binary> {
+    let NameServiceQueryMsg::ResolveRecord { name } = msg;
+    to_json_binary(&ResolveRecordResponse {
+        address: (name == "Alice.sei").then(|| "alice".to_string()),
+    })
+}
+
+#[test]
+fn recipient_name_is_resolved_through_the_name_service() {
+    let mut app = App::default();
+    let contract = setup_app(&mut app);
+    let name_service = instantiate_stub(&mut app, stub(resolve_alice), "names");
+    let set = ExecuteMsg::SetNameService { address: Some(name_service.to_string()) };
+    app.execute_contract(Addr::unchecked(ADMIN), contract.clone(), &set, &[]).unwrap();
+
+    app.execute_contract(Addr::unchecked(SENDER), contract.clone(), &record_msg("file", "Alice.sei"), &[])
+        .unwrap();
+
+    let transfer: Option<FileTransfer> = app_query(&app, &contract, &QueryMsg::VerifyTransferDetailed {
+        file_hash: "file".to_string(),
+        recipient: "alice".to_string(),
+    });
+    assert_eq!(transfer.unwrap().recipient, "alice");
+}
+
+#[test]
+fn unknown_name_is_unresolvable() {
+    let mut app = App::default();
+    let contract = setup_app(&mut app);
+    let name_service = instantiate_stub(&mut app, stub(resolve_alice), "names");
+    let set = ExecuteMsg::SetNameService { address: Some(name_service.to_string()) };
+    app.execute_contract(Addr::unchecked(ADMIN), contract.clone(), &set, &[]).unwrap();
+
+    let err = app
+        .execute_contract(Addr::unchecked(SENDER), contract, &record_msg("file", "Bob.sei"), &[])
+        .unwrap_err();
+    assert!(matches!(contract_error(err), ContractError::UnresolvableRecipient {}));
+}
+
+#[test]
+fn treasury_lists_accrued_fees_and_live_balance_per_denom() {
+    let mut app = App::new(|router, _, storage| {
+        let funds = vec![coin(10000, "uatom"), coin(10000, "usei")];
+        router.bank.init_balance(storage, &Addr::unchecked(SENDER), funds).unwrap();
+    });
+    let contract = setup_app(&mut app);
+    let denoms = ExecuteMsg::SetAcceptedDenoms { denoms: vec!["usei".to_string(), "uatom".to_string()] };
+    app.execute_contract(Addr::unchecked(ADMIN), contract.clone(), &denoms, &[]).unwrap();
+
+    app.execute_contract(Addr::unchecked(SENDER), contract.clone(), &record_msg("a", RECIPIENT), &coins(5000, "usei"))
+        .unwrap();
+    app.execute_contract(Addr::unchecked(SENDER), contract.clone(), &record_msg("b", RECIPIENT), &coins(2000, "uatom"))
+        .unwrap();
+
+    let treasury: Vec<TreasuryEntry> = app_query(&app, &contract, &QueryMsg::GetTreasury {});
+    let balance = |denom| app.wrap().query_balance(&contract, denom).unwrap().amount;
+    assert_eq!(treasury, vec![
+        TreasuryEntry { denom: "usei".to_string(), accrued: Uint128::new(50), contract_balance: balance("usei") },
+        TreasuryEntry { denom: "uatom".to_string(), accrued: Uint128::new(20), contract_balance: balance("uatom") },
+    ]);
+    assert!(!balance("uatom").is_zero());
+}
+
+fn warning(response: &AppResponse) -> Option<String> {
+    response
+        .events
+        .iter()
+        .flat_map(|event| &event.attributes)
+        .find(|attr| attr.key == "warning")
+        .map(|attr| attr.value.clone())
+}
+
+#[test]
+fn withdrawal_is_capped_by_the_live_balance_on_drift() {
+    let mut app = App::new(|router, _, storage| {
+        router.bank.init_balance(storage, &Addr::unchecked(SENDER), coins(10000, "usei")).unwrap();
+    });
+    let contract = setup_app(&mut app);
+    app.execute_contract(Addr::unchecked(SENDER), contract.clone(), &record_msg("a", RECIPIENT), &coins(10000, "usei"))
+        .unwrap();
+    let treasury: Vec<TreasuryEntry> = app_query(&app, &contract, &QueryMsg::GetTreasury {});
+    assert_eq!(treasury[0].accrued, Uint128::new(100));
+
+    // Funds leave the contract without the accounting knowing, e.g. a chain-level slash
+    app.init_modules(|router, _, storage| router.bank.init_balance(storage, &contract, coins(60, "usei")))
+        .unwrap();
+
+    let withdraw = |amount| ExecuteMsg::WithdrawFees { amount: Uint128::new(amount), to: None, denom: None };
+    let err = app.execute_contract(Addr::unchecked(ADMIN), contract.clone(), &withdraw(100), &[]).unwrap_err();
+    assert!(matches!(contract_error(err), ContractError::InsufficientFunds {}));
+
+    let response = app.execute_contract(Addr::unchecked(ADMIN), contract.clone(), &withdraw(50), &[]).unwrap();
+    assert_eq!(warning(&response).as_deref(), Some("accounting_drift:40"));
+    assert_eq!(app.wrap().query_balance(ADMIN, "usei").unwrap().amount, Uint128::new(50));
+}
+
+#[test]
+fn withdrawal_without_drift_carries_no_warning() {
+    let mut app = App::new(|router, _, storage| {
+        router.bank.init_balance(storage, &Addr::unchecked(SENDER), coins(10000, "usei")).unwrap();
+    });
+    let contract = setup_app(&mut app);
+    app.execute_contract(Addr::unchecked(SENDER), contract.clone(), &record_msg("a", RECIPIENT), &coins(10000, "usei"))
+        .unwrap();
+
+    let withdraw = ExecuteMsg::WithdrawFees { amount: Uint128::new(100), to: None, denom: None };
+    let response = app.execute_contract(Addr::unchecked(ADMIN), contract, &withdraw, &[]).unwrap();
+    assert_eq!(warning(&response), None);
+}
+
+// Prices one usei at 0.25 uusd; other pairs are unknown
+fn fixed_price(_: Deps, _: Env, msg: PriceOracleQueryMsg) -> StdResult<Binary> {
+    let PriceOracleQueryMsg::Price { base_denom, quote_denom } = msg;
+    if (base_denom.as_str(), quote_denom.as_str()) != ("usei", "uusd") {
+        return Err(StdError::not_found("price"));
+    }
+    to_json_binary(&PriceResponse { rate: Decimal::percent(25) })
+}
+
+#[test]
+fn fee_is_quoted_in_the_display_denom_at_the_oracle_rate() {
+    let mut app = App::default();
+    let contract = setup_app(&mut app);
+    let oracle = instantiate_stub(&mut app, stub(fixed_price), "oracle");
+    let set = ExecuteMsg::SetPriceOracle { address: Some(oracle.to_string()) };
+    app.execute_contract(Addr::unchecked(ADMIN), contract.clone(), &set, &[]).unwrap();
+
+    let quote = |display_denom: &str| {
+        app.wrap().query_wasm_smart::<Versioned<Coin>>(&contract, &QueryMsg::QuoteFeeInDisplay {
+            amount: Uint128::new(100_000),
+            display_denom: display_denom.to_string(),
+        })
+    };
+    // 1% of 100000 usei is 1000 usei, at 0.25 uusd each
+    assert_eq!(quote("uusd").unwrap().data, coin(250, "uusd"));
+    assert!(quote("ueur").is_err());
+}
+
+#[test]
+fn display_quote_needs_an_oracle() {
+    let mut app = App::default();
+    let contract = setup_app(&mut app);
+    let msg = QueryMsg::QuoteFeeInDisplay { amount: Uint128::new(100), display_denom: "uusd".to_string() };
+    assert!(app.wrap().query_wasm_smart::<Versioned<Coin>>(&contract, &msg).is_err());
+}
+
+// Accepts exactly the proof bytes "valid", and only for the file hash "file"
+fn accept_valid(_: Deps, _: Env, msg: VerifierQueryMsg) -> StdResult<Binary> {
+    let VerifierQueryMsg::VerifyProof { proof, file_hash, .. } = msg;
+    to_json_binary(&VerifyProofResponse { valid: proof.as_slice() == b"valid" && file_hash == "file" })
+}
+
+fn proven_record(app: &mut App, contract: &Addr, recipient: &str, proof: &[u8]) -> AppResponse {
+    let mut msg = record_msg("file", recipient);
+    if let ExecuteMsg::RecordTransfer { zk_proof, .. } = &mut msg {
+        *zk_proof = proof.to_vec();
+    }
+    app.execute_contract(Addr::unchecked("prover"), contract.clone(), &msg, &[]).unwrap()
+}
+
+#[test]
+fn verifier_contract_answer_decides_the_record() {
+    let mut app = App::default();
+    let contract = setup_app(&mut app);
+    let verifier = instantiate_stub(&mut app, stub(accept_valid), "verifier");
+    let set = ExecuteMsg::SetVerifierContract { address: Some(verifier.to_string()) };
+    app.execute_contract(Addr::unchecked(ADMIN), contract.clone(), &set, &[]).unwrap();
+
+    proven_record(&mut app, &contract, "alice", b"valid");
+    let rejected = proven_record(&mut app, &contract, "bob", b"forged");
+    assert!(rejected.events.iter().any(|event| event.attributes.iter().any(|a| a.value == "reject_proof")));
+
+    let verified = |recipient: &str| -> bool {
+        app_query(&app, &contract, &QueryMsg::VerifyTransfer { file_hash: "file".to_string(), recipient: recipient.to_string() })
+    };
+    assert!(verified("alice"));
+    assert!(!verified("bob"));
+    let stats: ProofStats = app_query(&app, &contract, &QueryMsg::GetProofStats {});
+    assert_eq!((stats.proofs_verified, stats.proofs_rejected), (1, 1));
+}
+
+#[test]
+fn rejected_proof_reverts_the_batch_and_its_verified_counts() {
+    let mut app = App::default();
+    let contract = setup_app(&mut app);
+    let verifier = instantiate_stub(&mut app, stub(accept_valid), "verifier");
+    let set = ExecuteMsg::SetVerifierContract { address: Some(verifier.to_string()) };
+    app.execute_contract(Addr::unchecked(ADMIN), contract.clone(), &set, &[]).unwrap();
+
+    // The verifier only accepts proofs for "file", so the second item is rejected
+    let mut valid = batch_item("file", "alice", 0);
+    valid.zk_proof = b"valid".to_vec();
+    let mut forged = batch_item("other", "alice", 0);
+    forged.zk_proof = b"valid".to_vec();
+    let batch = ExecuteMsg::BatchRecordTransfer { transfers: vec![valid, forged] };
+    let err = app.execute_contract(Addr::unchecked("prover"), contract.clone(), &batch, &[]).unwrap_err();
+    assert!(matches!(contract_error(err), ContractError::InvalidProof {}));
+
+    let verified: bool = app_query(&app, &contract, &QueryMsg::VerifyTransfer {
+        file_hash: "file".to_string(),
+        recipient: "alice".to_string(),
+    });
+    assert!(!verified);
+    let stats: ProofStats = app_query(&app, &contract, &QueryMsg::GetProofStats {});
+    assert_eq!((stats.proofs_verified, stats.proofs_rejected), (0, 0));
+}
+
+#[test]
+fn unreachable_verifier_fails_the_record() {
+    let mut app = App::default();
+    let contract = setup_app(&mut app);
+    let set = ExecuteMsg::SetVerifierContract { address: Some("nothing".to_string()) };
+    app.execute_contract(Addr::unchecked(ADMIN), contract.clone(), &set, &[]).unwrap();
+
+    let mut msg = record_msg("file", "alice");
+    if let ExecuteMsg::RecordTransfer { zk_proof, .. } = &mut msg {
+        *zk_proof = b"valid".to_vec();
+    }
+    app.execute_contract(Addr::unchecked("prover"), contract, &msg, &[]).unwrap_err();
+}
+
+// Bank that refuses to send anything to BLOCKED, like a chain-level blocked address
+const BLOCKED: &str = "blocked";
+
+struct BlockingBank(BankKeeper);
+
+impl Module for BlockingBank {
+    type ExecT = BankMsg;
+    type QueryT = BankQuery;
+    type SudoT = BankSudo;
+
+    fn execute<ExecC, QueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        sender: Addr,
+        msg: BankMsg,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: Debug + Clone + PartialEq + JsonSchema + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        if let BankMsg::Send { to_address, .. } = &msg {
+            if to_address == BLOCKED {
+                bail!("{} is blocked from receiving funds", to_address);
+            }
+        }
+        self.0.execute(api, storage, router, block, sender, msg)
+    }
+
+    fn sudo<ExecC, QueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        msg: BankSudo,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: Debug + Clone + PartialEq + JsonSchema + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        self.0.sudo(api, storage, router, block, msg)
+    }
+
+    fn query(
+        &self,
+        api: &dyn Api,
+        storage: &dyn Storage,
+        querier: &dyn Querier,
+        block: &BlockInfo,
+        request: BankQuery,
+    ) -> AnyResult<Binary> {
+        self.0.query(api, storage, querier, block, request)
+    }
+}
+
+impl Bank for BlockingBank {}
+
+// Escrows 3000 usei from `sender` across two recipients, optionally with a fallback
+// recipient configured, and returns the app with the deadline passed
+fn escrow_then_expire(sender: &str, fallback: Option<&str>) -> (App<BlockingBank>, Addr) {
+    let mut app = AppBuilder::new()
+        .with_bank(BlockingBank(BankKeeper::new()))
+        .build(|router, _, storage| {
+            router.bank.0.init_balance(storage, &Addr::unchecked(sender), coins(3000, "usei")).unwrap();
+        });
+    let code_id = app.store_code(contract());
+    let contract = app
+        .instantiate_contract(code_id, Addr::unchecked(ADMIN), &instantiate_msg(), &[], "zk", None)
+        .unwrap();
+    app.execute_contract(Addr::unchecked(ADMIN), contract.clone(), &policy_trusting(&[sender]), &[])
+        .unwrap();
+    if let Some(fallback) = fallback {
+        let set = ExecuteMsg::SetFallbackRecipient { address: Some(fallback.to_string()) };
+        app.execute_contract(Addr::unchecked(ADMIN), contract.clone(), &set, &[]).unwrap();
+    }
+
+    let deadline = app.block_info().time.seconds() + 1000;
+    let msg = ExecuteMsg::RecordMultiRecipient {
+        file_hash: "file".to_string(),
+        recipients: vec!["alice".to_string(), "bob".to_string()],
+        zk_proof: vec![],
+        proved_height: None,
+        escrow_deadline: Some(deadline),
+    };
+    app.execute_contract(Addr::unchecked(sender), contract.clone(), &msg, &coins(3000, "usei"))
+        .unwrap();
+    app.update_block(|block| block.time = block.time.plus_seconds(1000));
+    (app, contract)
+}
+
+fn reclaim(app: &mut App<BlockingBank>, contract: &Addr, sender: &str) -> AnyResult<AppResponse> {
+    let msg = ExecuteMsg::ReclaimEscrow { file_hash: "file".to_string() };
+    app.execute_contract(Addr::unchecked(sender), contract.clone(), &msg, &[])
+}
+
+#[test]
+fn failed_refund_goes_to_the_fallback_recipient() {
+    let (mut app, contract) = escrow_then_expire(BLOCKED, Some("fallback"));
+    let escrowed = app.wrap().query_balance(&contract, "usei").unwrap().amount;
+
+    reclaim(&mut app, &contract, BLOCKED).unwrap();
+
+    let balance = |address: &str| app.wrap().query_balance(address, "usei").unwrap().amount;
+    assert_eq!(balance(BLOCKED), Uint128::zero());
+    assert_eq!(balance("fallback"), Uint128::new(2970));
+    assert_eq!(balance(contract.as_str()), escrowed - Uint128::new(2970));
+}
+
+#[test]
+fn successful_refund_ignores_the_fallback_recipient() {
+    let (mut app, contract) = escrow_then_expire(SENDER, Some("fallback"));
+
+    reclaim(&mut app, &contract, SENDER).unwrap();
+
+    let balance = |address: &str| app.wrap().query_balance(address, "usei").unwrap().amount;
+    assert_eq!(balance(SENDER), Uint128::new(2970));
+    assert_eq!(balance("fallback"), Uint128::zero());
+}
+
+#[test]
+fn failed_refund_without_a_fallback_reverts_the_reclaim() {
+    let (mut app, contract) = escrow_then_expire(BLOCKED, None);
+    let escrowed = app.wrap().query_balance(&contract, "usei").unwrap().amount;
+
+    reclaim(&mut app, &contract, BLOCKED).unwrap_err();
+    assert_eq!(app.wrap().query_balance(&contract, "usei").unwrap().amount, escrowed);
+}
+
+#[test]
+fn contract_balances_list_every_funded_denom() {
+    let mut app = App::new(|router, _, storage| {
+        let funds = vec![coin(10000, "usei"), coin(5000, "uatom")];
+        router.bank.init_balance(storage, &Addr::unchecked(SENDER), funds).unwrap();
+    });
+    let contract = setup_app(&mut app);
+    let denoms = ExecuteMsg::SetAcceptedDenoms { denoms: vec!["usei".to_string(), "uatom".to_string()] };
+    app.execute_contract(Addr::unchecked(ADMIN), contract.clone(), &denoms, &[]).unwrap();
+
+    let sender = Addr::unchecked(SENDER);
+    app.execute_contract(sender.clone(), contract.clone(), &record_msg("a", RECIPIENT), &coins(10000, "usei"))
+        .unwrap();
+    app.execute_contract(sender, contract.clone(), &record_msg("b", RECIPIENT), &coins(5000, "uatom"))
+        .unwrap();
+    // A denom the contract never accepts still shows up
+    app.sudo(BankSudo::Mint { to_address: contract.to_string(), amount: coins(7, "ufoo") }.into())
+        .unwrap();
+
+    let balances: Vec<Coin> = app_query(&app, &contract, &QueryMsg::GetContractBalances {});
+    assert_eq!(balances, vec![coin(5000, "uatom"), coin(7, "ufoo"), coin(10000, "usei")]);
+    // The single-denom variant still reports usei alone
+    let usei: Uint128 = app_query(&app, &contract, &QueryMsg::GetContractBalance {});
+    assert_eq!(usei, Uint128::new(10000));
+}
@@ -0,0 +1,687 @@
+// Groth16 proofs against parameters generated once per test run
+use super::*;
+use bellman::groth16::{create_random_proof, generate_random_parameters, Parameters};
+use bellman::SynthesisError;
+use cosmwasm_std::{attr, coins};
+use std::sync::OnceLock;
+use zk_proof::{FileTransferCircuit, Proof};
+
+// Hex file hash and a recipient long enough to fill the 32-byte circuit inputs
+const FILE_HASH: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+const PROVEN_RECIPIENT: &str = "recipient0000000000000000000000000000001";
+const PROVER: &str = "prover";
+
+fn params() -> &'static Parameters<Bls12> {
+    static PARAMS: OnceLock<Parameters<Bls12>> = OnceLock::new();
+    PARAMS.get_or_init(|| {
+        let blank = FileTransferCircuit {
+            file_hash: None,
+            recipient: None,
+            secret: None,
+            metadata: None,
+            metadata_commitment: None,
+            block_height: None,
+            epoch: None,
+        };
+        generate_random_parameters::<Bls12, _, _>(blank, &mut OsRng).unwrap()
+    })
+}
+
+fn verifying_key() -> Vec<u8> {
+    let mut bytes = vec![];
+    params().vk.write(&mut bytes).unwrap();
+    bytes
+}
+
+fn input(bytes: &[u8]) -> [u8; 32] {
+    bytes[..32].try_into().unwrap()
+}
+
+fn scalar(bytes: [u8; 32]) -> Scalar {
+    Scalar::from_bytes(&bytes).unwrap()
+}
+
+// Public and private inputs of one proof; `commitment` defaults to the one the
+// metadata and secret imply
+struct Witness {
+    file_hash: [u8; 32],
+    recipient: [u8; 32],
+    metadata: u64,
+    commitment: Option<[u8; 32]>,
+    block_height: u64,
+    epoch: u64,
+}
+
+impl Witness {
+    fn new(file_hash: &str, recipient: &[u8]) -> Self {
+        Witness {
+            file_hash: input(file_hash.as_bytes()),
+            recipient: input(recipient),
+            metadata: 42,
+            commitment: None,
+            block_height: 0,
+            epoch: 0,
+        }
+    }
+
+    fn secret(&self) -> Scalar {
+        scalar(self.file_hash) + scalar(self.recipient)
+    }
+
+    fn commitment(&self) -> [u8; 32] {
+        self.commitment
+            .unwrap_or_else(|| (Scalar::from(self.metadata) * self.secret()).to_bytes())
+    }
+
+    fn circuit(&self) -> FileTransferCircuit {
+        FileTransferCircuit {
+            file_hash: Some(self.file_hash),
+            recipient: Some(self.recipient),
+            secret: Some(self.secret().to_bytes()),
+            metadata: Some(Scalar::from(self.metadata).to_bytes()),
+            metadata_commitment: Some(self.commitment()),
+            block_height: Some(self.block_height),
+            epoch: Some(self.epoch),
+        }
+    }
+
+    fn prove(&self) -> Vec<u8> {
+        let proof = create_random_proof(self.circuit(), params(), &mut OsRng).unwrap();
+        let mut bytes = vec![];
+        proof.write(&mut bytes).unwrap();
+        bytes
+    }
+}
+
+// Check a proof directly against the shared verifying key
+fn verifies(proof: Vec<u8>, file_hash: &str, recipient: &[u8], commitment: &[u8; 32], height: u64, epoch: u64) -> bool {
+    Proof(proof).verify(file_hash.as_bytes(), recipient, commitment, height, epoch, Some(&verifying_key()))
+}
+
+// Contract with the shared verifying key committed and no trusted senders
+fn setup_verifying() -> TestDeps {
+    setup_verifying_with(instantiate_msg())
+}
+
+fn setup_verifying_with(msg: InstantiateMsg) -> TestDeps {
+    let mut deps = mock_dependencies();
+    instantiate(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    let set = ExecuteMsg::SetVerifyingKey { verifying_key: Binary::from(verifying_key()) };
+    exec(&mut deps, ADMIN, &[], set).unwrap();
+    deps
+}
+
+// Whether a record was turned away for its proof rather than recorded
+fn rejected(response: Response) -> bool {
+    response.attributes.contains(&attr("action", "reject_proof"))
+}
+
+fn proven_record_msg(proof: Vec<u8>, commitment: Option<[u8; 32]>, proved_height: Option<u64>) -> ExecuteMsg {
+    let mut msg = record_msg(FILE_HASH, PROVEN_RECIPIENT);
+    if let ExecuteMsg::RecordTransfer {
+        zk_proof,
+        metadata_commitment,
+        proved_height: height,
+        ..
+    } = &mut msg
+    {
+        *zk_proof = proof;
+        *metadata_commitment = commitment.map(|c| Binary::from(c.to_vec()));
+        *height = proved_height;
+    }
+    msg
+}
+
+#[test]
+fn proof_with_consistent_metadata_commitment_verifies() {
+    let witness = Witness::new(FILE_HASH, PROVEN_RECIPIENT.as_bytes());
+    let commitment = witness.commitment();
+
+    assert!(verifies(witness.prove(), FILE_HASH, PROVEN_RECIPIENT.as_bytes(), &commitment, 0, 0));
+}
+
+#[test]
+fn proof_with_inconsistent_metadata_commitment_is_rejected() {
+    let mut witness = Witness::new(FILE_HASH, PROVEN_RECIPIENT.as_bytes());
+    let inconsistent = Scalar::from(7u64).to_bytes();
+    witness.commitment = Some(inconsistent);
+
+    assert!(!verifies(witness.prove(), FILE_HASH, PROVEN_RECIPIENT.as_bytes(), &inconsistent, 0, 0));
+}
+
+#[test]
+fn proof_is_bound_to_the_public_commitment() {
+    let witness = Witness::new(FILE_HASH, PROVEN_RECIPIENT.as_bytes());
+    let other = Scalar::from(7u64).to_bytes();
+
+    assert!(!verifies(witness.prove(), FILE_HASH, PROVEN_RECIPIENT.as_bytes(), &other, 0, 0));
+}
+
+#[test]
+fn record_verifies_the_metadata_commitment() {
+    let mut deps = setup_verifying();
+    let witness = Witness::new(FILE_HASH, PROVEN_RECIPIENT.as_bytes());
+    let proof = witness.prove();
+
+    let wrong = Scalar::from(7u64).to_bytes();
+    let response = exec(&mut deps, PROVER, &[], proven_record_msg(proof.clone(), Some(wrong), None)).unwrap();
+    assert!(rejected(response));
+    assert!(load_transfer(&deps.storage, FILE_HASH, PROVEN_RECIPIENT).unwrap().is_none());
+
+    let msg = proven_record_msg(proof, Some(witness.commitment()), None);
+    exec(&mut deps, PROVER, &[], msg).unwrap();
+    let transfer = stored_transfer(&deps, FILE_HASH, PROVEN_RECIPIENT);
+    assert_eq!(transfer.metadata_commitment, Some(Binary::from(witness.commitment().to_vec())));
+}
+
+#[test]
+fn metadata_commitment_must_be_32_bytes() {
+    let mut deps = setup_verifying();
+    let mut msg = proven_record_msg(vec![], None, None);
+    if let ExecuteMsg::RecordTransfer { metadata_commitment, .. } = &mut msg {
+        *metadata_commitment = Some(Binary::from(vec![1u8; 31]));
+    }
+
+    let err = exec(&mut deps, PROVER, &[], msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidMetadataCommitment {}));
+}
+
+fn reverify(deps: &TestDeps, proof: Vec<u8>) -> bool {
+    query_data(deps, QueryMsg::ReverifyTransfer {
+        file_hash: FILE_HASH.to_string(),
+        recipient: PROVEN_RECIPIENT.to_string(),
+        zk_proof: Binary::from(proof),
+    })
+}
+
+#[test]
+fn reverify_accepts_the_recorded_proof() {
+    let mut deps = setup_verifying();
+    let witness = Witness::new(FILE_HASH, PROVEN_RECIPIENT.as_bytes());
+    let proof = witness.prove();
+    let msg = proven_record_msg(proof.clone(), Some(witness.commitment()), None);
+    exec(&mut deps, PROVER, &[], msg).unwrap();
+
+    assert!(reverify(&deps, proof));
+}
+
+#[test]
+fn reverify_rejects_a_proof_not_matching_the_stored_hash() {
+    let mut deps = setup_verifying();
+    let witness = Witness::new(FILE_HASH, PROVEN_RECIPIENT.as_bytes());
+    let msg = proven_record_msg(witness.prove(), Some(witness.commitment()), None);
+    exec(&mut deps, PROVER, &[], msg).unwrap();
+
+    // Valid for the same inputs, but not the proof that was recorded
+    assert!(!reverify(&deps, witness.prove()));
+}
+
+#[test]
+fn reverify_of_unknown_transfer_is_not_found() {
+    let deps = setup_verifying();
+    let msg = QueryMsg::ReverifyTransfer {
+        file_hash: FILE_HASH.to_string(),
+        recipient: PROVEN_RECIPIENT.to_string(),
+        zk_proof: Binary::default(),
+    };
+    assert!(query(deps.as_ref(), mock_env(), msg).is_err());
+}
+
+#[test]
+fn proof_is_bound_to_the_proved_height() {
+    let mut witness = Witness::new(FILE_HASH, PROVEN_RECIPIENT.as_bytes());
+    witness.block_height = 12340;
+    let proof = witness.prove();
+    let commitment = witness.commitment();
+
+    assert!(verifies(proof.clone(), FILE_HASH, PROVEN_RECIPIENT.as_bytes(), &commitment, 12340, 0));
+    assert!(!verifies(proof, FILE_HASH, PROVEN_RECIPIENT.as_bytes(), &commitment, 12341, 0));
+}
+
+#[test]
+fn record_accepts_a_proof_inside_the_freshness_window() {
+    let mut deps = setup_verifying();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetProofFreshnessWindow { blocks: Some(10) }).unwrap();
+    let mut witness = Witness::new(FILE_HASH, PROVEN_RECIPIENT.as_bytes());
+    witness.block_height = mock_env().block.height - 10;
+
+    let msg = proven_record_msg(witness.prove(), Some(witness.commitment()), Some(witness.block_height));
+    exec(&mut deps, PROVER, &[], msg).unwrap();
+    assert_eq!(stored_transfer(&deps, FILE_HASH, PROVEN_RECIPIENT).proved_height, witness.block_height);
+}
+
+#[test]
+fn record_rejects_a_proof_outside_the_freshness_window() {
+    let mut deps = setup_verifying();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetProofFreshnessWindow { blocks: Some(10) }).unwrap();
+    let mut witness = Witness::new(FILE_HASH, PROVEN_RECIPIENT.as_bytes());
+    witness.block_height = mock_env().block.height - 11;
+
+    let msg = proven_record_msg(witness.prove(), Some(witness.commitment()), Some(witness.block_height));
+    let err = exec(&mut deps, PROVER, &[], msg).unwrap_err();
+    assert!(matches!(err, ContractError::StaleProof {}));
+}
+
+fn proof_cache(response: &Response) -> String {
+    event_attr(response, "proof_cache").unwrap()
+}
+
+// Revoke the proven transfer so its key can be recorded again
+fn revoke_proven(deps: &mut TestDeps) {
+    let msg = ExecuteMsg::RevokeTransfer {
+        file_hash: FILE_HASH.to_string(),
+        recipient: PROVEN_RECIPIENT.to_string(),
+    };
+    exec(deps, PROVER, &[], msg).unwrap();
+}
+
+#[test]
+fn identical_proof_is_served_from_the_cache() {
+    let mut deps = setup_verifying_with(InstantiateMsg {
+        allow_rerecord: Some(true),
+        ..instantiate_msg()
+    });
+    let witness = Witness::new(FILE_HASH, PROVEN_RECIPIENT.as_bytes());
+    let proof = witness.prove();
+    let msg = proven_record_msg(proof, Some(witness.commitment()), None);
+
+    let first = exec(&mut deps, PROVER, &[], msg.clone()).unwrap();
+    assert_eq!(proof_cache(&first), "miss");
+    revoke_proven(&mut deps);
+    let second = exec(&mut deps, PROVER, &[], msg).unwrap();
+    assert_eq!(proof_cache(&second), "hit");
+
+    // A fresh proof of the same statement is verified in full
+    revoke_proven(&mut deps);
+    let fresh = proven_record_msg(witness.prove(), Some(witness.commitment()), None);
+    assert_eq!(proof_cache(&exec(&mut deps, PROVER, &[], fresh).unwrap()), "miss");
+}
+
+#[test]
+fn cached_proof_is_still_bound_to_its_public_inputs() {
+    let mut deps = setup_verifying_with(InstantiateMsg {
+        allow_rerecord: Some(true),
+        ..instantiate_msg()
+    });
+    let witness = Witness::new(FILE_HASH, PROVEN_RECIPIENT.as_bytes());
+    let proof = witness.prove();
+    exec(&mut deps, PROVER, &[], proven_record_msg(proof.clone(), Some(witness.commitment()), None)).unwrap();
+    revoke_proven(&mut deps);
+
+    let other = Scalar::from(7u64).to_bytes();
+    let response = exec(&mut deps, PROVER, &[], proven_record_msg(proof, Some(other), None)).unwrap();
+    assert!(rejected(response));
+}
+
+#[test]
+fn cached_proof_is_verified_again_after_a_key_rotation() {
+    let mut deps = setup_verifying_with(InstantiateMsg {
+        allow_rerecord: Some(true),
+        ..instantiate_msg()
+    });
+    let witness = Witness::new(FILE_HASH, PROVEN_RECIPIENT.as_bytes());
+    let msg = proven_record_msg(witness.prove(), Some(witness.commitment()), None);
+    exec(&mut deps, PROVER, &[], msg.clone()).unwrap();
+    revoke_proven(&mut deps);
+
+    // Parameters generated afresh give a key the old proof doesn't verify under
+    let blank = FileTransferCircuit {
+        file_hash: None,
+        recipient: None,
+        secret: None,
+        metadata: None,
+        metadata_commitment: None,
+        block_height: None,
+        epoch: None,
+    };
+    let rotated: Parameters<Bls12> = generate_random_parameters(blank, &mut OsRng).unwrap();
+    let mut verifying_key = vec![];
+    rotated.vk.write(&mut verifying_key).unwrap();
+    let set = ExecuteMsg::SetVerifyingKey { verifying_key: Binary::from(verifying_key) };
+    exec(&mut deps, ADMIN, &[], set).unwrap();
+
+    assert!(rejected(exec(&mut deps, PROVER, &[], msg).unwrap()));
+}
+
+// Proof for FILE_HASH committing to the sorted recipient set, with no metadata
+fn prove_recipient_set(recipients: &[&str]) -> Vec<u8> {
+    let mut sorted: Vec<String> = recipients.iter().map(|r| r.to_string()).collect();
+    sorted.sort();
+    let mut witness = Witness::new(FILE_HASH, &recipient_set_hash(&sorted));
+    witness.metadata = 0;
+    witness.prove()
+}
+
+fn multi_recipient_msg(recipients: &[&str], zk_proof: Vec<u8>) -> ExecuteMsg {
+    ExecuteMsg::RecordMultiRecipient {
+        file_hash: FILE_HASH.to_string(),
+        recipients: recipients.iter().map(|r| r.to_string()).collect(),
+        zk_proof,
+        proved_height: None,
+        escrow_deadline: None,
+    }
+}
+
+#[test]
+fn one_proof_records_every_recipient_in_the_set() {
+    let mut deps = setup_verifying();
+    let proof = prove_recipient_set(&["alice", "bob"]);
+
+    // The set is sorted before hashing, so submission order doesn't matter
+    exec(&mut deps, PROVER, &[], multi_recipient_msg(&["bob", "alice"], proof)).unwrap();
+    for recipient in ["alice", "bob"] {
+        let transfer = stored_transfer(&deps, FILE_HASH, recipient);
+        assert_eq!(transfer.proof_hash, stored_transfer(&deps, FILE_HASH, "alice").proof_hash);
+        assert!(transfer.recipient_set_hash.is_some());
+    }
+}
+
+#[test]
+fn proof_for_another_recipient_set_is_rejected() {
+    let mut deps = setup_verifying();
+    let proof = prove_recipient_set(&["alice", "bob"]);
+
+    let response = exec(&mut deps, PROVER, &[], multi_recipient_msg(&["alice", "carol"], proof)).unwrap();
+    assert!(rejected(response));
+    assert!(load_transfer(&deps.storage, FILE_HASH, "alice").unwrap().is_none());
+}
+
+#[test]
+fn only_a_hash_of_the_proof_and_inputs_is_stored() {
+    let mut deps = setup_verifying();
+    let witness = Witness::new(FILE_HASH, PROVEN_RECIPIENT.as_bytes());
+    let proof = witness.prove();
+    exec(&mut deps, PROVER, &[], proven_record_msg(proof.clone(), Some(witness.commitment()), None)).unwrap();
+
+    let inputs = ProofInputs {
+        file_hash: FILE_HASH,
+        recipient: PROVEN_RECIPIENT.as_bytes(),
+        metadata_commitment: witness.commitment(),
+        proved_height: 0,
+        epoch: 0,
+    };
+    let stored = stored_transfer(&deps, FILE_HASH, PROVEN_RECIPIENT).proof_hash;
+    assert_eq!(stored.len(), 32);
+    assert_eq!(stored, Binary::from(proof_hash(&proof, &inputs)));
+
+    let mut modified = proof.clone();
+    modified[0] ^= 1;
+    assert_ne!(stored, Binary::from(proof_hash(&modified, &inputs)));
+    assert!(reverify(&deps, proof));
+    assert!(!reverify(&deps, modified));
+}
+
+#[test]
+fn trusted_sender_skips_verification() {
+    let mut deps = setup_verifying();
+    exec(&mut deps, ADMIN, &[], policy_trusting(&[PROVER])).unwrap();
+
+    let response = exec(&mut deps, PROVER, &[], proven_record_msg(vec![], None, None)).unwrap();
+    assert_eq!(proof_cache(&response), "trusted");
+}
+
+#[test]
+fn trusted_denom_skips_verification() {
+    let mut deps = setup_verifying();
+    let policy = ExecuteMsg::SetVerificationPolicy { trusted_senders: vec![], trusted_denoms: vec!["usei".to_string()] };
+    let response = exec(&mut deps, ADMIN, &[], policy).unwrap();
+    assert_eq!(event_attr(&response, "trusted_denoms").as_deref(), Some("usei"));
+
+    let response = exec(&mut deps, PROVER, &coins(100, "usei"), proven_record_msg(vec![], None, None)).unwrap();
+    assert_eq!(proof_cache(&response), "trusted");
+}
+
+#[test]
+fn untrusted_records_require_a_proof() {
+    let mut deps = setup_verifying();
+    let response = exec(&mut deps, ADMIN, &[], policy_trusting(&[SENDER])).unwrap();
+    assert_eq!(event_attr(&response, "trusted_denoms").as_deref(), Some("none"));
+
+    // A proof for some other recipient doesn't cover this record
+    let other = Witness::new(FILE_HASH, b"someone0000000000000000000000000000000001");
+    let response = exec(&mut deps, PROVER, &[], proven_record_msg(other.prove(), Some(other.commitment()), None)).unwrap();
+    assert!(rejected(response));
+
+    let witness = Witness::new(FILE_HASH, PROVEN_RECIPIENT.as_bytes());
+    let response = exec(&mut deps, PROVER, &[], proven_record_msg(witness.prove(), Some(witness.commitment()), None)).unwrap();
+    assert_eq!(proof_cache(&response), "miss");
+}
+
+// Proven record of FILE_HASH to a recipient numbered `n`, submitted `blocks` after mock_env
+fn exec_proven_at(deps: &mut TestDeps, sender: &str, n: u8, blocks: u64) -> Result<Response, ContractError> {
+    let recipient = format!("recipient{}{:030}", n, 0);
+    let witness = Witness::new(FILE_HASH, recipient.as_bytes());
+    let mut msg = record_msg(FILE_HASH, &recipient);
+    if let ExecuteMsg::RecordTransfer { zk_proof, metadata_commitment, .. } = &mut msg {
+        *zk_proof = witness.prove();
+        *metadata_commitment = Some(Binary::from(witness.commitment().to_vec()));
+    }
+    let mut env = mock_env();
+    env.block.height += blocks;
+    exec_at(deps, env, sender, &[], msg)
+}
+
+#[test]
+fn verifying_records_are_throttled_globally() {
+    let mut deps = setup_verifying();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetMinVerifyInterval { blocks: 5, per_sender: false }).unwrap();
+
+    exec_proven_at(&mut deps, PROVER, 1, 0).unwrap();
+    let err = exec_proven_at(&mut deps, "other", 2, 4).unwrap_err();
+    assert!(matches!(err, ContractError::TooSoon {}));
+    exec_proven_at(&mut deps, "other", 2, 5).unwrap();
+}
+
+#[test]
+fn verifying_records_are_throttled_per_sender() {
+    let mut deps = setup_verifying();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetMinVerifyInterval { blocks: 5, per_sender: true }).unwrap();
+
+    exec_proven_at(&mut deps, PROVER, 1, 0).unwrap();
+    exec_proven_at(&mut deps, "other", 2, 1).unwrap();
+    let err = exec_proven_at(&mut deps, PROVER, 3, 1).unwrap_err();
+    assert!(matches!(err, ContractError::TooSoon {}));
+    exec_proven_at(&mut deps, PROVER, 3, 5).unwrap();
+}
+
+#[test]
+fn rejected_record_leaves_no_throttle_entry() {
+    let wrong = Scalar::from(7u64).to_bytes();
+    for per_sender in [false, true] {
+        let mut deps = setup_verifying();
+        exec(&mut deps, ADMIN, &[], ExecuteMsg::SetMinVerifyInterval { blocks: 5, per_sender }).unwrap();
+        let witness = Witness::new(FILE_HASH, PROVEN_RECIPIENT.as_bytes());
+
+        let msg = proven_record_msg(witness.prove(), Some(wrong), None);
+        assert!(rejected(exec(&mut deps, PROVER, &[], msg).unwrap()));
+        assert!(!LAST_VERIFY_HEIGHT.exists(&deps.storage));
+        assert!(!SENDER_LAST_VERIFY_HEIGHT.has(&deps.storage, &Addr::unchecked(PROVER)));
+
+        // The sender's next record at the same height is not held back
+        let msg = proven_record_msg(witness.prove(), Some(witness.commitment()), None);
+        assert!(!rejected(exec(&mut deps, PROVER, &[], msg).unwrap()));
+    }
+}
+
+#[test]
+fn rejected_record_prunes_nothing() {
+    let mut deps = setup_verifying();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetAutoPrune { enabled: true, limit: 10 }).unwrap();
+    exec(&mut deps, ADMIN, &[], policy_trusting(&[SENDER])).unwrap();
+    let mut msg = record_msg("expiring", RECIPIENT);
+    if let ExecuteMsg::RecordTransfer { ttl_seconds, .. } = &mut msg {
+        *ttl_seconds = Some(100);
+    }
+    exec(&mut deps, SENDER, &[], msg).unwrap();
+
+    let msg = proven_record_msg(vec![0xff; 10], None, None);
+    assert!(rejected(exec_at(&mut deps, env_after(200), PROVER, &[], msg).unwrap()));
+    assert!(TRANSFER_KEYS.has(&deps.storage, ("expiring", RECIPIENT)));
+}
+
+fn proof_stats(deps: &TestDeps) -> (u64, u64) {
+    let stats: ProofStats = query_data(deps, QueryMsg::GetProofStats {});
+    (stats.proofs_verified, stats.proofs_rejected)
+}
+
+#[test]
+fn valid_and_invalid_records_bump_their_counters() {
+    let mut deps = setup_verifying();
+    let witness = Witness::new(FILE_HASH, PROVEN_RECIPIENT.as_bytes());
+    let wrong = Scalar::from(7u64).to_bytes();
+
+    // The rejected record doesn't revert: it is counted and its funds sent back
+    let response = exec(&mut deps, PROVER, &coins(1000, "usei"), proven_record_msg(witness.prove(), Some(wrong), None)).unwrap();
+    assert_eq!(sent_to(&response), vec![(PROVER.to_string(), coins(1000, "usei"))]);
+    assert_eq!(event_attr(&response, "proofs_rejected").as_deref(), Some("1"));
+    assert_eq!(proof_stats(&deps), (0, 1));
+
+    let msg = proven_record_msg(witness.prove(), Some(witness.commitment()), None);
+    exec(&mut deps, PROVER, &coins(1000, "usei"), msg).unwrap();
+    assert_eq!(proof_stats(&deps), (1, 1));
+}
+
+#[test]
+fn malformed_proof_bytes_are_rejected() {
+    let mut deps = setup_verifying();
+    for proof in [vec![], vec![0xff; 10]] {
+        assert!(rejected(exec(&mut deps, PROVER, &[], proven_record_msg(proof, None, None)).unwrap()));
+    }
+    assert_eq!(proof_stats(&deps), (0, 2));
+}
+
+#[test]
+fn invalid_proof_in_a_batch_aborts_it_uncounted() {
+    let mut deps = setup_verifying();
+    let mut item = batch_item(FILE_HASH, PROVEN_RECIPIENT, 0);
+    item.zk_proof = vec![0xff; 10];
+    let err = exec(&mut deps, PROVER, &[], ExecuteMsg::BatchRecordTransfer { transfers: vec![item] }).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidProof {}));
+    assert_eq!(proof_stats(&deps), (0, 0));
+}
+
+#[test]
+fn zero_file_hash_cannot_be_proven() {
+    let zero = "\0".repeat(32);
+    let witness = Witness::new(&zero, PROVEN_RECIPIENT.as_bytes());
+    let result = create_random_proof(witness.circuit(), params(), &mut OsRng);
+    assert!(matches!(result, Err(SynthesisError::DivisionByZero)));
+
+    // Nor does a proof for another file hash carry over to it
+    let nonzero = Witness::new(FILE_HASH, PROVEN_RECIPIENT.as_bytes());
+    assert!(!verifies(nonzero.prove(), &zero, PROVEN_RECIPIENT.as_bytes(), &witness.commitment(), 0, 0));
+}
+
+#[test]
+fn nonzero_file_hash_is_proven() {
+    let one = format!("\u{1}{}", "\0".repeat(31));
+    let witness = Witness::new(&one, PROVEN_RECIPIENT.as_bytes());
+    assert!(verifies(witness.prove(), &one, PROVEN_RECIPIENT.as_bytes(), &witness.commitment(), 0, 0));
+}
+
+fn from_hex(hex: &str) -> Scalar {
+    let bytes: Vec<u8> = (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect();
+    scalar(bytes.try_into().unwrap())
+}
+
+#[test]
+fn public_inputs_reproduce_the_on_chain_verification() {
+    let mut deps = setup_verifying();
+    let mut witness = Witness::new(FILE_HASH, PROVEN_RECIPIENT.as_bytes());
+    witness.block_height = mock_env().block.height;
+    let proof = witness.prove();
+    let msg = proven_record_msg(proof.clone(), Some(witness.commitment()), Some(witness.block_height));
+    exec(&mut deps, PROVER, &[], msg).unwrap();
+
+    let inputs: PublicInputs = query_data(&deps, QueryMsg::GetPublicInputs {
+        file_hash: FILE_HASH.to_string(),
+        recipient: PROVEN_RECIPIENT.to_string(),
+    });
+    let scalars = [&inputs.file_hash, &inputs.recipient, &inputs.metadata_commitment, &inputs.block_height, &inputs.epoch]
+        .map(|hex| from_hex(hex));
+    assert_eq!(scalars, [
+        scalar(witness.file_hash),
+        scalar(witness.recipient),
+        scalar(witness.commitment()),
+        Scalar::from(witness.block_height),
+        Scalar::from(0u64),
+    ]);
+
+    // An off-chain verifier holding only the key, the proof and these inputs agrees
+    let proof = bellman::groth16::Proof::<Bls12>::read(&proof[..]).unwrap();
+    let pvk = bellman::groth16::prepare_verifying_key(&params().vk);
+    assert!(bellman::groth16::verify_proof(&pvk, &proof, &scalars).is_ok());
+}
+
+#[test]
+fn public_inputs_of_an_unknown_transfer_are_not_found() {
+    let deps = setup_verifying();
+    let msg = QueryMsg::GetPublicInputs { file_hash: FILE_HASH.to_string(), recipient: PROVEN_RECIPIENT.to_string() };
+    assert!(matches!(query(deps.as_ref(), mock_env(), msg), Err(StdError::NotFound { .. })));
+}
+
+#[test]
+fn verifying_key_hash_matches_the_stored_key() {
+    let deps = setup();
+    let none: Option<Binary> = query_data(&deps, QueryMsg::GetVerifyingKeyHash {});
+    assert_eq!(none, None);
+
+    let deps = setup_verifying();
+    let hash: Option<Binary> = query_data(&deps, QueryMsg::GetVerifyingKeyHash {});
+    let stored = VERIFYING_KEY.load(&deps.storage).unwrap();
+    assert_eq!(stored.as_slice(), verifying_key().as_slice());
+    assert_eq!(hash, Some(Binary::from(Sha256::digest(&stored).to_vec())));
+}
+
+#[test]
+fn verifying_key_must_parse_and_is_admin_only() {
+    let mut deps = setup();
+    let set = |bytes: Vec<u8>| ExecuteMsg::SetVerifyingKey { verifying_key: Binary::from(bytes) };
+
+    let err = exec(&mut deps, SENDER, &[], set(verifying_key())).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    assert!(exec(&mut deps, ADMIN, &[], set(vec![1, 2, 3])).is_err());
+    assert!(!VERIFYING_KEY.exists(&deps.storage));
+}
+
+#[test]
+fn proof_is_bound_to_its_epoch() {
+    let mut witness = Witness::new(FILE_HASH, PROVEN_RECIPIENT.as_bytes());
+    witness.epoch = 1;
+    let proof = witness.prove();
+    let commitment = witness.commitment();
+
+    assert!(verifies(proof.clone(), FILE_HASH, PROVEN_RECIPIENT.as_bytes(), &commitment, 0, 1));
+    assert!(!verifies(proof, FILE_HASH, PROVEN_RECIPIENT.as_bytes(), &commitment, 0, 0));
+}
+
+#[test]
+fn epoch_bump_rejects_proofs_from_the_previous_epoch() {
+    let mut deps = setup_verifying();
+    let stale = Witness::new(FILE_HASH, PROVEN_RECIPIENT.as_bytes());
+    let stale_proof = stale.prove();
+
+    let err = exec(&mut deps, SENDER, &[], ExecuteMsg::BumpEpoch {}).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::BumpEpoch {}).unwrap();
+
+    let msg = proven_record_msg(stale_proof, Some(stale.commitment()), None);
+    assert!(rejected(exec(&mut deps, PROVER, &[], msg).unwrap()));
+    assert!(load_transfer(&deps.storage, FILE_HASH, PROVEN_RECIPIENT).unwrap().is_none());
+
+    let mut fresh = Witness::new(FILE_HASH, PROVEN_RECIPIENT.as_bytes());
+    fresh.epoch = 1;
+    let msg = proven_record_msg(fresh.prove(), Some(fresh.commitment()), None);
+    assert!(!rejected(exec(&mut deps, PROVER, &[], msg).unwrap()));
+    assert_eq!(stored_transfer(&deps, FILE_HASH, PROVEN_RECIPIENT).epoch, 1);
+}
+
+#[test]
+fn proof_from_the_current_epoch_records_before_a_bump() {
+    let mut deps = setup_verifying();
+    let witness = Witness::new(FILE_HASH, PROVEN_RECIPIENT.as_bytes());
+
+    let msg = proven_record_msg(witness.prove(), Some(witness.commitment()), None);
+    assert!(!rejected(exec(&mut deps, PROVER, &[], msg).unwrap()));
+    assert_eq!(stored_transfer(&deps, FILE_HASH, PROVEN_RECIPIENT).epoch, 0);
+}
@@ -0,0 +1,565 @@
+use super::*;
+use cosmwasm_std::coins;
+use cosmwasm_std::testing::MOCK_CONTRACT_ADDR;
+
+// Only the envelope's version, whatever the payload
+#[derive(Deserialize)]
+struct Envelope {
+    schema_version: u16,
+}
+
+#[test]
+fn responses_carry_the_schema_version() {
+    let deps = setup();
+    for msg in [
+        QueryMsg::GetFeePercentage {},
+        QueryMsg::GetVolume {},
+        QueryMsg::GetFileTransfers { page: None },
+        QueryMsg::EstimateRecordGas {},
+        QueryMsg::GetParameters {},
+    ] {
+        let envelope: Envelope = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(envelope.schema_version, SCHEMA_VERSION);
+    }
+}
+
+#[test]
+fn versioned_response_wraps_the_data() {
+    let deps = setup();
+    let response: Versioned<Uint128> =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::GetFeePercentage {}).unwrap()).unwrap();
+    assert_eq!(
+        response,
+        Versioned {
+            schema_version: SCHEMA_VERSION,
+            data: Uint128::new(100),
+        }
+    );
+}
+
+#[test]
+fn recent_transfers_are_newest_first() {
+    let mut deps = setup();
+    for hash in ["hash1", "hash2", "hash3"] {
+        record(&mut deps, hash, RECIPIENT, &[]).unwrap();
+    }
+    let recent: Vec<FileTransfer> = query_data(&deps, QueryMsg::GetRecentTransfers { limit: 2 });
+    let hashes: Vec<_> = recent.iter().map(|t| t.file_hash.as_str()).collect();
+    assert_eq!(hashes, ["hash3", "hash2"]);
+}
+
+#[test]
+fn recent_transfers_are_capped() {
+    let mut deps = setup();
+    for i in 0..=MAX_RECENT_TRANSFERS {
+        record(&mut deps, &format!("hash{}", i), RECIPIENT, &[]).unwrap();
+    }
+    let recent: Vec<FileTransfer> = query_data(&deps, QueryMsg::GetRecentTransfers { limit: 1000 });
+    assert_eq!(recent.len(), MAX_RECENT_TRANSFERS as usize);
+    assert_eq!(recent[0].file_hash, format!("hash{}", MAX_RECENT_TRANSFERS));
+}
+
+#[test]
+fn transfers_by_ids_align_with_the_input() {
+    let mut deps = setup();
+    record(&mut deps, "hash0", RECIPIENT, &[]).unwrap();
+    record(&mut deps, "hash1", RECIPIENT, &[]).unwrap();
+
+    let found: Vec<Option<FileTransfer>> = query_data(&deps, QueryMsg::GetTransfersByIds { ids: vec![1, 7, 0] });
+    let hashes: Vec<_> = found.iter().map(|t| t.as_ref().map(|t| t.file_hash.as_str())).collect();
+    assert_eq!(hashes, [Some("hash1"), None, Some("hash0")]);
+}
+
+#[test]
+fn transfers_by_ids_is_capped() {
+    let deps = setup();
+    let ids = (0..=MAX_BATCH_QUERY_IDS as u64).collect();
+    assert!(query(deps.as_ref(), mock_env(), QueryMsg::GetTransfersByIds { ids }).is_err());
+
+    let ids = (0..MAX_BATCH_QUERY_IDS as u64).collect();
+    let found: Vec<Option<FileTransfer>> = query_data(&deps, QueryMsg::GetTransfersByIds { ids });
+    assert_eq!(found.len(), MAX_BATCH_QUERY_IDS);
+}
+
+// Follow next_key from the first page to the last, collecting each page's file hashes
+fn walk_pages(deps: &TestDeps, limit: u32, reverse: bool) -> Vec<Vec<String>> {
+    let mut pages = vec![];
+    let mut key = None;
+    loop {
+        let page: PageResponse<FileTransfer> = query_data(deps, QueryMsg::GetFileTransfers {
+            page: Some(PageRequest { key, limit: Some(limit), reverse }),
+        });
+        pages.push(page.items.into_iter().map(|t| t.file_hash).collect());
+        match page.next_key {
+            Some(next) => key = Some(next),
+            None => return pages,
+        }
+    }
+}
+
+fn setup_five() -> TestDeps {
+    let mut deps = setup();
+    for i in 0..5 {
+        record(&mut deps, &format!("hash{}", i), RECIPIENT, &[]).unwrap();
+    }
+    deps
+}
+
+#[test]
+fn pages_forward_until_next_key_runs_out() {
+    let deps = setup_five();
+    assert_eq!(walk_pages(&deps, 2, false), vec![
+        vec!["hash0", "hash1"],
+        vec!["hash2", "hash3"],
+        vec!["hash4"],
+    ]);
+}
+
+#[test]
+fn pages_in_reverse() {
+    let deps = setup_five();
+    assert_eq!(walk_pages(&deps, 2, true), vec![
+        vec!["hash4", "hash3"],
+        vec!["hash2", "hash1"],
+        vec!["hash0"],
+    ]);
+}
+
+#[test]
+fn malformed_page_key_is_rejected() {
+    let deps = setup_five();
+    let msg = QueryMsg::GetFileTransfers {
+        page: Some(PageRequest { key: Some(Binary::from(b"abc".to_vec())), limit: None, reverse: false }),
+    };
+    assert!(query(deps.as_ref(), mock_env(), msg).is_err());
+}
+
+fn file_exists(deps: &TestDeps, file_hash: &str) -> bool {
+    query_data(deps, QueryMsg::FileExists { file_hash: file_hash.to_string() })
+}
+
+#[test]
+fn file_exists_for_any_recipient() {
+    let mut deps = setup();
+    record(&mut deps, "shared", RECIPIENT, &[]).unwrap();
+    record(&mut deps, "shared", "other", &[]).unwrap();
+
+    assert!(file_exists(&deps, "shared"));
+    assert!(!file_exists(&deps, "unrecorded"));
+    assert_eq!(FILE_HASH_COUNTS.load(&deps.storage, "shared").unwrap(), 2);
+}
+
+#[test]
+fn file_transfers_are_truncated_at_the_scan_limit() {
+    let mut deps = setup_five();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetQueryScanLimit { limit: 3 }).unwrap();
+
+    let page: PageResponse<FileTransfer> = query_data(&deps, QueryMsg::GetFileTransfers { page: None });
+    assert_eq!(page.items.len(), 3);
+    assert!(page.truncated);
+    assert!(page.next_key.is_some());
+
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetQueryScanLimit { limit: 5 }).unwrap();
+    let page: PageResponse<FileTransfer> = query_data(&deps, QueryMsg::GetFileTransfers { page: None });
+    assert_eq!(page.items.len(), 5);
+    assert!(!page.truncated);
+}
+
+#[test]
+fn time_range_is_truncated_at_the_scan_limit() {
+    let mut deps = setup();
+    for i in 0..5 {
+        exec_at(&mut deps, env_after(i), SENDER, &[], record_msg(&format!("hash{}", i), RECIPIENT)).unwrap();
+    }
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetQueryScanLimit { limit: 3 }).unwrap();
+    let start = mock_env().block.time.seconds() + 1;
+
+    let range = |page| QueryMsg::GetTransfersByTimeRange { start, end: start + 4, page };
+    let first: PageResponse<FileTransfer> = query_data(&deps, range(None));
+    let hashes: Vec<_> = first.items.iter().map(|t| t.file_hash.as_str()).collect();
+    assert_eq!(hashes, ["hash1", "hash2"]);
+    assert!(first.truncated);
+
+    let rest: PageResponse<FileTransfer> =
+        query_data(&deps, range(Some(PageRequest { key: first.next_key, limit: None, reverse: false })));
+    let hashes: Vec<_> = rest.items.iter().map(|t| t.file_hash.as_str()).collect();
+    assert_eq!(hashes, ["hash3", "hash4"]);
+    assert!(!rest.truncated);
+}
+
+#[test]
+fn latest_for_recipient_is_the_newest_transfer() {
+    let mut deps = setup();
+    record(&mut deps, "first", RECIPIENT, &[]).unwrap();
+    record(&mut deps, "elsewhere", "other", &[]).unwrap();
+    record(&mut deps, "second", RECIPIENT, &[]).unwrap();
+
+    let latest: Option<FileTransfer> =
+        query_data(&deps, QueryMsg::GetLatestForRecipient { recipient: RECIPIENT.to_string() });
+    assert_eq!(latest.unwrap().file_hash, "second");
+
+    let none: Option<FileTransfer> =
+        query_data(&deps, QueryMsg::GetLatestForRecipient { recipient: "nobody".to_string() });
+    assert_eq!(none, None);
+}
+
+#[test]
+fn record_gas_estimate_is_within_a_sane_band() {
+    let deps = setup();
+    let estimate: GasEstimate = query_data(&deps, QueryMsg::EstimateRecordGas {});
+    assert!(estimate.base > 0 && estimate.per_transfer > 0 && estimate.proof_verify > 0);
+    assert_eq!(estimate.single_record, estimate.base + estimate.per_transfer + estimate.proof_verify);
+    // Pairing checks dominate, and a record must fit well inside a block
+    assert!(estimate.proof_verify > estimate.base + estimate.per_transfer);
+    assert!((1_000_000..10_000_000).contains(&estimate.single_record));
+}
+
+fn event_log(deps: &TestDeps, start_after: Option<u64>, limit: Option<u32>) -> Vec<EventRecord> {
+    query_data(deps, QueryMsg::GetEventLog { start_after, limit })
+}
+
+#[test]
+fn significant_actions_are_logged_in_order() {
+    let mut deps = setup();
+    record(&mut deps, "file", RECIPIENT, &coins(10000, "usei")).unwrap();
+    let revoke = ExecuteMsg::RevokeTransfer { file_hash: "file".to_string(), recipient: RECIPIENT.to_string() };
+    exec(&mut deps, SENDER, &[], revoke).unwrap();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetFeePercentage { percentage: Uint128::new(50) }).unwrap();
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, coins(10000, "usei"));
+    let withdraw = ExecuteMsg::WithdrawFees { amount: Uint128::new(10), to: None, denom: None };
+    exec(&mut deps, ADMIN, &[], withdraw).unwrap();
+
+    let log = event_log(&deps, None, None);
+    let actions: Vec<_> = log.iter().map(|record| (record.seq, record.action.as_str())).collect();
+    assert_eq!(actions, [
+        (0, "record_transfer"),
+        (1, "revoke_transfer"),
+        (2, "set_fee_percentage"),
+        (3, "withdraw_fees"),
+    ]);
+    assert!(log[0].attributes.contains(&("file_hash".to_string(), "file".to_string())));
+
+    let page: Vec<_> = event_log(&deps, Some(1), Some(1)).into_iter().map(|record| record.seq).collect();
+    assert_eq!(page, [2]);
+}
+
+#[test]
+fn seen_denoms_outlive_accepted_denom_changes() {
+    let mut deps = setup();
+    let denoms = ExecuteMsg::SetAcceptedDenoms { denoms: vec!["usei".to_string(), "uatom".to_string()] };
+    exec(&mut deps, ADMIN, &[], denoms).unwrap();
+    record(&mut deps, "a", RECIPIENT, &coins(100, "usei")).unwrap();
+    record(&mut deps, "b", RECIPIENT, &coins(100, "uatom")).unwrap();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetAcceptedDenoms { denoms: vec!["usei".to_string()] }).unwrap();
+
+    let seen: Vec<String> = query_data(&deps, QueryMsg::GetSeenDenoms {});
+    assert_eq!(seen, ["uatom", "usei"]);
+}
+
+fn updated_since(deps: &TestDeps, seq: u64, limit: Option<u32>) -> Vec<(String, u64)> {
+    let updates: Vec<FileTransfer> = query_data(deps, QueryMsg::GetUpdatedSince { seq, limit });
+    updates.into_iter().map(|t| (t.file_hash, t.update_seq)).collect()
+}
+
+#[test]
+fn records_and_revokes_advance_the_update_sequence() {
+    let mut deps = setup();
+    record(&mut deps, "a", RECIPIENT, &[]).unwrap();
+    record(&mut deps, "b", RECIPIENT, &[]).unwrap();
+    assert_eq!(updated_since(&deps, 0, None), [("a".to_string(), 1), ("b".to_string(), 2)]);
+
+    let revoke = ExecuteMsg::RevokeTransfer { file_hash: "a".to_string(), recipient: RECIPIENT.to_string() };
+    exec(&mut deps, SENDER, &[], revoke).unwrap();
+    // A transfer appears once, at its latest change
+    assert_eq!(updated_since(&deps, 0, None), [("b".to_string(), 2), ("a".to_string(), 3)]);
+    assert_eq!(stored_transfer(&deps, "a", RECIPIENT).status, TransferStatus::Revoked);
+}
+
+#[test]
+fn updated_since_returns_only_newer_changes() {
+    let mut deps = setup();
+    for file_hash in ["a", "b", "c"] {
+        record(&mut deps, file_hash, RECIPIENT, &[]).unwrap();
+    }
+
+    assert_eq!(updated_since(&deps, 2, None), [("c".to_string(), 3)]);
+    assert!(updated_since(&deps, 3, None).is_empty());
+    assert_eq!(updated_since(&deps, 0, Some(2)).len(), 2);
+}
+
+// Embargoed transfer, long enough in both fields to derive proof public inputs
+const EMBARGOED: &str = "embargoed00000000000000000000000000";
+const EMBARGOED_RECIPIENT: &str = "recipient0000000000000000000000000000001";
+
+// Records EMBARGOED, released 100 seconds after mock_env, next to a public transfer
+fn setup_embargoed() -> TestDeps {
+    let mut deps = setup();
+    let mut msg = record_msg(EMBARGOED, EMBARGOED_RECIPIENT);
+    if let ExecuteMsg::RecordTransfer { visible_at, .. } = &mut msg {
+        *visible_at = Some(mock_env().block.time.seconds() + 100);
+    }
+    exec(&mut deps, SENDER, &[], msg).unwrap();
+    record(&mut deps, "public", RECIPIENT, &[]).unwrap();
+    deps
+}
+
+fn listed_at(deps: &TestDeps, env: Env) -> Vec<String> {
+    let page: PageResponse<FileTransfer> = query_data_at(deps, env, QueryMsg::GetFileTransfers { page: None });
+    page.items.into_iter().map(|t| t.file_hash).collect()
+}
+
+#[test]
+fn embargoed_transfer_is_hidden_until_its_release_time() {
+    let deps = setup_embargoed();
+    let verify = QueryMsg::VerifyTransfer { file_hash: EMBARGOED.to_string(), recipient: EMBARGOED_RECIPIENT.to_string() };
+    let exists = QueryMsg::FileExists { file_hash: EMBARGOED.to_string() };
+
+    let before = env_after(99);
+    assert_eq!(listed_at(&deps, before.clone()), ["public"]);
+    assert!(!query_data_at::<bool>(&deps, before.clone(), verify.clone()));
+    assert!(!query_data_at::<bool>(&deps, before, exists.clone()));
+
+    let release = env_after(100);
+    assert_eq!(listed_at(&deps, release.clone()), [EMBARGOED, "public"]);
+    assert!(query_data_at::<bool>(&deps, release.clone(), verify));
+    assert!(query_data_at::<bool>(&deps, release, exists));
+}
+
+#[test]
+fn every_transfer_query_respects_the_embargo() {
+    let deps = setup_embargoed();
+    let (before, release) = (env_after(99), env_after(100));
+    let hashes = |transfers: &[FileTransfer]| transfers.iter().map(|t| t.file_hash.clone()).collect::<Vec<_>>();
+
+    // A skipped entry doesn't use up the limit
+    let updated: Vec<FileTransfer> = query_data_at(&deps, before.clone(), QueryMsg::GetUpdatedSince { seq: 0, limit: Some(1) });
+    assert_eq!(hashes(&updated), ["public"]);
+    let export = QueryMsg::ExportSnapshot { start_after: None, limit: None };
+    let snapshot: Snapshot = query_data_at(&deps, before.clone(), export.clone());
+    assert_eq!(hashes(&snapshot.transfers), ["public"]);
+    let snapshot: Snapshot = query_data_at(&deps, release.clone(), export);
+    assert_eq!(hashes(&snapshot.transfers), [EMBARGOED, "public"]);
+
+    let (file_hash, recipient) = (EMBARGOED.to_string(), EMBARGOED_RECIPIENT.to_string());
+    let by_key = [
+        QueryMsg::GetDeliveryProof { file_hash: file_hash.clone(), recipient: recipient.clone() },
+        QueryMsg::GetPublicInputs { file_hash: file_hash.clone(), recipient: recipient.clone() },
+        QueryMsg::GetTransferHistory { file_hash, recipient },
+    ];
+    for msg in by_key {
+        let err = query(deps.as_ref(), before.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, StdError::NotFound { .. }));
+        query(deps.as_ref(), release.clone(), msg).unwrap();
+    }
+}
+
+fn roles(deps: &TestDeps, address: &str) -> Roles {
+    query_data(deps, QueryMsg::GetRoles { address: address.to_string() })
+}
+
+#[test]
+fn admin_holds_the_admin_and_fee_recipient_roles() {
+    let mut deps = setup();
+    let add = ExecuteMsg::AddWithdrawDestination { address: ADMIN.to_string() };
+    exec(&mut deps, ADMIN, &[], add).unwrap();
+
+    assert_eq!(roles(&deps, ADMIN), Roles {
+        is_admin: true,
+        is_fee_recipient: true,
+        is_allowlisted: true,
+        is_opted_in: false,
+    });
+}
+
+#[test]
+fn random_address_holds_no_roles() {
+    let deps = setup();
+    assert_eq!(roles(&deps, "random"), Roles {
+        is_admin: false,
+        is_fee_recipient: false,
+        is_allowlisted: false,
+        is_opted_in: false,
+    });
+
+    let msg = QueryMsg::GetRoles { address: "Not An Address".to_string() };
+    assert!(query(deps.as_ref(), mock_env(), msg).is_err());
+}
+
+fn detailed(deps: &TestDeps, env: Env, file_hash: &str) -> Option<FileTransfer> {
+    query_data_at(deps, env, QueryMsg::VerifyTransferDetailed {
+        file_hash: file_hash.to_string(),
+        recipient: RECIPIENT.to_string(),
+    })
+}
+
+#[test]
+fn detailed_verification_returns_the_full_record() {
+    let mut deps = setup();
+    record(&mut deps, "file", RECIPIENT, &coins(1000, "usei")).unwrap();
+
+    let transfer = detailed(&deps, mock_env(), "file").unwrap();
+    assert_eq!(transfer, stored_transfer(&deps, "file", RECIPIENT));
+    assert_eq!(transfer.transfer_fee, Uint128::new(10));
+}
+
+#[test]
+fn detailed_verification_of_an_absent_transfer_is_none() {
+    let mut deps = setup();
+    assert_eq!(detailed(&deps, mock_env(), "missing"), None);
+
+    // A revoked transfer is still returned, with the status telling it no longer verifies
+    record(&mut deps, "file", RECIPIENT, &[]).unwrap();
+    let revoke = ExecuteMsg::RevokeTransfer { file_hash: "file".to_string(), recipient: RECIPIENT.to_string() };
+    exec(&mut deps, SENDER, &[], revoke).unwrap();
+    assert_eq!(detailed(&deps, mock_env(), "file").unwrap().status, TransferStatus::Revoked);
+}
+
+#[test]
+fn dashboard_populates_every_section() {
+    let mut deps = setup();
+    record(&mut deps, "a", RECIPIENT, &coins(1000, "usei")).unwrap();
+    record(&mut deps, "b", RECIPIENT, &coins(2000, "usei")).unwrap();
+    record(&mut deps, "c", RECIPIENT, &coins(3000, "usei")).unwrap();
+
+    let dashboard: Dashboard = query_data(&deps, QueryMsg::GetDashboard { recent_limit: 2 });
+    assert_eq!(dashboard.config.admin, Addr::unchecked(ADMIN));
+    assert_eq!(dashboard.fee_schedule.effective_percentage, Uint128::new(100));
+    assert_eq!(dashboard.treasury[0].denom, "usei");
+    assert_eq!(dashboard.treasury[0].accrued, Uint128::new(60));
+    assert_eq!(dashboard.volume, coins(6000, "usei"));
+    assert_eq!(dashboard.transfer_count, 3);
+    assert_eq!(dashboard.proof_stats, ProofStats { proofs_verified: 0, proofs_rejected: 0 });
+
+    let recent: Vec<&str> = dashboard.recent_transfers.iter().map(|t| t.file_hash.as_str()).collect();
+    assert_eq!(recent, vec!["c", "b"]);
+}
+
+// Contract with five transfers and page limits of 2 by default, 3 at most
+fn setup_limited() -> TestDeps {
+    let mut deps = setup();
+    for file_hash in ["a", "b", "c", "d", "e"] {
+        record(&mut deps, file_hash, RECIPIENT, &[]).unwrap();
+    }
+    let limit = QueryLimit { default: 2, max: 3 };
+    let limits = QueryLimits {
+        transfers: limit,
+        recent_transfers: limit,
+        ..QueryLimits::default()
+    };
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetQueryLimits { limits }).unwrap();
+    deps
+}
+
+fn transfers_page(deps: &TestDeps, limit: Option<u32>) -> usize {
+    let page = PageRequest { key: None, limit, reverse: false };
+    let page: PageResponse<FileTransfer> = query_data(deps, QueryMsg::GetFileTransfers { page: Some(page) });
+    page.items.len()
+}
+
+#[test]
+fn effective_limit_respects_the_request_and_the_configured_max() {
+    let deps = setup_limited();
+
+    assert_eq!(transfers_page(&deps, None), 2);
+    assert_eq!(transfers_page(&deps, Some(1)), 1);
+    assert_eq!(transfers_page(&deps, Some(10)), 3);
+
+    let recent = |limit| query_data::<Vec<FileTransfer>>(&deps, QueryMsg::GetRecentTransfers { limit }).len();
+    assert_eq!(recent(1), 1);
+    assert_eq!(recent(10), 3);
+}
+
+#[test]
+fn query_limit_defaults_must_fit_under_their_max() {
+    let mut deps = setup();
+    let limits = QueryLimits {
+        event_log: QueryLimit { default: 5, max: 4 },
+        ..QueryLimits::default()
+    };
+    assert!(exec(&mut deps, ADMIN, &[], ExecuteMsg::SetQueryLimits { limits }).is_err());
+
+    let limits = QueryLimits::default();
+    let err = exec(&mut deps, SENDER, &[], ExecuteMsg::SetQueryLimits { limits }).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+}
+
+fn parameters(deps: &TestDeps) -> Vec<Parameter> {
+    query_data(deps, QueryMsg::GetParameters {})
+}
+
+fn parameter<'a>(parameters: &'a [Parameter], name: &str) -> &'a Parameter {
+    parameters.iter().find(|p| p.name == name).unwrap_or_else(|| panic!("{} is not listed", name))
+}
+
+#[test]
+fn unset_parameters_report_their_defaults() {
+    let mut deps = setup();
+    let splits = ExecuteMsg::SetFeeSplits { splits: vec![("dao".to_string(), 3000)] };
+    exec(&mut deps, ADMIN, &[], splits).unwrap();
+    let listed = parameters(&deps);
+
+    for name in [
+        "withdraw_whitelist",
+        "features",
+        "admins",
+        "recovery_admin",
+        "max_recipients",
+        "query_limits",
+        "verifying_key_hash",
+        "denom_fee_percentages",
+        "auto_withdraw_thresholds",
+    ] {
+        let parameter = parameter(&listed, name);
+        assert_eq!(parameter.default.as_ref(), Some(&parameter.current), "{}", name);
+    }
+    assert_eq!(parameter(&listed, "recovery_admin").current, "null");
+
+    // Parameters set since instantiate differ from their defaults
+    let splits = parameter(&listed, "fee_splits");
+    assert_eq!(splits.current, r#"[["dao",3000]]"#);
+    assert_eq!(splits.default.as_deref(), Some("[]"));
+    let policy = parameter(&listed, "verification_policy");
+    assert_ne!(policy.default.as_ref(), Some(&policy.current));
+
+    // Values instantiate must supply have no default
+    assert_eq!(parameter(&listed, "fee_percentage").default, None);
+}
+
+#[test]
+fn per_denom_tunables_are_listed_and_runtime_state_is_not() {
+    let mut deps = setup();
+    let msg = ExecuteMsg::SetMinTransferAmount { denom: "usei".to_string(), amount: Some(Uint128::new(1000)) };
+    exec(&mut deps, ADMIN, &[], msg).unwrap();
+    let listed = parameters(&deps);
+
+    let minimums = parameter(&listed, "min_transfer_amounts");
+    assert_eq!(minimums.current, r#"[["usei","1000"]]"#);
+    assert_eq!(minimums.default.as_deref(), Some("[]"));
+    for name in ["pending_fee", "fee_ramp", "current_epoch", "paused"] {
+        assert!(listed.iter().all(|p| p.name != name), "{}", name);
+    }
+}
+
+fn compute_key(deps: &TestDeps, file_hash: &str, recipient: &str) -> Binary {
+    query_data(deps, QueryMsg::ComputeKey {
+        file_hash: file_hash.to_string(),
+        recipient: recipient.to_string(),
+    })
+}
+
+#[test]
+fn computed_key_is_the_one_transfer_lookups_read() {
+    let mut deps = setup();
+    record(&mut deps, "a", RECIPIENT, &[]).unwrap();
+    record(&mut deps, "b", RECIPIENT, &[]).unwrap();
+
+    let key = compute_key(&deps, "b", RECIPIENT);
+    let raw = deps.storage.get(key.as_slice()).unwrap();
+    let id: u64 = from_json(raw).unwrap();
+    assert_eq!(id, TRANSFER_KEYS.load(&deps.storage, ("b", RECIPIENT)).unwrap());
+    assert_eq!(TRANSFERS.load(&deps.storage, id).unwrap(), stored_transfer(&deps, "b", RECIPIENT));
+
+    // Keys are deterministic and nothing is stored under an unrecorded pair's key
+    assert_eq!(compute_key(&deps, "b", RECIPIENT), key);
+    assert!(deps.storage.get(compute_key(&deps, "b", "other").as_slice()).is_none());
+}
@@ -0,0 +1,800 @@
+use super::*;
+use cosmwasm_std::{attr, coins};
+
+fn record_for_msg(on_behalf_of: &str, file_hash: &str) -> ExecuteMsg {
+    ExecuteMsg::RecordTransferFor {
+        on_behalf_of: on_behalf_of.to_string(),
+        file_hash: file_hash.to_string(),
+        recipient: RECIPIENT.to_string(),
+        zk_proof: vec![],
+        ttl_seconds: None,
+        metadata_commitment: None,
+        proved_height: None,
+        wrapped_key: None,
+        file_size: None,
+        metadata_signature: None,
+        coupon: None,
+        visible_at: None,
+        memo: None,
+    }
+}
+
+#[test]
+fn grantee_records_on_behalf_of_granter() {
+    let mut deps = setup();
+    exec(&mut deps, SENDER, &[], ExecuteMsg::Grant { grantee: "platform".to_string() }).unwrap();
+
+    exec(&mut deps, "platform", &coins(1000, "usei"), record_for_msg(SENDER, "file")).unwrap();
+
+    let transfer = stored_transfer(&deps, "file", RECIPIENT);
+    assert_eq!(transfer.sender, SENDER);
+    assert_eq!(transfer.transfer_fee, Uint128::new(10));
+}
+
+#[test]
+fn record_for_without_grant_is_unauthorized() {
+    let mut deps = setup();
+
+    let err = exec(&mut deps, "platform", &[], record_for_msg(SENDER, "file")).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+}
+
+#[test]
+fn revoked_grant_no_longer_authorizes() {
+    let mut deps = setup();
+    exec(&mut deps, SENDER, &[], ExecuteMsg::Grant { grantee: "platform".to_string() }).unwrap();
+    exec(&mut deps, SENDER, &[], ExecuteMsg::Revoke { grantee: "platform".to_string() }).unwrap();
+
+    let err = exec(&mut deps, "platform", &[], record_for_msg(SENDER, "file")).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+}
+
+fn revoke(deps: &mut TestDeps, file_hash: &str) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::RevokeTransfer {
+        file_hash: file_hash.to_string(),
+        recipient: RECIPIENT.to_string(),
+    };
+    exec(deps, SENDER, &[], msg)
+}
+
+fn verifies(deps: &TestDeps, file_hash: &str) -> bool {
+    query_data(deps, QueryMsg::VerifyTransfer {
+        file_hash: file_hash.to_string(),
+        recipient: RECIPIENT.to_string(),
+    })
+}
+
+#[test]
+fn revoked_transfer_is_kept_for_audit_but_fails_verification() {
+    let mut deps = setup();
+    record(&mut deps, "file", RECIPIENT, &[]).unwrap();
+    assert!(verifies(&deps, "file"));
+
+    revoke(&mut deps, "file").unwrap();
+
+    assert!(!verifies(&deps, "file"));
+    let revoked: PageResponse<FileTransfer> = query_data(&deps, QueryMsg::GetRevokedTransfers { page: None });
+    assert_eq!(revoked.items.len(), 1);
+    assert_eq!(revoked.items[0].status, TransferStatus::Revoked);
+    assert_eq!(revoked.items[0].revoked_at, Some(mock_env().block.time.seconds()));
+
+    let err = revoke(&mut deps, "file").unwrap_err();
+    assert!(matches!(err, ContractError::AlreadyRevoked {}));
+}
+
+#[test]
+fn only_sender_or_admin_may_revoke() {
+    let mut deps = setup();
+    record(&mut deps, "file", RECIPIENT, &[]).unwrap();
+    let msg = ExecuteMsg::RevokeTransfer {
+        file_hash: "file".to_string(),
+        recipient: RECIPIENT.to_string(),
+    };
+
+    let err = exec(&mut deps, "stranger", &[], msg.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    exec(&mut deps, ADMIN, &[], msg).unwrap();
+}
+
+#[test]
+fn revoked_key_cannot_be_rerecorded_by_default() {
+    let mut deps = setup();
+    record(&mut deps, "file", RECIPIENT, &[]).unwrap();
+    let err = record(&mut deps, "file", RECIPIENT, &[]).unwrap_err();
+    assert!(matches!(err, ContractError::DuplicateTransfer {}));
+
+    revoke(&mut deps, "file").unwrap();
+    let err = record(&mut deps, "file", RECIPIENT, &[]).unwrap_err();
+    assert!(matches!(err, ContractError::DuplicateTransfer {}));
+}
+
+#[test]
+fn revoked_key_can_be_rerecorded_when_allowed() {
+    let mut deps = setup();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetAllowRerecord { allow: true }).unwrap();
+    record(&mut deps, "file", RECIPIENT, &[]).unwrap();
+
+    // Still only once while the first record is live
+    let err = record(&mut deps, "file", RECIPIENT, &[]).unwrap_err();
+    assert!(matches!(err, ContractError::DuplicateTransfer {}));
+
+    revoke(&mut deps, "file").unwrap();
+    record(&mut deps, "file", RECIPIENT, &[]).unwrap();
+    assert!(verifies(&deps, "file"));
+    // The re-record is a new transfer; the revoked one stays for audit
+    let revoked: PageResponse<FileTransfer> = query_data(&deps, QueryMsg::GetRevokedTransfers { page: None });
+    assert_eq!(revoked.items.len(), 1);
+    let exists: bool = query_data(&deps, QueryMsg::FileExists { file_hash: "file".to_string() });
+    assert!(exists);
+}
+
+fn record_with_ttl(deps: &mut TestDeps, file_hash: &str, ttl: u64) -> Result<Response, ContractError> {
+    let mut msg = record_msg(file_hash, RECIPIENT);
+    if let ExecuteMsg::RecordTransfer { ttl_seconds, .. } = &mut msg {
+        *ttl_seconds = Some(ttl);
+    }
+    exec(deps, SENDER, &[], msg)
+}
+
+#[test]
+fn ttl_is_capped_by_max_ttl() {
+    let mut deps = setup();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetMaxTtl { max_ttl_seconds: Some(3600) }).unwrap();
+
+    record_with_ttl(&mut deps, "below", 3599).unwrap();
+    record_with_ttl(&mut deps, "at", 3600).unwrap();
+    let err = record_with_ttl(&mut deps, "above", 3601).unwrap_err();
+    assert!(matches!(err, ContractError::TtlTooLong { max: 3600 }));
+
+    let now = mock_env().block.time.seconds();
+    assert_eq!(stored_transfer(&deps, "at", RECIPIENT).expires_at, Some(now + 3600));
+}
+
+#[test]
+fn ttl_is_unbounded_without_max_ttl() {
+    let mut deps = setup();
+    record_with_ttl(&mut deps, "file", u32::MAX as u64).unwrap();
+}
+
+#[test]
+fn ttl_overflowing_the_clock_is_too_long() {
+    let mut deps = setup();
+    let now = mock_env().block.time.seconds();
+
+    let err = record_with_ttl(&mut deps, "file", u64::MAX).unwrap_err();
+    assert!(matches!(err, ContractError::TtlTooLong { max } if max == u64::MAX - now));
+    record_with_ttl(&mut deps, "file", u64::MAX - now).unwrap();
+    assert_eq!(stored_transfer(&deps, "file", RECIPIENT).expires_at, Some(u64::MAX));
+}
+
+#[test]
+fn profiling_attributes_only_when_verbose() {
+    let mut deps = setup();
+    let quiet = record(&mut deps, "quiet", RECIPIENT, &[]).unwrap();
+    assert_eq!(event_attr(&quiet, "proof_bytes"), None);
+    assert_eq!(event_attr(&quiet, "transfer_count_after"), None);
+
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetVerboseEvents { enabled: true }).unwrap();
+    let verbose = record(&mut deps, "verbose", RECIPIENT, &[]).unwrap();
+    assert_eq!(event_attr(&verbose, "proof_bytes").as_deref(), Some("0"));
+    assert_eq!(event_attr(&verbose, "transfer_count_after").as_deref(), Some("2"));
+}
+
+// Opt-in feature compiled in, which also makes it required from instantiation
+fn setup_opt_in() -> TestDeps {
+    setup_with(InstantiateMsg {
+        features: Some(Features { opt_in: true, ..Features::default() }),
+        ..instantiate_msg()
+    })
+}
+
+#[test]
+fn opted_in_recipient_can_receive() {
+    let mut deps = setup_opt_in();
+    exec(&mut deps, RECIPIENT, &[], ExecuteMsg::OptIn {}).unwrap();
+    record(&mut deps, "file", RECIPIENT, &[]).unwrap();
+}
+
+#[test]
+fn recipient_not_opted_in_is_rejected() {
+    let mut deps = setup_opt_in();
+    let err = record(&mut deps, "file", RECIPIENT, &[]).unwrap_err();
+    assert!(matches!(err, ContractError::RecipientNotOptedIn {}));
+
+    exec(&mut deps, RECIPIENT, &[], ExecuteMsg::OptIn {}).unwrap();
+    exec(&mut deps, RECIPIENT, &[], ExecuteMsg::OptOut {}).unwrap();
+    let err = record(&mut deps, "file", RECIPIENT, &[]).unwrap_err();
+    assert!(matches!(err, ContractError::RecipientNotOptedIn {}));
+}
+
+#[test]
+fn opt_in_is_not_checked_unless_required() {
+    let mut deps = setup_opt_in();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetOptInRequired { required: false }).unwrap();
+    record(&mut deps, "file", RECIPIENT, &[]).unwrap();
+}
+
+#[test]
+fn batch_with_an_intra_batch_duplicate_records_nothing() {
+    let mut deps = setup();
+    let transfers = vec![
+        batch_item("a", RECIPIENT, 0),
+        batch_item("b", RECIPIENT, 0),
+        batch_item("a", RECIPIENT, 0),
+    ];
+    let err = exec(&mut deps, SENDER, &[], ExecuteMsg::BatchRecordTransfer { transfers }).unwrap_err();
+    assert!(matches!(err, ContractError::DuplicateInBatch { index: 2 }));
+    assert!(load_transfer(&deps.storage, "a", RECIPIENT).unwrap().is_none());
+}
+
+#[test]
+fn batch_without_duplicates_records_every_item() {
+    let mut deps = setup();
+    let transfers = vec![batch_item("a", RECIPIENT, 0), batch_item("b", "other", 0)];
+    exec(&mut deps, SENDER, &[], ExecuteMsg::BatchRecordTransfer { transfers }).unwrap();
+    stored_transfer(&deps, "a", RECIPIENT);
+    stored_transfer(&deps, "b", "other");
+}
+
+fn record_with_wrapped_key(deps: &mut TestDeps, file_hash: &str, key: Vec<u8>) -> Result<Response, ContractError> {
+    let mut msg = record_msg(file_hash, RECIPIENT);
+    if let ExecuteMsg::RecordTransfer { wrapped_key, .. } = &mut msg {
+        *wrapped_key = Some(Binary::from(key));
+    }
+    exec(deps, SENDER, &[], msg)
+}
+
+#[test]
+fn wrapped_key_is_stored_and_returned() {
+    let mut deps = setup();
+    record_with_wrapped_key(&mut deps, "keyed", vec![7; MAX_WRAPPED_KEY_LEN]).unwrap();
+    record(&mut deps, "plain", RECIPIENT, &[]).unwrap();
+
+    let keyed: Option<FileTransfer> = query_data(&deps, QueryMsg::VerifyTransferDetailed {
+        file_hash: "keyed".to_string(),
+        recipient: RECIPIENT.to_string(),
+    });
+    assert_eq!(keyed.unwrap().wrapped_key, Some(Binary::from(vec![7; MAX_WRAPPED_KEY_LEN])));
+    assert_eq!(stored_transfer(&deps, "plain", RECIPIENT).wrapped_key, None);
+}
+
+#[test]
+fn wrapped_key_length_is_bounded() {
+    let mut deps = setup();
+    let err = record_with_wrapped_key(&mut deps, "keyed", vec![7; MAX_WRAPPED_KEY_LEN + 1]).unwrap_err();
+    assert!(matches!(err, ContractError::WrappedKeyTooLong { max: MAX_WRAPPED_KEY_LEN }));
+}
+
+// Keys of every event attribute in a response
+fn event_keys(response: &Response) -> Vec<&str> {
+    response
+        .events
+        .iter()
+        .flat_map(|event| &event.attributes)
+        .map(|attr| attr.key.as_str())
+        .collect()
+}
+
+#[test]
+fn minimal_events_emit_only_the_opaque_id() {
+    let mut deps = setup();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetMinimalEvents { enabled: true }).unwrap();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetVerboseEvents { enabled: true }).unwrap();
+
+    let response = record(&mut deps, "file", RECIPIENT, &[]).unwrap();
+    assert_eq!(event_keys(&response), ["transfer_id", "proof_cache"]);
+    assert_eq!(response.attributes, vec![attr("action", "record_transfer")]);
+}
+
+#[test]
+fn full_events_name_the_transfer() {
+    let mut deps = setup();
+    let response = record(&mut deps, "file", RECIPIENT, &[]).unwrap();
+    assert_eq!(event_attr(&response, "file_hash").as_deref(), Some("file"));
+    assert_eq!(event_attr(&response, "recipient").as_deref(), Some(RECIPIENT));
+}
+
+fn record_with_size(deps: &mut TestDeps, file_hash: &str, size: u64) -> Result<Response, ContractError> {
+    let mut msg = record_msg(file_hash, RECIPIENT);
+    if let ExecuteMsg::RecordTransfer { file_size, .. } = &mut msg {
+        *file_size = Some(size);
+    }
+    exec(deps, SENDER, &[], msg)
+}
+
+#[test]
+fn file_size_at_the_limit_is_accepted() {
+    let mut deps = setup();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetMaxFileSize { max_file_size: Some(1024) }).unwrap();
+    record_with_size(&mut deps, "file", 1024).unwrap();
+    assert_eq!(stored_transfer(&deps, "file", RECIPIENT).file_size, Some(1024));
+}
+
+#[test]
+fn file_size_above_the_limit_is_rejected() {
+    let mut deps = setup();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetMaxFileSize { max_file_size: Some(1024) }).unwrap();
+    let err = record_with_size(&mut deps, "file", 1025).unwrap_err();
+    assert!(matches!(err, ContractError::FileTooLarge {}));
+
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetMaxFileSize { max_file_size: None }).unwrap();
+    record_with_size(&mut deps, "file", 1025).unwrap();
+}
+
+fn record_signed(deps: &mut TestDeps, commitment: [u8; 32], signature: Option<Binary>) -> Result<Response, ContractError> {
+    let mut msg = record_msg("file", RECIPIENT);
+    if let ExecuteMsg::RecordTransfer { metadata_commitment, metadata_signature, .. } = &mut msg {
+        *metadata_commitment = Some(Binary::from(commitment.to_vec()));
+        *metadata_signature = signature;
+    }
+    exec(deps, SENDER, &[], msg)
+}
+
+fn setup_with_pubkey() -> TestDeps {
+    let mut deps = setup();
+    let register = ExecuteMsg::RegisterPubkey { pubkey: pubkey(&signing_key(1)) };
+    exec(&mut deps, SENDER, &[], register).unwrap();
+    deps
+}
+
+#[test]
+fn metadata_signed_by_the_sender_is_accepted() {
+    let mut deps = setup_with_pubkey();
+    let commitment = [9u8; 32];
+    record_signed(&mut deps, commitment, Some(sign(&signing_key(1), &commitment))).unwrap();
+}
+
+#[test]
+fn metadata_signature_mismatch_is_rejected() {
+    let mut deps = setup_with_pubkey();
+    let commitment = [9u8; 32];
+
+    // Signed by another key, and over other metadata
+    for signature in [sign(&signing_key(2), &commitment), sign(&signing_key(1), &[8u8; 32])] {
+        let err = record_signed(&mut deps, commitment, Some(signature)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSignature {}));
+    }
+}
+
+#[test]
+fn metadata_signature_needs_a_registered_pubkey() {
+    let mut deps = setup();
+    let commitment = [9u8; 32];
+    let err = record_signed(&mut deps, commitment, Some(sign(&signing_key(1), &commitment))).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidSignature {}));
+}
+
+fn acknowledge(deps: &mut TestDeps, file_hash: &str) -> Result<Response, ContractError> {
+    exec(deps, RECIPIENT, &[], ExecuteMsg::AcknowledgeTransfer { file_hash: file_hash.to_string() })
+}
+
+fn hashes_with_status(deps: &TestDeps, env: Env, status: TransferStatus) -> Vec<String> {
+    let page: PageResponse<FileTransfer> =
+        query_data_at(deps, env, QueryMsg::GetTransfersByStatus { status, limit: None });
+    page.items.into_iter().map(|t| t.file_hash).collect()
+}
+
+#[test]
+fn status_tracks_each_transfer_through_its_lifecycle() {
+    let mut deps = setup();
+    record(&mut deps, "acked", RECIPIENT, &[]).unwrap();
+    record(&mut deps, "revoked", RECIPIENT, &[]).unwrap();
+    record_with_ttl(&mut deps, "expiring", 100).unwrap();
+    record(&mut deps, "live", RECIPIENT, &[]).unwrap();
+    acknowledge(&mut deps, "acked").unwrap();
+    revoke(&mut deps, "revoked").unwrap();
+
+    let later = env_after(100);
+    assert_eq!(hashes_with_status(&deps, later.clone(), TransferStatus::Recorded), ["live"]);
+    assert_eq!(hashes_with_status(&deps, later.clone(), TransferStatus::Acknowledged), ["acked"]);
+    assert_eq!(hashes_with_status(&deps, later.clone(), TransferStatus::Revoked), ["revoked"]);
+    assert_eq!(hashes_with_status(&deps, later, TransferStatus::Expired), ["expiring"]);
+    // Expiry is derived when read, so it is still Recorded before its time
+    assert_eq!(hashes_with_status(&deps, mock_env(), TransferStatus::Expired), Vec::<String>::new());
+}
+
+#[test]
+fn only_recorded_transfers_can_be_acknowledged() {
+    let mut deps = setup();
+    record(&mut deps, "revoked", RECIPIENT, &[]).unwrap();
+    revoke(&mut deps, "revoked").unwrap();
+    let err = acknowledge(&mut deps, "revoked").unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::InvalidStatusTransition { from: TransferStatus::Revoked, to: TransferStatus::Acknowledged }
+    ));
+
+    record(&mut deps, "acked", RECIPIENT, &[]).unwrap();
+    acknowledge(&mut deps, "acked").unwrap();
+    let err = acknowledge(&mut deps, "acked").unwrap_err();
+    assert!(matches!(err, ContractError::InvalidStatusTransition { from: TransferStatus::Acknowledged, .. }));
+}
+
+fn confirm_delivery(deps: &mut TestDeps, signature: Binary) -> Result<Response, ContractError> {
+    exec(deps, RECIPIENT, &[], ExecuteMsg::ConfirmDelivery { file_hash: "file".to_string(), signature })
+}
+
+fn delivery_proof(deps: &TestDeps) -> Option<Binary> {
+    query_data(deps, QueryMsg::GetDeliveryProof { file_hash: "file".to_string(), recipient: RECIPIENT.to_string() })
+}
+
+fn setup_delivered() -> TestDeps {
+    let mut deps = setup();
+    record(&mut deps, "file", RECIPIENT, &[]).unwrap();
+    exec(&mut deps, RECIPIENT, &[], ExecuteMsg::RegisterPubkey { pubkey: pubkey(&signing_key(3)) }).unwrap();
+    deps
+}
+
+#[test]
+fn recipient_signature_is_stored_as_delivery_proof() {
+    let mut deps = setup_delivered();
+    assert_eq!(delivery_proof(&deps), None);
+
+    let signature = sign(&signing_key(3), b"file");
+    confirm_delivery(&mut deps, signature.clone()).unwrap();
+    assert_eq!(delivery_proof(&deps), Some(signature));
+}
+
+#[test]
+fn invalid_delivery_signature_is_rejected() {
+    let mut deps = setup_delivered();
+    for signature in [sign(&signing_key(4), b"file"), sign(&signing_key(3), b"other"), Binary::from(vec![0u8; 3])] {
+        let err = confirm_delivery(&mut deps, signature).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSignature {}));
+    }
+    assert_eq!(delivery_proof(&deps), None);
+}
+
+fn extend(deps: &mut TestDeps, env: Env, file_hash: &str, additional_seconds: u64) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::ExtendTransfer {
+        file_hash: file_hash.to_string(),
+        recipient: RECIPIENT.to_string(),
+        additional_seconds,
+    };
+    exec_at(deps, env, SENDER, &[], msg)
+}
+
+#[test]
+fn sender_extends_a_live_transfer_within_max_ttl() {
+    let mut deps = setup();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetMaxTtl { max_ttl_seconds: Some(3600) }).unwrap();
+    record_with_ttl(&mut deps, "file", 1000).unwrap();
+
+    extend(&mut deps, env_after(500), "file", 2000).unwrap();
+    let now = mock_env().block.time.seconds();
+    assert_eq!(stored_transfer(&deps, "file", RECIPIENT).expires_at, Some(now + 3000));
+
+    let err = extend(&mut deps, env_after(500), "file", 601).unwrap_err();
+    assert!(matches!(err, ContractError::TtlTooLong { max: 3600 }));
+    let msg = ExecuteMsg::ExtendTransfer {
+        file_hash: "file".to_string(),
+        recipient: RECIPIENT.to_string(),
+        additional_seconds: 1,
+    };
+    let err = exec(&mut deps, "stranger", &[], msg).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+}
+
+#[test]
+fn extension_overflowing_the_clock_is_rejected() {
+    let mut deps = setup();
+    record_with_ttl(&mut deps, "file", 1000).unwrap();
+
+    let err = extend(&mut deps, mock_env(), "file", u64::MAX).unwrap_err();
+    assert!(matches!(err, ContractError::TransferNotExtendable {}));
+    let now = mock_env().block.time.seconds();
+    assert_eq!(stored_transfer(&deps, "file", RECIPIENT).expires_at, Some(now + 1000));
+}
+
+#[test]
+fn expired_or_acknowledged_transfers_cannot_be_extended() {
+    let mut deps = setup();
+    record_with_ttl(&mut deps, "expired", 100).unwrap();
+    let err = extend(&mut deps, env_after(100), "expired", 1000).unwrap_err();
+    assert!(matches!(err, ContractError::TransferNotExtendable {}));
+
+    record_with_ttl(&mut deps, "acknowledged", 100).unwrap();
+    acknowledge(&mut deps, "acknowledged").unwrap();
+    let err = extend(&mut deps, mock_env(), "acknowledged", 1000).unwrap_err();
+    assert!(matches!(err, ContractError::TransferNotExtendable {}));
+
+    // Transfers recorded without a TTL have nothing to extend
+    record(&mut deps, "forever", RECIPIENT, &[]).unwrap();
+    let err = extend(&mut deps, mock_env(), "forever", 1000).unwrap_err();
+    assert!(matches!(err, ContractError::TransferNotExtendable {}));
+}
+
+fn record_to_many(deps: &mut TestDeps, file_hash: &str, count: usize) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::RecordMultiRecipient {
+        file_hash: file_hash.to_string(),
+        recipients: (0..count).map(|n| format!("recipient{}", n)).collect(),
+        zk_proof: vec![],
+        proved_height: None,
+        escrow_deadline: None,
+    };
+    exec(deps, SENDER, &[], msg)
+}
+
+#[test]
+fn recipient_count_is_capped_by_max_recipients() {
+    let mut deps = setup();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetMaxRecipients { max: 3 }).unwrap();
+
+    record_to_many(&mut deps, "at", 3).unwrap();
+    let err = record_to_many(&mut deps, "above", 4).unwrap_err();
+    assert!(matches!(err, ContractError::TooManyRecipients {}));
+    assert!(load_transfer(&deps.storage, "above", "recipient0").unwrap().is_none());
+}
+
+#[test]
+fn default_cap_applies_until_changed() {
+    let mut deps = setup();
+    record_to_many(&mut deps, "at", DEFAULT_MAX_RECIPIENTS as usize).unwrap();
+    let err = record_to_many(&mut deps, "above", DEFAULT_MAX_RECIPIENTS as usize + 1).unwrap_err();
+    assert!(matches!(err, ContractError::TooManyRecipients {}));
+
+    let err = exec(&mut deps, ADMIN, &[], ExecuteMsg::SetMaxRecipients { max: 0 }).unwrap_err();
+    assert!(matches!(err, ContractError::Std(_)));
+}
+
+fn history(deps: &TestDeps, file_hash: &str) -> Vec<Amendment> {
+    query_data(deps, QueryMsg::GetTransferHistory {
+        file_hash: file_hash.to_string(),
+        recipient: RECIPIENT.to_string(),
+    })
+}
+
+#[test]
+fn extensions_accumulate_in_the_history() {
+    let mut deps = setup();
+    let now = mock_env().block.time.seconds();
+    record_with_ttl(&mut deps, "file", 100).unwrap();
+    assert!(history(&deps, "file").is_empty());
+
+    extend(&mut deps, env_after(10), "file", 50).unwrap();
+    extend(&mut deps, env_after(20), "file", 25).unwrap();
+
+    let amendment = |old: u64, new: u64, at: u64| Amendment {
+        field: "expires_at".to_string(),
+        old_value: (now + old).to_string(),
+        new_value: (now + new).to_string(),
+        timestamp: now + at,
+    };
+    assert_eq!(history(&deps, "file"), [amendment(100, 150, 10), amendment(150, 175, 20)]);
+}
+
+#[test]
+fn history_keeps_only_the_newest_amendments() {
+    let mut deps = setup();
+    record_with_ttl(&mut deps, "file", 100).unwrap();
+    for _ in 0..MAX_TRANSFER_HISTORY + 2 {
+        extend(&mut deps, mock_env(), "file", 1).unwrap();
+    }
+
+    let history = history(&deps, "file");
+    let now = mock_env().block.time.seconds();
+    assert_eq!(history.len(), MAX_TRANSFER_HISTORY);
+    assert_eq!(history[0].old_value, (now + 102).to_string());
+    assert_eq!(history.last().unwrap().new_value, (now + 100 + MAX_TRANSFER_HISTORY as u64 + 2).to_string());
+}
+
+#[test]
+fn same_file_twice_in_a_batch_is_locked() {
+    let mut deps = setup();
+    let transfers = vec![
+        batch_item("a", RECIPIENT, 0),
+        batch_item("b", RECIPIENT, 0),
+        batch_item("a", "other", 0),
+    ];
+    let err = exec(&mut deps, SENDER, &[], ExecuteMsg::BatchRecordTransfer { transfers }).unwrap_err();
+    assert!(matches!(err, ContractError::FileLocked { index: 2 }));
+}
+
+#[test]
+fn file_locks_are_released_after_the_batch() {
+    let mut deps = setup();
+    let batch = |recipient: &str| ExecuteMsg::BatchRecordTransfer {
+        transfers: vec![batch_item("a", recipient, 0), batch_item("b", recipient, 0)],
+    };
+    exec(&mut deps, SENDER, &[], batch(RECIPIENT)).unwrap();
+    assert!(!FILE_LOCKS.has(&deps.storage, "a"));
+    assert!(!FILE_LOCKS.has(&deps.storage, "b"));
+
+    // The same files can go to another recipient in a later batch or record
+    exec(&mut deps, SENDER, &[], batch("other")).unwrap();
+    record(&mut deps, "a", "third", &[]).unwrap();
+    let count: u32 = FILE_HASH_COUNTS.load(&deps.storage, "a").unwrap();
+    assert_eq!(count, 3);
+}
+
+fn block(file_hash: &str) -> ExecuteMsg {
+    ExecuteMsg::BlockFileHash { file_hash: file_hash.to_string() }
+}
+
+#[test]
+fn blocked_file_hash_cannot_be_recorded() {
+    let mut deps = setup();
+    assert!(matches!(exec(&mut deps, SENDER, &[], block("bad")), Err(ContractError::Unauthorized {})));
+    exec(&mut deps, ADMIN, &[], block("bad")).unwrap();
+
+    let err = record(&mut deps, "bad", RECIPIENT, &[]).unwrap_err();
+    assert!(matches!(err, ContractError::FileHashBlocked {}));
+    let batch = ExecuteMsg::BatchRecordTransfer {
+        transfers: vec![batch_item("good", RECIPIENT, 0), batch_item("bad", RECIPIENT, 0)],
+    };
+    assert!(matches!(exec(&mut deps, SENDER, &[], batch), Err(ContractError::FileHashBlocked {})));
+
+    // Other files are unaffected
+    record(&mut deps, "other", RECIPIENT, &[]).unwrap();
+}
+
+#[test]
+fn unblocked_file_hash_can_be_recorded_again() {
+    let mut deps = setup();
+    exec(&mut deps, ADMIN, &[], block("bad")).unwrap();
+    assert!(record(&mut deps, "bad", RECIPIENT, &[]).is_err());
+
+    let unblock = ExecuteMsg::UnblockFileHash { file_hash: "bad".to_string() };
+    exec(&mut deps, ADMIN, &[], unblock).unwrap();
+    record(&mut deps, "bad", RECIPIENT, &[]).unwrap();
+    assert_eq!(stored_transfer(&deps, "bad", RECIPIENT).sender, SENDER);
+}
+
+fn record_with_memo(deps: &mut TestDeps, file_hash: &str, memo: Option<String>) -> Result<Response, ContractError> {
+    let mut msg = record_msg(file_hash, RECIPIENT);
+    if let ExecuteMsg::RecordTransfer { memo: field, .. } = &mut msg {
+        *field = memo;
+    }
+    exec(deps, SENDER, &[], msg)
+}
+
+#[test]
+fn memo_is_stored_and_returned_in_queries() {
+    let mut deps = setup();
+    record_with_memo(&mut deps, "with", Some("invoice 42".to_string())).unwrap();
+    record_with_memo(&mut deps, "without", None).unwrap();
+
+    let latest: Option<FileTransfer> =
+        query_data(&deps, QueryMsg::GetLatestForRecipient { recipient: RECIPIENT.to_string() });
+    assert_eq!(latest.unwrap().memo, None);
+    let recent: Vec<FileTransfer> = query_data(&deps, QueryMsg::GetRecentTransfers { limit: 2 });
+    let memos: Vec<Option<&str>> = recent.iter().map(|t| t.memo.as_deref()).collect();
+    assert_eq!(memos, vec![None, Some("invoice 42")]);
+}
+
+#[test]
+fn memo_over_the_length_bound_is_rejected() {
+    let mut deps = setup();
+    record_with_memo(&mut deps, "fits", Some("m".repeat(MAX_MEMO_LEN))).unwrap();
+
+    let err = record_with_memo(&mut deps, "long", Some("m".repeat(MAX_MEMO_LEN + 1))).unwrap_err();
+    assert!(matches!(err, ContractError::MemoTooLong { max: MAX_MEMO_LEN }));
+    assert!(load_transfer(&deps.storage, "long", RECIPIENT).unwrap().is_none());
+}
+
+// Contract pruning up to `limit` expired transfers per record
+fn setup_auto_prune(limit: u32) -> TestDeps {
+    let mut deps = setup();
+    exec(&mut deps, ADMIN, &[], ExecuteMsg::SetAutoPrune { enabled: true, limit }).unwrap();
+    deps
+}
+
+fn record_after(deps: &mut TestDeps, seconds: u64, file_hash: &str, recipient: &str) -> Response {
+    exec_at(deps, env_after(seconds), SENDER, &[], record_msg(file_hash, recipient)).unwrap()
+}
+
+fn pruned(response: &Response) -> Option<&str> {
+    response.events[0]
+        .attributes
+        .iter()
+        .find(|a| a.key == "pruned")
+        .map(|a| a.value.as_str())
+}
+
+#[test]
+fn auto_prune_removes_expired_transfers_incrementally() {
+    let mut deps = setup_auto_prune(2);
+    for file_hash in ["e1", "e2", "e3", "e4", "e5"] {
+        record_with_ttl(&mut deps, file_hash, 100).unwrap();
+    }
+    let remaining = |deps: &TestDeps| -> Vec<bool> {
+        ["e1", "e2", "e3", "e4", "e5"]
+            .iter()
+            .map(|file_hash| TRANSFER_KEYS.has(&deps.storage, (file_hash, RECIPIENT)))
+            .collect()
+    };
+
+    assert_eq!(pruned(&record_after(&mut deps, 200, "n1", "other")), Some("2"));
+    assert_eq!(remaining(&deps), vec![false, false, true, true, true]);
+    assert_eq!(pruned(&record_after(&mut deps, 200, "n2", "other")), Some("2"));
+    assert_eq!(pruned(&record_after(&mut deps, 200, "n3", "other")), Some("1"));
+    assert_eq!(remaining(&deps), vec![false; 5]);
+    assert!(TRANSFERS.may_load(&deps.storage, 0).unwrap().is_none());
+    assert!(!FILE_HASH_COUNTS.has(&deps.storage, "e1"));
+    assert_eq!(pruned(&record_after(&mut deps, 200, "n4", "other")), None);
+}
+
+#[test]
+fn auto_prune_leaves_live_transfers() {
+    let mut deps = setup_auto_prune(10);
+    record_with_ttl(&mut deps, "live", 1000).unwrap();
+    record(&mut deps, "forever", RECIPIENT, &[]).unwrap();
+
+    assert_eq!(pruned(&record_after(&mut deps, 999, "n1", "other")), None);
+    assert!(TRANSFER_KEYS.has(&deps.storage, ("live", RECIPIENT)));
+    assert!(TRANSFER_KEYS.has(&deps.storage, ("forever", RECIPIENT)));
+}
+
+#[test]
+fn re_recording_a_pruned_key_keeps_the_file_count() {
+    let mut deps = setup_auto_prune(10);
+    record_with_ttl(&mut deps, "file", 100).unwrap();
+    record(&mut deps, "file", "other", &[]).unwrap();
+
+    // The expired record under this key is pruned before the duplicate check, so the
+    // new one is a fresh key and counted again
+    record_after(&mut deps, 200, "file", RECIPIENT);
+    let count: u32 = FILE_HASH_COUNTS.load(&deps.storage, "file").unwrap();
+    assert_eq!(count, 2);
+    assert_eq!(stored_transfer(&deps, "file", RECIPIENT).expires_at, None);
+}
+
+#[test]
+fn auto_prune_keeps_revoked_transfers_for_audit() {
+    let mut deps = setup_auto_prune(10);
+    record_with_ttl(&mut deps, "revoked", 100).unwrap();
+    let revoke = ExecuteMsg::RevokeTransfer {
+        file_hash: "revoked".to_string(),
+        recipient: RECIPIENT.to_string(),
+    };
+    exec(&mut deps, SENDER, &[], revoke).unwrap();
+
+    assert_eq!(pruned(&record_after(&mut deps, 200, "n1", "other")), None);
+    assert_eq!(stored_transfer(&deps, "revoked", RECIPIENT).status, TransferStatus::Revoked);
+    assert!(TRANSFER_EXPIRIES.is_empty(&deps.storage));
+}
+
+#[test]
+fn record_gas_estimate_covers_the_auto_prune_limit() {
+    let deps = setup();
+    let estimate: GasEstimate = query_data(&deps, QueryMsg::EstimateRecordGas {});
+    assert!(estimate.per_transfer >= u64::from(MAX_AUTO_PRUNE_LIMIT) * GAS_PRUNE_PER_TRANSFER);
+}
+
+fn require_prefix(deps: &mut TestDeps, prefix: Option<&str>) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::SetRequiredPrefix { prefix: prefix.map(str::to_string) };
+    exec(deps, ADMIN, &[], msg)
+}
+
+#[test]
+fn recipient_with_the_required_prefix_is_recorded() {
+    let mut deps = setup();
+    require_prefix(&mut deps, Some("sei")).unwrap();
+
+    record(&mut deps, "file", "sei1recipient", &[]).unwrap();
+    assert_eq!(stored_transfer(&deps, "file", "sei1recipient").recipient, "sei1recipient");
+}
+
+#[test]
+fn recipient_with_another_prefix_is_rejected() {
+    let mut deps = setup();
+    require_prefix(&mut deps, Some("sei")).unwrap();
+
+    for recipient in ["cosmos1recipient", "seiv1recipient", RECIPIENT] {
+        let err = record(&mut deps, "file", recipient, &[]).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidRecipientPrefix { expected } if expected == "sei"));
+    }
+
+    // Clearing the requirement accepts any valid address again
+    require_prefix(&mut deps, None).unwrap();
+    record(&mut deps, "file", "cosmos1recipient", &[]).unwrap();
+}
+
+#[test]
+fn required_prefix_must_be_lowercase_alphanumeric() {
+    let mut deps = setup();
+    assert!(require_prefix(&mut deps, Some("")).is_err());
+    assert!(require_prefix(&mut deps, Some("Sei")).is_err());
+    let msg = ExecuteMsg::SetRequiredPrefix { prefix: Some("sei".to_string()) };
+    assert!(matches!(exec(&mut deps, SENDER, &[], msg), Err(ContractError::Unauthorized {})));
+}
@@ -0,0 +1,121 @@
+// Equivalence of the JSON and MessagePack storage codecs
+use super::*;
+use compact_storage::{decode, encode, to_msgpack};
+use cosmwasm_std::to_json_vec;
+use std::time::Instant;
+
+// Transfer with every optional field populated, so each one goes through the codec
+fn full_transfer() -> FileTransfer {
+    FileTransfer {
+        file_hash: "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08".to_string(),
+        sender: SENDER.to_string(),
+        recipient: RECIPIENT.to_string(),
+        timestamp: 1_600_000_000,
+        transfer_fee: Uint128::new(12345),
+        status: TransferStatus::Acknowledged,
+        revoked_at: Some(1_600_000_100),
+        expires_at: Some(1_600_086_400),
+        metadata_commitment: Some(Binary::from([3u8; 32].to_vec())),
+        proof_hash: Binary::from([4u8; 32].to_vec()),
+        proved_height: 12340,
+        wrapped_key: Some(Binary::from(vec![5u8; 64])),
+        file_size: Some(1 << 20),
+        recipient_set_hash: Some(Binary::from([6u8; 32].to_vec())),
+        delivery_signature: Some(Binary::from(vec![7u8; 64])),
+        update_seq: 3,
+        visible_at: Some(1_600_000_050),
+        memo: Some("invoice 42".to_string()),
+        epoch: 2,
+    }
+}
+
+fn full_state() -> State {
+    State {
+        fee_splits: vec![(Addr::unchecked("dev"), 2000)],
+        min_fee: Some(Coin::new(5, "usei")),
+        pending_fee: Some(PendingFee { percentage: Uint128::new(500), effective_at: 1_600_000_000 }),
+        ..default_state(ADMIN.to_string(), Uint128::new(100), 1_571_797_419)
+    }
+}
+
+#[test]
+fn both_formats_decode_to_the_same_transfer() {
+    let transfer = full_transfer();
+    let from_json: FileTransfer = decode(&to_json_vec(&transfer).unwrap()).unwrap();
+    let from_msgpack: FileTransfer = decode(&to_msgpack(&transfer).unwrap()).unwrap();
+    assert_eq!(from_json, transfer);
+    assert_eq!(from_msgpack, transfer);
+}
+
+#[test]
+fn both_formats_decode_to_the_same_state() {
+    let state = full_state();
+    let from_json: State = decode(&to_json_vec(&state).unwrap()).unwrap();
+    let from_msgpack: State = decode(&to_msgpack(&state).unwrap()).unwrap();
+    assert_eq!(from_json, state);
+    assert_eq!(from_msgpack, state);
+}
+
+#[test]
+fn values_written_by_either_build_stay_readable() {
+    let mut deps = setup();
+    record(&mut deps, "file", RECIPIENT, &[]).unwrap();
+    let id = TRANSFER_KEYS.load(&deps.storage, ("file", RECIPIENT)).unwrap();
+    let transfer = TRANSFERS.load(&deps.storage, id).unwrap();
+
+    // Rewrite the stored entry in whichever format this build doesn't use
+    let other = if cfg!(feature = "msgpack") { to_json_vec(&transfer) } else { to_msgpack(&transfer) };
+    TRANSFERS.remove(&mut deps.storage, id);
+    deps.storage.set(&[b"\x00\x09transfers".as_slice(), &id.to_be_bytes()].concat(), &other.unwrap());
+    assert_eq!(TRANSFERS.load(&deps.storage, id).unwrap(), transfer);
+    assert_eq!(stored_transfer(&deps, "file", RECIPIENT), transfer);
+}
+
+#[test]
+fn default_build_writes_cw_storage_plus_compatible_json() {
+    let bytes = encode(&full_transfer()).unwrap();
+    assert_eq!(bytes.first() == Some(&b'{'), cfg!(not(feature = "msgpack")));
+}
+
+#[test]
+fn msgpack_is_smaller_than_json() {
+    for (json, msgpack) in [
+        (to_json_vec(&full_transfer()).unwrap(), to_msgpack(&full_transfer()).unwrap()),
+        (to_json_vec(&full_state()).unwrap(), to_msgpack(&full_state()).unwrap()),
+    ] {
+        assert!(msgpack.len() < json.len());
+    }
+}
+
+// Size and decode-time comparison; run with `cargo test codec_benchmark -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn codec_benchmark() {
+    const ROUNDS: u32 = 10_000;
+    let transfer = full_transfer();
+    let state = full_state();
+    for (name, json, msgpack) in [
+        ("FileTransfer", to_json_vec(&transfer).unwrap(), to_msgpack(&transfer).unwrap()),
+        ("State", to_json_vec(&state).unwrap(), to_msgpack(&state).unwrap()),
+    ] {
+        let time = |bytes: &[u8]| {
+            let start = Instant::now();
+            for _ in 0..ROUNDS {
+                if name == "State" {
+                    decode::<State>(bytes).unwrap();
+                } else {
+                    decode::<FileTransfer>(bytes).unwrap();
+                }
+            }
+            start.elapsed() / ROUNDS
+        };
+        println!(
+            "{}: json {} bytes, {:?}/decode; msgpack {} bytes, {:?}/decode",
+            name,
+            json.len(),
+            time(&json),
+            msgpack.len(),
+            time(&msgpack)
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use super::*;
+use cosmwasm_std::testing::MOCK_CONTRACT_ADDR;
+use cosmwasm_std::{coin, coins};
+
+// Contract holding the 10000 usei of one record, 100 of it accrued as fees
+fn funded() -> TestDeps {
+    let mut deps = setup();
+    record(&mut deps, "file", RECIPIENT, &coins(10000, "usei")).unwrap();
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, coins(10000, "usei"));
+    deps
+}
+
+fn withdraw(amount: u128, to: Option<&str>) -> ExecuteMsg {
+    ExecuteMsg::WithdrawFees {
+        amount: Uint128::new(amount),
+        to: to.map(str::to_string),
+        denom: None,
+    }
+}
+
+#[test]
+fn withdrawal_defaults_to_the_fee_recipient() {
+    let mut deps = funded();
+
+    let response = exec(&mut deps, ADMIN, &[], withdraw(60, None)).unwrap();
+    assert_eq!(sent_to(&response), vec![(ADMIN.to_string(), coins(60, "usei"))]);
+}
+
+#[test]
+fn withdrawal_to_whitelisted_destination() {
+    let mut deps = funded();
+    let add = ExecuteMsg::AddWithdrawDestination { address: "treasury".to_string() };
+    exec(&mut deps, ADMIN, &[], add).unwrap();
+
+    let response = exec(&mut deps, ADMIN, &[], withdraw(60, Some("treasury"))).unwrap();
+    assert_eq!(sent_to(&response), vec![("treasury".to_string(), coins(60, "usei"))]);
+}
+
+#[test]
+fn withdrawal_to_unlisted_destination_is_rejected() {
+    let mut deps = funded();
+    let add = ExecuteMsg::AddWithdrawDestination { address: "treasury".to_string() };
+    exec(&mut deps, ADMIN, &[], add).unwrap();
+    let remove = ExecuteMsg::RemoveWithdrawDestination { address: "treasury".to_string() };
+    exec(&mut deps, ADMIN, &[], remove).unwrap();
+
+    for to in ["stranger", "treasury"] {
+        let err = exec(&mut deps, ADMIN, &[], withdraw(60, Some(to))).unwrap_err();
+        assert!(matches!(err, ContractError::DestinationNotWhitelisted {}));
+    }
+}
+
+// Funded contract where two of admin, alice and bob must approve withdrawals
+fn two_of_three() -> TestDeps {
+    let mut deps = funded();
+    let admins = ExecuteMsg::SetAdmins {
+        admins: vec![ADMIN.to_string(), "alice".to_string(), "bob".to_string()],
+        threshold: 2,
+    };
+    exec(&mut deps, ADMIN, &[], admins).unwrap();
+    deps
+}
+
+#[test]
+fn withdrawal_below_threshold_is_only_queued() {
+    let mut deps = two_of_three();
+    let response = exec(&mut deps, "alice", &[], withdraw(60, None)).unwrap();
+    assert!(sent_to(&response).is_empty());
+    assert_eq!(ACCRUED_FEES.load(&deps.storage, "usei").unwrap(), Uint128::new(100));
+
+    let err = exec(&mut deps, "alice", &[], ExecuteMsg::ApproveOperation { id: 0 }).unwrap_err();
+    assert!(matches!(err, ContractError::AlreadyApproved {}));
+    let err = exec(&mut deps, "stranger", &[], ExecuteMsg::ApproveOperation { id: 0 }).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+}
+
+#[test]
+fn withdrawal_executes_once_threshold_is_met() {
+    let mut deps = two_of_three();
+    exec(&mut deps, "alice", &[], withdraw(60, None)).unwrap();
+
+    let response = exec(&mut deps, "bob", &[], ExecuteMsg::ApproveOperation { id: 0 }).unwrap();
+    assert_eq!(sent_to(&response), vec![(ADMIN.to_string(), coins(60, "usei"))]);
+    let err = exec(&mut deps, ADMIN, &[], ExecuteMsg::ApproveOperation { id: 0 }).unwrap_err();
+    assert!(matches!(err, ContractError::OperationNotFound {}));
+}
+
+#[test]
+fn approvals_from_removed_admins_no_longer_count() {
+    let mut deps = two_of_three();
+    exec(&mut deps, "alice", &[], withdraw(60, None)).unwrap();
+    let admins = ExecuteMsg::SetAdmins {
+        admins: vec![ADMIN.to_string(), "bob".to_string(), "carol".to_string()],
+        threshold: 2,
+    };
+    exec(&mut deps, ADMIN, &[], admins).unwrap();
+
+    // Alice's proposal approval was dropped with her, so bob's alone is not enough
+    let response = exec(&mut deps, "bob", &[], ExecuteMsg::ApproveOperation { id: 0 }).unwrap();
+    assert!(sent_to(&response).is_empty());
+    assert_eq!(event_attr(&response, "approvals").as_deref(), Some("1"));
+
+    let response = exec(&mut deps, "carol", &[], ExecuteMsg::ApproveOperation { id: 0 }).unwrap();
+    assert_eq!(sent_to(&response), vec![(ADMIN.to_string(), coins(60, "usei"))]);
+}
+
+#[test]
+fn clearing_the_admin_list_emits_none() {
+    let mut deps = two_of_three();
+    let clear = ExecuteMsg::SetAdmins { admins: vec![], threshold: 1 };
+    let response = exec(&mut deps, ADMIN, &[], clear).unwrap();
+    assert_eq!(event_attr(&response, "admins").as_deref(), Some("none"));
+}
+
+#[test]
+fn zero_withdrawal_is_rejected() {
+    let mut deps = funded();
+    let err = exec(&mut deps, ADMIN, &[], withdraw(0, None)).unwrap_err();
+    assert!(matches!(err, ContractError::ZeroAmount {}));
+    assert_eq!(ACCRUED_FEES.load(&deps.storage, "usei").unwrap(), Uint128::new(100));
+
+    let response = exec(&mut deps, ADMIN, &[], withdraw(1, None)).unwrap();
+    assert_eq!(sent_to(&response), vec![(ADMIN.to_string(), coins(1, "usei"))]);
+}
+
+#[test]
+fn withdrawal_in_a_non_fee_denom_is_rejected() {
+    let mut deps = funded();
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, vec![coin(10000, "usei"), coin(500, "uescrow")]);
+    let msg = ExecuteMsg::WithdrawFees {
+        amount: Uint128::new(10),
+        to: None,
+        denom: Some("uescrow".to_string()),
+    };
+    let err = exec(&mut deps, ADMIN, &[], msg).unwrap_err();
+    assert!(matches!(err, ContractError::UnsupportedDenom {}));
+}
+
+// 3000 usei escrowed to alice, bob and carol until 1000 seconds from now
+fn escrowed() -> TestDeps {
+    let mut deps = setup();
+    let msg = ExecuteMsg::RecordMultiRecipient {
+        file_hash: "file".to_string(),
+        recipients: vec!["alice".to_string(), "bob".to_string(), "carol".to_string()],
+        zk_proof: vec![],
+        proved_height: None,
+        escrow_deadline: Some(mock_env().block.time.seconds() + 1000),
+    };
+    exec(&mut deps, SENDER, &coins(3000, "usei"), msg).unwrap();
+    deps
+}
+
+fn reclaim_at(deps: &mut TestDeps, env: Env) -> Result<Response, ContractError> {
+    exec_at(deps, env, SENDER, &[], ExecuteMsg::ReclaimEscrow { file_hash: "file".to_string() })
+}
+
+#[test]
+fn reclaim_refunds_only_unacknowledged_recipients() {
+    let mut deps = escrowed();
+    let escrow_of = |deps: &TestDeps, recipient| ESCROWS.load(&deps.storage, ("file", recipient)).unwrap().amount;
+    let (bob, carol) = (escrow_of(&deps, "bob"), escrow_of(&deps, "carol"));
+    exec(&mut deps, "alice", &[], ExecuteMsg::AcknowledgeTransfer { file_hash: "file".to_string() }).unwrap();
+
+    let response = reclaim_at(&mut deps, env_after(1000)).unwrap();
+    assert_eq!(sent_to(&response), vec![
+        (SENDER.to_string(), coins(bob.u128(), "usei")),
+        (SENDER.to_string(), coins(carol.u128(), "usei")),
+    ]);
+    assert_eq!(event_attr(&response, "reclaimed").as_deref(), Some("2"));
+    assert_eq!(stored_transfer(&deps, "file", "alice").status, TransferStatus::Acknowledged);
+    assert!(matches!(reclaim_at(&mut deps, env_after(1000)), Err(ContractError::NothingToReclaim {})));
+}
+
+#[test]
+fn reclaim_waits_for_the_deadline() {
+    let mut deps = escrowed();
+    assert!(matches!(reclaim_at(&mut deps, env_after(999)), Err(ContractError::NothingToReclaim {})));
+}
+
+fn sweep_at(deps: &mut TestDeps, env: Env, limit: u32) -> Result<Response, ContractError> {
+    exec_at(deps, env, ADMIN, &[], ExecuteMsg::SweepAbandonedEscrow { limit })
+}
+
+#[test]
+fn sweep_waits_for_the_abandon_period_after_the_deadline() {
+    let mut deps = escrowed();
+    let accrued = ACCRUED_FEES.load(&deps.storage, "usei").unwrap();
+    let held = ESCROWED.load(&deps.storage, "usei").unwrap();
+
+    // Past the deadline alone the sender can still reclaim, so nothing is abandoned
+    let early = sweep_at(&mut deps, env_after(1000 + ESCROW_ABANDON_PERIOD - 1), 10);
+    assert!(matches!(early, Err(ContractError::NothingToReclaim {})));
+
+    let response = sweep_at(&mut deps, env_after(1000 + ESCROW_ABANDON_PERIOD), 10).unwrap();
+    assert_eq!(event_attr(&response, "swept").as_deref(), Some("3"));
+    assert!(sent_to(&response).is_empty());
+    assert_eq!(ACCRUED_FEES.load(&deps.storage, "usei").unwrap(), accrued + held);
+    assert_eq!(ESCROWED.load(&deps.storage, "usei").unwrap(), Uint128::zero());
+    assert!(matches!(reclaim_at(&mut deps, env_after(1000 + ESCROW_ABANDON_PERIOD)), Err(ContractError::NothingToReclaim {})));
+}
+
+#[test]
+fn sweep_skips_released_escrows_and_honours_the_limit() {
+    let mut deps = escrowed();
+    exec(&mut deps, "alice", &[], ExecuteMsg::AcknowledgeTransfer { file_hash: "file".to_string() }).unwrap();
+    let abandoned = env_after(1000 + ESCROW_ABANDON_PERIOD);
+
+    let response = sweep_at(&mut deps, abandoned.clone(), 1).unwrap();
+    assert_eq!(event_attr(&response, "swept").as_deref(), Some("1"));
+    assert!(ESCROWS.has(&deps.storage, ("file", "carol")));
+
+    let response = sweep_at(&mut deps, abandoned.clone(), 10).unwrap();
+    assert_eq!(event_attr(&response, "swept").as_deref(), Some("1"));
+    assert!(matches!(sweep_at(&mut deps, abandoned, 10), Err(ContractError::NothingToReclaim {})));
+}
+
+#[test]
+fn only_the_admin_sweeps() {
+    let mut deps = escrowed();
+    let msg = ExecuteMsg::SweepAbandonedEscrow { limit: 10 };
+    let err = exec_at(&mut deps, env_after(1000 + ESCROW_ABANDON_PERIOD), SENDER, &[], msg).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+}
+
+// Contract sending accrued usei to the fee recipient once 150 have accrued, holding
+// whatever the records attach
+fn auto_withdrawing() -> TestDeps {
+    let mut deps = setup();
+    let threshold = ExecuteMsg::SetAutoWithdrawThreshold {
+        denom: "usei".to_string(),
+        threshold: Uint128::new(150),
+    };
+    exec(&mut deps, ADMIN, &[], threshold).unwrap();
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, coins(20000, "usei"));
+    deps
+}
+
+#[test]
+fn accrual_below_the_threshold_stays_in_the_contract() {
+    let mut deps = auto_withdrawing();
+
+    let response = record(&mut deps, "file", RECIPIENT, &coins(10000, "usei")).unwrap();
+    assert!(sent_to(&response).is_empty());
+    assert_eq!(ACCRUED_FEES.load(&deps.storage, "usei").unwrap(), Uint128::new(100));
+}
+
+#[test]
+fn crossing_the_threshold_sends_accrued_fees_to_the_fee_recipient() {
+    let mut deps = auto_withdrawing();
+    record(&mut deps, "a", RECIPIENT, &coins(10000, "usei")).unwrap();
+
+    let response = record(&mut deps, "b", RECIPIENT, &coins(10000, "usei")).unwrap();
+    assert_eq!(sent_to(&response), vec![(ADMIN.to_string(), coins(200, "usei"))]);
+    assert_eq!(ACCRUED_FEES.load(&deps.storage, "usei").unwrap(), Uint128::zero());
+}
+
+#[test]
+fn zero_threshold_disables_auto_withdrawal() {
+    let mut deps = auto_withdrawing();
+    let disable = ExecuteMsg::SetAutoWithdrawThreshold {
+        denom: "usei".to_string(),
+        threshold: Uint128::zero(),
+    };
+    exec(&mut deps, ADMIN, &[], disable).unwrap();
+
+    record(&mut deps, "a", RECIPIENT, &coins(10000, "usei")).unwrap();
+    let response = record(&mut deps, "b", RECIPIENT, &coins(10000, "usei")).unwrap();
+    assert!(sent_to(&response).is_empty());
+    assert_eq!(ACCRUED_FEES.load(&deps.storage, "usei").unwrap(), Uint128::new(200));
+}